@@ -1,18 +1,26 @@
+// svdocgen sets `svdocgenBundledJs` to true when the libraries are copied next to this file.
+var svdocgenBundledJs = false;
+var svdocgenJsBase = document.currentScript.src.replace(/[^\/]*$/, "");
+
 document.addEventListener("load", loadWaveDrom());
 
+function libUrl(cdnUrl, fileName) {
+    return svdocgenBundledJs ? svdocgenJsBase + fileName : cdnUrl;
+}
+
 function loadWaveDrom() {
     var script = document.createElement("script");
-    script.src = "https://cdnjs.cloudflare.com/ajax/libs/wavedrom/2.6.8/skins/default.js";
+    script.src = libUrl("https://cdnjs.cloudflare.com/ajax/libs/wavedrom/2.6.8/skins/default.js", "wavedrom-skin-default.js");
     script.onload = function() {
         var script2 = document.createElement("script");
-        script2.src = "https://cdnjs.cloudflare.com/ajax/libs/wavedrom/2.6.8/wavedrom.min.js";
+        script2.src = libUrl("https://cdnjs.cloudflare.com/ajax/libs/wavedrom/2.6.8/wavedrom.min.js", "wavedrom.min.js");
         script2.onload = function() { WaveDrom.ProcessAll(); }
         document.head.appendChild(script2);
     }
     document.head.appendChild(script);
 
     var script3 = document.createElement("script");
-    script3.src = "https://cdn.jsdelivr.net/npm/mermaid/dist/mermaid.min.js";
+    script3.src = libUrl("https://cdn.jsdelivr.net/npm/mermaid@8.14.0/dist/mermaid.min.js", "mermaid.min.js");
     script3.onload = function() { mermaid.initialize({startOnLoad:true}); }
     document.head.appendChild(script3);
-}
\ No newline at end of file
+}
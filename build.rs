@@ -0,0 +1,38 @@
+//! Embed WaveDrom and Mermaid into the binary when they are in `assets/js`,
+//! see `assets/js/Makefile`.
+//!
+//! With `SVDOCGEN_BUNDLED_JS=1`, like in release builds, missing libraries
+//! fail the build; otherwise the binary is built without them and
+//! `--js-libs bundled` reports an error.
+//!
+
+const BUNDLED_JS: [&str; 3] = [
+    "assets/js/wavedrom.min.js",
+    "assets/js/wavedrom-skin-default.js",
+    "assets/js/mermaid.min.js",
+];
+
+fn main() {
+    println!("cargo:rustc-check-cfg=cfg(bundled_js)");
+    println!("cargo:rerun-if-changed=assets/js");
+    println!("cargo:rerun-if-env-changed=SVDOCGEN_BUNDLED_JS");
+
+    let required = std::env::var("SVDOCGEN_BUNDLED_JS").is_ok_and(|v| !v.is_empty() && v != "0");
+
+    let missing: Vec<&str> = BUNDLED_JS.iter()
+        .copied()
+        .filter(|path| !std::path::Path::new(path).exists())
+        .collect();
+
+    if missing.is_empty() {
+        println!("cargo:rustc-cfg=bundled_js");
+    }
+    else if required || missing.len() < BUNDLED_JS.len() {
+        panic!("bundled JavaScript libraries are missing: {}, run `make -C assets/js`",
+            missing.join(", "));
+    }
+    else {
+        println!("cargo:warning=building without bundled JavaScript libraries, \
+            `--js-libs bundled` is not available; run `make -C assets/js` to bundle them");
+    }
+}
@@ -6,6 +6,10 @@
 
 use clap::{Arg, App/*, SubCommand*/};
 
+/// Names of built-in renderers, see `mdbook::render::renderers`.
+///
+pub const FORMATS: &[&str] = &["mdbook", "html", "markdown", "json", "ipxact"];
+
 /// All configuration options and input info in one place.
 ///
 /// TODO: think to use <https://lib.rs/crates/structopt>
@@ -13,7 +17,16 @@ use clap::{Arg, App/*, SubCommand*/};
 pub struct ParsedOptions {
     pub output_dir: String,
     pub inputs: Vec<String>,
+    pub filelists: Vec<String>,
     pub includes: Vec<String>,
+    pub incdirs: Vec<String>,
+    pub defines: Vec<(String, Option<String>)>,
+    pub tops: Vec<String>,
+    pub collapse_buses: bool,
+    /// Name of output renderer, `mdbook`, `html`, `markdown`, `json`, `ipxact`.
+    pub format: String,
+    /// Where pages load WaveDrom and Mermaid from, `cdn` or `bundled`.
+    pub js_libs: String,
     pub project_name: String
 }
 
@@ -35,17 +48,76 @@ pub fn parse_args() -> ParsedOptions {
             .help("Set output directory for generated artifacts."))
         .arg(Arg::with_name("INPUT")
             .help("Set the input file or directory")
-            .required(true)
+            .required_unless("filelist")
             .multiple(true)
             .index(1))
+        .arg(Arg::with_name("filelist")
+            .short("f")
+            .long("filelist")
+            .value_name("FILE")
+            .help("Read input files, +incdir+ and +define+ from simulator-style filelist")
+            .required(false)
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1))
         .arg(Arg::with_name("include")
             .short("i")
             .long("include")
-            .help("Include path where input files and directories are located")
+            .help("Include path where input files and directories are located, also used as SV include path")
+            .required(false)
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1))
+        .arg(Arg::with_name("incdir")
+            .short("I")
+            .long("incdir")
+            .value_name("DIR")
+            .help("SV include path to search for `include files")
+            .required(false)
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1))
+        .arg(Arg::with_name("define")
+            .short("D")
+            .long("define")
+            .value_name("NAME[=VALUE]")
+            .help("Define SV preprocessor macro")
+            .required(false)
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1))
+        .arg(Arg::with_name("top")
+            .long("top")
+            .value_name("NAME")
+            .help("Top-level module of design hierarchy, detected automatically if not set")
             .required(false)
             .takes_value(true)
             .multiple(true)
             .number_of_values(1))
+        .arg(Arg::with_name("collapse-buses")
+            .long("collapse-buses")
+            .help("Draw one net per bus in module schematics instead of one per connected bit range"))
+        .arg(Arg::with_name("format")
+            .long("format")
+            .value_name("FORMAT")
+            .takes_value(true)
+            .possible_values(FORMATS)
+            .default_value("mdbook")
+            .help("Output format: mdbook, html (static pages), markdown (single file), json (design data) or ipxact (components)"))
+        .arg(Arg::with_name("emit")
+            .long("emit")
+            .value_name("KIND")
+            .takes_value(true)
+            .possible_values(&["mdbook", "json", "ipxact"])
+            .conflicts_with("format")
+            .help("Deprecated alias of --format"))
+        .arg(Arg::with_name("js-libs")
+            .long("js-libs")
+            .value_name("SOURCE")
+            .takes_value(true)
+            .possible_values(&["cdn", "bundled"])
+            .default_value("cdn")
+            .help("Load WaveDrom and Mermaid from CDN or from copies bundled with the generated pages"))
         .arg(Arg::with_name("project-name")
             .long("project-name")
             .takes_value(true)
@@ -54,19 +126,66 @@ pub fn parse_args() -> ParsedOptions {
 
     let output_dir = matches.value_of("output").unwrap_or("svdoc");
 
-    let inputs: Vec<&str> = matches.values_of("INPUT").unwrap().collect();
+    let inputs: Vec<&str> = match matches.values_of("INPUT") {
+        Some(values) => values.collect(),
+        None => Vec::new(),
+    };
+
+    let filelists: Vec<&str> = match matches.values_of("filelist") {
+        Some(values) => values.collect(),
+        None => Vec::new(),
+    };
 
     let includes: Vec<&str> = match matches.values_of("include") {
         Some(values) => values.collect(),
         None => Vec::new(),
     };
 
+    let incdirs: Vec<&str> = match matches.values_of("incdir") {
+        Some(values) => values.collect(),
+        None => Vec::new(),
+    };
+
+    let defines: Vec<&str> = match matches.values_of("define") {
+        Some(values) => values.collect(),
+        None => Vec::new(),
+    };
+
+    let tops: Vec<&str> = match matches.values_of("top") {
+        Some(values) => values.collect(),
+        None => Vec::new(),
+    };
+
     let project_name = matches.value_of("project-name").unwrap_or("");
 
+    let format = match matches.value_of("emit") {
+        Some(emit) => {
+            println!("Warning: --emit is deprecated, use --format {}", emit);
+            emit
+        }
+        None => matches.value_of("format").unwrap(),
+    };
+
     ParsedOptions {
         output_dir: String::from(output_dir),
         inputs: inputs.iter().map(|&x| String::from(x)).collect(),
+        filelists: filelists.iter().map(|&x| String::from(x)).collect(),
         includes: includes.iter().map(|&x| String::from(x)).collect(),
+        incdirs: incdirs.iter().map(|&x| String::from(x)).collect(),
+        defines: defines.iter().map(|&x| parse_define(x)).collect(),
+        tops: tops.iter().map(|&x| String::from(x)).collect(),
+        collapse_buses: matches.is_present("collapse-buses"),
+        format: String::from(format),
+        js_libs: String::from(matches.value_of("js-libs").unwrap()),
         project_name: String::from(project_name)
     }
-}
\ No newline at end of file
+}
+
+/// Split `NAME[=VALUE]` macro definition into name and optional value.
+///
+pub fn parse_define(define: &str) -> (String, Option<String>) {
+    match define.split_once('=') {
+        Some((name, value)) => (String::from(name), Some(String::from(value))),
+        None => (String::from(define), None),
+    }
+}
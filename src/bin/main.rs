@@ -1,28 +1,19 @@
 //! SvDocGen executable that reads SV files
-//! and generates documentation in mdBook format.
-//!
+//! and generates documentation in mdBook format
+//! or other format selected with `--format`.
 //!
 
-use svdocgen;
-use svdocgen::mdbook::generate as generator;
-use svdocgen::mdbook::build as builder;
 
+use svdocgen::mdbook::render;
 
-fn main() -> Result<(), i32>{
 
-    let options = svdocgen::args::parse_args();
+fn main() {
 
-    match generator::generate(&options) {
-        Err(e) => { println!("Error during generation: {}", e);
-                    return Err(2); },
-        _ => (),
-    }
+    let options = svdocgen::args::parse_args();
 
-    match builder::build(&options) {
-        Err(e) => { println!("Error while building mdBook: {}", e);
-                    return Err(3); },
-        _ => (),
+    if let Err(e) = render::render(&options, &render::renderers()) {
+        println!("Error during '{}' generation: {}", options.format, e);
+        // `Err` returned from `main` would always exit with 1.
+        std::process::exit(e.exit_code);
     }
-
-    Ok(())
 }
@@ -7,7 +7,7 @@
 //!
 
 use std::path;
-use std::path::{/*Path,*/ PathBuf};
+use std::path::{Path, PathBuf};
 
 /// File System node as path to file or directory.
 ///
@@ -19,49 +19,43 @@ pub struct FsNode {
 
 
 /// Tuple struct wrapping FsNode.
-pub struct FsNodeIter<'a>(&'a FsNode);
+pub struct FsNodeIter<'a>(pub &'a FsNode);
 
 
 impl FsNode {
 
-    pub fn push(&mut self, path: &PathBuf) {
+    pub fn push(&mut self, path: &Path) {
         let mut node: &mut FsNode = self;
         for component in path.components() {
             //println!("component {:?}", component);
-            match component {
-                path::Component::Normal(_) => {
-                    //println!("normal component {:?}", component);
-                    let name = component.as_os_str().to_string_lossy();
-                    let pos = node.children.iter().position(|child| child.name.eq(&name));
-                    node = match pos {
-                        Some(pos) => node.children.get_mut(pos).unwrap(),
-                        None => {
-                            let new_node = FsNode {name: name.to_string(), children: Vec::new()};
-                            node.children.push(new_node);
-                            node.children.last_mut().unwrap()
-                        },
-                    }
-                },
-                _ => (),
+            if is_named(&component) {
+                //println!("normal component {:?}", component);
+                let name = component.as_os_str().to_string_lossy();
+                let pos = node.children.iter().position(|child| child.name.eq(&name));
+                node = match pos {
+                    Some(pos) => node.children.get_mut(pos).unwrap(),
+                    None => {
+                        let new_node = FsNode {name: name.to_string(), children: Vec::new()};
+                        node.children.push(new_node);
+                        node.children.last_mut().unwrap()
+                    },
+                }
             }
         }
     }
 
-    pub fn exists(&self, path: &PathBuf) -> bool {
+    pub fn exists(&self, path: &Path) -> bool {
         let mut node: &FsNode = self;
         for component in path.components() {
             //println!("component {:?}", component);
-            match component {
-                path::Component::Normal(_) => {
-                    //println!("normal component {:?}", component);
-                    let name = component.as_os_str().to_string_lossy();
-                    let child = node.children.iter().find(|child| child.name.eq(&name));
-                    node = match child {
-                        Some(existing_node) => existing_node,
-                        None => return false,
-                    }
-                },
-                _ => (),
+            if is_named(&component) {
+                //println!("normal component {:?}", component);
+                let name = component.as_os_str().to_string_lossy();
+                let child = node.children.iter().find(|child| child.name.eq(&name));
+                node = match child {
+                    Some(existing_node) => existing_node,
+                    None => return false,
+                }
             }
         }
         true
@@ -78,9 +72,12 @@ impl FsNode {
         let path = parent_path;
         for child in &self.children {
             path.push(child.name.clone());
-            f(child, &path, level);
+            f(child, path, level);
             child.traverse(path, level + 1, f);
-            path.pop();
+            // Root directory `/` can't be popped.
+            if !path.pop() {
+                *path = PathBuf::new();
+            }
         }
     }
 
@@ -97,6 +94,14 @@ impl FsNode {
 
 }
 
+/// Components that are nodes, root directory and `..` are kept
+/// so paths outside of current directory stay valid.
+///
+fn is_named(component: &path::Component) -> bool {
+    matches!(component,
+        path::Component::Normal(_) | path::Component::RootDir | path::Component::ParentDir)
+}
+
 impl Clone for FsNode {
 
     fn clone(&self) -> Self {
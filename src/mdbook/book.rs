@@ -0,0 +1,167 @@
+//! In-memory book: pages generated from the design before any output is written.
+//!
+//! All paths are relative to mdBook `src` directory, like `src/rtl/top.module.top.md`,
+//! pages link to each other with relative links as mdBook expects.
+//! Renderers decide how to lay out the pages, see `render.rs`.
+//!
+
+use std::path::{Component, Path, PathBuf};
+
+use regex::Regex;
+
+/// Generated page or file.
+///
+pub struct Page {
+    /// Path relative to mdBook `src` directory.
+    pub path: String,
+    pub text: String,
+}
+
+/// Entry of table of contents, `path` is `None` for draft chapters.
+///
+pub struct Chapter {
+    /// Nesting level, top level chapters are 1.
+    pub level: usize,
+    pub title: String,
+    pub path: Option<String>,
+}
+
+/// Everything to be rendered.
+///
+pub struct Book {
+    pub title: String,
+    /// Chapters of user's markdown documentation.
+    pub user_chapters: Vec<Chapter>,
+    /// Chapters generated from SV sources.
+    pub chapters: Vec<Chapter>,
+    /// Markdown pages, both user's and generated.
+    pub pages: Vec<Page>,
+    /// Other generated files like Graphviz DOT schematics.
+    pub files: Vec<Page>,
+    /// Input files copied as is, relative to current directory,
+    /// inside the book they are under `src/`.
+    pub assets: Vec<PathBuf>,
+}
+
+impl Book {
+    pub fn new(title: &str) -> Book {
+        Book {
+            title: title.to_string(),
+            user_chapters: Vec::new(),
+            chapters: Vec::new(),
+            pages: Vec::new(),
+            files: Vec::new(),
+            assets: Vec::new(),
+        }
+    }
+
+    pub fn add_page(&mut self, path: &str, text: String) {
+        self.pages.push(Page { path: path.to_string(), text });
+    }
+
+    pub fn add_file(&mut self, path: &str, text: String) {
+        self.files.push(Page { path: path.to_string(), text });
+    }
+
+    pub fn page(&self, path: &str) -> Option<&Page> {
+        self.pages.iter().find(|page| page.path == path)
+    }
+
+    /// All chapters with pages in table of contents order.
+    ///
+    pub fn chapter_pages(&self) -> Vec<(&Chapter, &Page)> {
+        self.user_chapters.iter().chain(self.chapters.iter())
+            .filter_map(|chapter| chapter.path.as_deref()
+                .and_then(|path| self.page(path))
+                .map(|page| (chapter, page)))
+            .collect()
+    }
+
+    /// Contents of mdBook `SUMMARY.md`.
+    ///
+    pub fn summary_md(&self) -> String {
+        let mut text = String::from("# Summary\n");
+
+        text.push_str("\n- [User's Documentation]()\n");
+        for chapter in &self.user_chapters {
+            print_summary_chapter(&mut text, chapter);
+        }
+
+        text.push_str("\n---\n\n");
+
+        for chapter in &self.chapters {
+            print_summary_chapter(&mut text, chapter);
+        }
+
+        text
+    }
+}
+
+fn print_summary_chapter(text: &mut String, chapter: &Chapter) {
+    text.push_str(format!("{:indent$}- [{}]({})\n", "",
+        chapter.title, chapter.path.as_deref().unwrap_or(""),
+        indent = (chapter.level - 1) * 2).as_str());
+}
+
+/// Rewrite relative links of page, markdown `](link)` and HTML `href="link"`.
+///
+/// `rewrite` gets the link target resolved against the page directory
+/// with `.html` replaced by `.md`, and the `#fragment` if any;
+/// it returns the new link or `None` to keep the link as is.
+///
+pub fn rewrite_links<F>(page: &Page, mut rewrite: F) -> String
+    where F: FnMut(&str, Option<&str>) -> Option<String>
+{
+    let re = Regex::new(r#"(\]\(|href=")([^)"\s]*)"#).unwrap();
+
+    re.replace_all(&page.text, |caps: &regex::Captures| {
+        let link = &caps[2];
+        let new_link = match is_relative_link(link) {
+            false => None,
+            true => {
+                let (target, fragment) = match link.split_once('#') {
+                    Some((target, fragment)) => (target, Some(fragment)),
+                    None => (link, None),
+                };
+                match target.is_empty() {
+                    true => None,
+                    false => {
+                        let target = resolve_link(&page.path, target);
+                        let target = match target.strip_suffix(".html") {
+                            Some(stem) => format!("{}.md", stem),
+                            None => target,
+                        };
+                        rewrite(&target, fragment)
+                    }
+                }
+            }
+        };
+        format!("{}{}", &caps[1], new_link.as_deref().unwrap_or(link))
+    }).to_string()
+}
+
+fn is_relative_link(link: &str) -> bool {
+    !(link.is_empty() || link.starts_with('#') || link.starts_with('/') || link.contains("://")
+        || link.starts_with("mailto:"))
+}
+
+/// Path of link target relative to mdBook `src` directory,
+/// `link` is relative to page `from`.
+///
+pub fn resolve_link(from: &str, link: &str) -> String {
+    let mut path: Vec<String> = Path::new(from).parent()
+        .map(|dir| dir.components()
+            .filter_map(|c| c.as_os_str().to_str().map(|s| s.to_string()))
+            .collect())
+        .unwrap_or_default();
+
+    for c in Path::new(link).components() {
+        match c {
+            Component::ParentDir => { path.pop(); },
+            Component::CurDir => (),
+            c => path.push(c.as_os_str().to_str().unwrap_or("").to_string()),
+        }
+    }
+
+    path.join("/")
+}
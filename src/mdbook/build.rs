@@ -12,6 +12,19 @@ use mdbook::MDBook;
 const MDBOOK_BOOK_DIR: &str = "book";
 //const MDBOOK_SRC_DIR: &str = "src";
 
+/// Script that loads WaveDrom and Mermaid, added to every page.
+pub const LOADER_JS: &str = "loadwavedrom.js";
+
+/// WaveDrom and Mermaid embedded by `build.rs` if they were in `assets/js`.
+#[cfg(bundled_js)]
+const BUNDLED_JS: &[(&str, &[u8])] = &[
+    ("wavedrom.min.js", include_bytes!("../../assets/js/wavedrom.min.js")),
+    ("wavedrom-skin-default.js", include_bytes!("../../assets/js/wavedrom-skin-default.js")),
+    ("mermaid.min.js", include_bytes!("../../assets/js/mermaid.min.js")),
+];
+#[cfg(not(bundled_js))]
+const BUNDLED_JS: &[(&str, &[u8])] = &[];
+
 #[svgbobdoc::transform]
 /// Build mdBook from mdBook sources.
 ///
@@ -40,12 +53,16 @@ pub fn build(options: &args::ParsedOptions) -> Result<(),String> {
 
     let /*mut*/ md = MDBook::load(&options.output_dir).expect("Unable to load the book");
 
-    copy_assets_to_src(&Path::new(&options.output_dir)).expect("failed to copy assets");
+    write_js_loader(Path::new(&options.output_dir), &options.js_libs)?;
 
     md.build().expect("Building failed");
 
     copy_assets(&mdbook_book_dir_p).expect("failed to copy assets");
 
+    if options.js_libs == "bundled" {
+        write_bundled_js(&mdbook_book_dir_p)?;
+    }
+
     Ok(())
 }
 
@@ -63,12 +80,40 @@ fn copy_assets(book_path: &Path)-> Result<(),String> {
     Ok(())
 }
 
-fn copy_assets_to_src(book_src_path: &Path)-> Result<(),String> {
+/// Write the script that loads WaveDrom and Mermaid into `dir`,
+/// with `js_libs` `bundled` it loads them from its own directory instead of CDN.
+///
+pub fn write_js_loader(dir: &Path, js_libs: &str) -> Result<(),String> {
 
-    let asset_loadwavedrom_js = include_bytes!("../../assets/js/loadwavedrom.js");
-    fs::write(
-        book_src_path.join("loadwavedrom.js"),
-        asset_loadwavedrom_js).expect("Unable to write file");
+    let loader = include_str!("../../assets/js/loadwavedrom.js");
+    let loader = match js_libs == "bundled" {
+        true => loader.replace("var svdocgenBundledJs = false;", "var svdocgenBundledJs = true;"),
+        false => loader.to_string(),
+    };
+
+    if let Err(e) = fs::write(dir.join(LOADER_JS), loader) {
+        return Err(e.to_string());
+    }
+
+    Ok(())
+}
+
+/// WaveDrom and Mermaid are embedded, so `--js-libs bundled` can be used.
+///
+pub fn has_bundled_js() -> bool {
+    !BUNDLED_JS.is_empty()
+}
+
+/// Write embedded WaveDrom and Mermaid into `dir`.
+///
+pub fn write_bundled_js(dir: &Path) -> Result<(),String> {
+
+    for (fname, data) in BUNDLED_JS {
+        match fs::write(dir.join(fname), data) {
+            Err(e) => return Err(e.to_string()),
+            Ok(_) => println!("Created '{}'", dir.join(fname).display()),
+        }
+    }
 
     Ok(())
-}
\ No newline at end of file
+}
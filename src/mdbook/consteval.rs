@@ -0,0 +1,353 @@
+//! Evaluate simple SV constant expressions.
+//!
+//! Good enough to compute widths of types and values of enum members:
+//! integer literals, known parameters, `+ - * / % ** << >>`,
+//! comparisons, `?:`, parentheses and `$clog2`.
+//! Anything else, like `x`/`z` digits or function calls, gives `None`.
+//!
+
+use std::collections::HashMap;
+
+/// Values of parameters visible in the scope.
+///
+pub type ConstEnv = HashMap<String, i64>;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(i64),
+    Ident(String),
+    Op(String),
+    LParen,
+    RParen,
+}
+
+/// Evaluate expression, `None` if it is not a constant known here.
+///
+pub fn eval(expr: &str, env: &ConstEnv) -> Option<i64> {
+    let tokens = tokenize(expr)?;
+    let mut parser = Parser { tokens, pos: 0, env };
+    let value = parser.conditional()?;
+
+    match parser.pos == parser.tokens.len() {
+        true => Some(value),
+        false => None,
+    }
+}
+
+/// Width of range `[msb:lsb]`.
+///
+pub fn range_width(msb: &str, lsb: &str, env: &ConstEnv) -> Option<u64> {
+    let msb = eval(msb, env)?;
+    let lsb = eval(lsb, env)?;
+
+    Some(msb.abs_diff(lsb) + 1)
+}
+
+fn tokenize(expr: &str) -> Option<Vec<Token>> {
+    let chars: Vec<char> = expr.chars().collect();
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        }
+        else if c.is_ascii_digit() || c == '\'' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_' || chars[i] == '\'') {
+                // Based literal may have a space after the base, like `4'b 1010`.
+                if chars[i] == '\'' {
+                    i += 1;
+                    while i < chars.len() && chars[i].is_ascii_alphabetic() { i += 1; }
+                    while i < chars.len() && chars[i].is_whitespace() { i += 1; }
+                    continue;
+                }
+                i += 1;
+            }
+            let literal: String = chars[start..i].iter().filter(|c| !c.is_whitespace()).collect();
+            tokens.push(Token::Number(parse_number(&literal)?));
+        }
+        else if c.is_ascii_alphabetic() || c == '_' || c == '$' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_' || chars[i] == '$') {
+                i += 1;
+                // Scoped name like `Pkg::NAME`, single `:` belongs to `?:`.
+                if chars[i..].starts_with(&[':', ':']) {
+                    i += 2;
+                }
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        }
+        else if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+        }
+        else if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+        }
+        else {
+            let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            let op = match two.as_str() {
+                "**" | "<<" | ">>" | "==" | "!=" | "<=" | ">=" => two,
+                _ if "+-*/%<>?:".contains(c) => c.to_string(),
+                _ => return None,
+            };
+            i += op.len();
+            tokens.push(Token::Op(op));
+        }
+    }
+
+    Some(tokens)
+}
+
+/// Parse decimal or based literal like `8'hFF`, `'d10` or `1_000`.
+///
+pub fn parse_number(literal: &str) -> Option<i64> {
+    let literal = literal.replace('_', "");
+
+    match literal.split_once('\'') {
+        None => literal.parse().ok(),
+        Some((_size, value)) => {
+            let value = value.trim_start_matches(['s', 'S']);
+            let (base, digits) = value.split_at(value.chars().next().map_or(0, |c| c.len_utf8()));
+            let radix = match base {
+                "b" | "B" => 2,
+                "o" | "O" => 8,
+                "d" | "D" => 10,
+                "h" | "H" => 16,
+                _ => return None,
+            };
+            i64::from_str_radix(digits, radix).ok()
+        }
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    env: &'a ConstEnv,
+}
+
+impl Parser<'_> {
+
+    fn peek_op(&self) -> Option<&str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) => Some(op.as_str()),
+            _ => None,
+        }
+    }
+
+    fn conditional(&mut self) -> Option<i64> {
+        let cond = self.comparison()?;
+        if self.peek_op() != Some("?") {
+            return Some(cond);
+        }
+        self.pos += 1;
+        let if_true = self.conditional()?;
+        if self.peek_op() != Some(":") {
+            return None;
+        }
+        self.pos += 1;
+        let if_false = self.conditional()?;
+
+        Some(if cond != 0 { if_true } else { if_false })
+    }
+
+    fn comparison(&mut self) -> Option<i64> {
+        let mut value = self.shift()?;
+        while let Some(op) = self.peek_op() {
+            let op = op.to_string();
+            if !["==", "!=", "<", "<=", ">", ">="].contains(&op.as_str()) { break; }
+            self.pos += 1;
+            let rhs = self.shift()?;
+            value = match op.as_str() {
+                "==" => value == rhs,
+                "!=" => value != rhs,
+                "<" => value < rhs,
+                "<=" => value <= rhs,
+                ">" => value > rhs,
+                _ => value >= rhs,
+            } as i64;
+        }
+        Some(value)
+    }
+
+    fn shift(&mut self) -> Option<i64> {
+        let mut value = self.additive()?;
+        while let Some(op) = self.peek_op() {
+            let op = op.to_string();
+            if op != "<<" && op != ">>" { break; }
+            self.pos += 1;
+            let rhs = u32::try_from(self.additive()?).ok()?;
+            value = match op.as_str() {
+                "<<" => value.checked_shl(rhs)?,
+                _ => value.checked_shr(rhs)?,
+            };
+        }
+        Some(value)
+    }
+
+    fn additive(&mut self) -> Option<i64> {
+        let mut value = self.multiplicative()?;
+        while let Some(op) = self.peek_op() {
+            let op = op.to_string();
+            if op != "+" && op != "-" { break; }
+            self.pos += 1;
+            let rhs = self.multiplicative()?;
+            value = match op.as_str() {
+                "+" => value.checked_add(rhs)?,
+                _ => value.checked_sub(rhs)?,
+            };
+        }
+        Some(value)
+    }
+
+    fn multiplicative(&mut self) -> Option<i64> {
+        let mut value = self.power()?;
+        while let Some(op) = self.peek_op() {
+            let op = op.to_string();
+            if op != "*" && op != "/" && op != "%" { break; }
+            self.pos += 1;
+            let rhs = self.power()?;
+            value = match op.as_str() {
+                "*" => value.checked_mul(rhs)?,
+                "/" => value.checked_div(rhs)?,
+                _ => value.checked_rem(rhs)?,
+            };
+        }
+        Some(value)
+    }
+
+    fn power(&mut self) -> Option<i64> {
+        let value = self.unary()?;
+        if self.peek_op() == Some("**") {
+            self.pos += 1;
+            let rhs = u32::try_from(self.power()?).ok()?;
+            return value.checked_pow(rhs);
+        }
+        Some(value)
+    }
+
+    fn unary(&mut self) -> Option<i64> {
+        match self.peek_op() {
+            Some("-") => { self.pos += 1; self.unary()?.checked_neg() }
+            Some("+") => { self.pos += 1; self.unary() }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Option<i64> {
+        let token = self.tokens.get(self.pos)?.clone();
+        self.pos += 1;
+
+        match token {
+            Token::Number(n) => Some(n),
+            Token::LParen => {
+                let value = self.conditional()?;
+                match self.tokens.get(self.pos) {
+                    Some(Token::RParen) => { self.pos += 1; Some(value) }
+                    _ => None,
+                }
+            }
+            Token::Ident(name) if name == "$clog2" => {
+                match self.primary()? {
+                    n if n <= 1 => Some(0),
+                    n => Some(64 - (n - 1).leading_zeros() as i64),
+                }
+            }
+            // Scoped name `Pkg::NAME` is looked up as is and then unscoped.
+            Token::Ident(name) => self.env.get(&name).copied()
+                .or_else(|| name.rsplit_once("::").and_then(|(_, n)| self.env.get(n).copied())),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literals() {
+        let env = ConstEnv::new();
+
+        assert_eq!(eval("42", &env), Some(42));
+        assert_eq!(eval("1_000", &env), Some(1000));
+        assert_eq!(eval("8'hFF", &env), Some(255));
+        assert_eq!(eval("4'b 1010", &env), Some(10));
+        assert_eq!(eval("'d10", &env), Some(10));
+        assert_eq!(eval("3'o7", &env), Some(7));
+        assert_eq!(eval("8'sh7f", &env), Some(127));
+        assert_eq!(eval("4'b1x0z", &env), None);
+    }
+
+    #[test]
+    fn operators() {
+        let env = ConstEnv::from([(String::from("W"), 8), (String::from("Pkg::N"), 3)]);
+
+        assert_eq!(eval("W * 2 + 1", &env), Some(17));
+        assert_eq!(eval("(W - 1) % 3", &env), Some(1));
+        assert_eq!(eval("2 ** 3 ** 2", &env), Some(512));
+        assert_eq!(eval("-W / 2", &env), Some(-4));
+        assert_eq!(eval("Pkg::N + N", &env), None);
+        assert_eq!(eval("Pkg::N", &env), Some(3));
+        assert_eq!(eval("UNKNOWN + 1", &env), None);
+        assert_eq!(eval("W +", &env), None);
+    }
+
+    #[test]
+    fn clog2() {
+        let env = ConstEnv::from([(String::from("DEPTH"), 16)]);
+
+        assert_eq!(eval("$clog2(1)", &env), Some(0));
+        assert_eq!(eval("$clog2(2)", &env), Some(1));
+        assert_eq!(eval("$clog2(DEPTH)", &env), Some(4));
+        assert_eq!(eval("$clog2(DEPTH + 1)", &env), Some(5));
+        assert_eq!(eval("$clog2(DEPTH) - 1", &env), Some(3));
+    }
+
+    #[test]
+    fn shifts() {
+        let env = ConstEnv::new();
+
+        assert_eq!(eval("1 << 4", &env), Some(16));
+        assert_eq!(eval("256 >> 2 + 2", &env), Some(16));
+        assert_eq!(eval("(1 << 3) - 1", &env), Some(7));
+        assert_eq!(eval("1 << -1", &env), None);
+    }
+
+    #[test]
+    fn ternaries() {
+        let env = ConstEnv::from([(String::from("W"), 8), (String::from("Pkg::N"), 3)]);
+
+        assert_eq!(eval("W > 4 ? 2 : 1", &env), Some(2));
+        assert_eq!(eval("W == 4 ? 2 : 1", &env), Some(1));
+        assert_eq!(eval("W < 4 ? 1 : W >= 8 ? 3 : 2", &env), Some(3));
+        assert_eq!(eval("Pkg::N != 3 ? W:Pkg::N", &env), Some(3));
+        assert_eq!(eval("(W ? 1 : 0) + 1", &env), Some(2));
+        assert_eq!(eval("W ? 1", &env), None);
+    }
+
+    #[test]
+    fn overflow() {
+        let env = ConstEnv::new();
+
+        assert_eq!(eval("9223372036854775807 + 1", &env), None);
+        assert_eq!(eval("2 ** 64", &env), None);
+        assert_eq!(eval("1 << 64", &env), None);
+        assert_eq!(eval("99999999999999999999", &env), None);
+        assert_eq!(eval("64'hFFFFFFFFFFFFFFFF", &env), None);
+        assert_eq!(eval("1 / 0", &env), None);
+    }
+
+    #[test]
+    fn ranges() {
+        let env = ConstEnv::from([(String::from("W"), 8)]);
+
+        assert_eq!(range_width("W-1", "0", &env), Some(8));
+        assert_eq!(range_width("0", "7", &env), Some(8));
+        assert_eq!(range_width("W", "X", &env), None);
+    }
+}
@@ -0,0 +1,786 @@
+//! Parse all SV files once and keep extracted info in a design model.
+//!
+//! Every SV file is parsed by `sv_parser` exactly one time,
+//! the information needed to document modules, packages, interfaces
+//! and classes is copied out of the syntax tree into `struct Design`,
+//! and the syntax tree is dropped.
+//! All page generators consume the same `Design`.
+//!
+
+use sv_parser::parse_sv;
+use sv_parser::{Define, DefineText, Defines};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::fsnode::FsNode;
+use crate::mdbook::extract;
+
+/// Place in the source code where an entity is declared.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct Location {
+    pub file: String,
+    pub line: u32,
+}
+
+/// Direction of a port.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Input,
+    Output,
+    Inout,
+    Ref,
+}
+
+/// Declared type of a port, net or variable.
+///
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct PortType {
+    /// Net type like `wire` or `tri`.
+    pub net_type: Option<String>,
+    /// Data type as declared, including signing and packed dimensions.
+    pub data_type: Option<String>,
+    /// User defined type name, may be scoped like `MyPkg::state_t`.
+    pub type_name: Option<String>,
+    pub packed_dims: Vec<String>,
+    /// Interface name of interface port.
+    pub interface: Option<String>,
+    /// Modport of interface port.
+    pub modport: Option<String>,
+}
+
+/// Port of a module.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct Port {
+    pub name: String,
+    pub direction: Option<Direction>,
+    pub port_type: PortType,
+    pub unpacked_dims: Vec<String>,
+    pub comment: Option<String>,
+}
+
+/// Kind of a parameter declaration.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParamKind {
+    Parameter,
+    Localparam,
+    TypeParameter,
+    TypeLocalparam,
+}
+
+impl ParamKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ParamKind::Parameter => "parameter",
+            ParamKind::Localparam => "localparam",
+            ParamKind::TypeParameter => "type parameter",
+            ParamKind::TypeLocalparam => "type localparam",
+        }
+    }
+}
+
+/// Parameter of a module, interface or class.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct Parameter {
+    pub name: String,
+    pub kind: ParamKind,
+    pub data_type: Option<String>,
+    pub default: Option<String>,
+    pub comment: Option<String>,
+}
+
+/// How a port of an instance is connected.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionKind {
+    /// `.port(expr)`
+    Named,
+    /// `.port`, connected to the signal with the same name.
+    Implicit,
+    /// `.*`, all remaining ports connected to signals with the same names.
+    Wildcard,
+    /// `expr` in the order of ports.
+    Positional,
+}
+
+/// Port connection of an instance.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct Connection {
+    pub kind: ConnectionKind,
+    /// Port name, `None` for positional and wildcard connections.
+    pub port: Option<String>,
+    /// Connected expression, `None` if the port is left unconnected.
+    pub expr: Option<String>,
+    /// Signals used in the expression, like `a` and `b` in `{a, b[3:0]}`.
+    pub signals: Vec<String>,
+}
+
+/// Parameter value given to an instance, like `#(.WIDTH(8))` or `#(8)`.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct ParameterOverride {
+    /// Parameter name, `None` for positional assignment.
+    pub name: Option<String>,
+    /// Assigned value, `None` for `.WIDTH()`.
+    pub value: Option<String>,
+}
+
+/// Instance of a module inside another module.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct Instance {
+    pub module_name: String,
+    pub instance_name: String,
+    pub location: Location,
+    pub parameters: Vec<ParameterOverride>,
+    pub connections: Vec<Connection>,
+}
+
+/// Scope where a function or task is declared.
+///
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "kind", content = "name")]
+pub enum Scope {
+    CompilationUnit,
+    Package(String),
+    Module(String),
+    Interface(String),
+    Class(String),
+}
+
+impl Scope {
+    /// Name of the scope owner, `$unit` for compilation unit.
+    pub fn name(&self) -> &str {
+        match self {
+            Scope::CompilationUnit => "$unit",
+            Scope::Package(name) | Scope::Module(name) |
+            Scope::Interface(name) | Scope::Class(name) => name.as_str(),
+        }
+    }
+}
+
+/// Function or task.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubroutineKind {
+    Function,
+    Task,
+}
+
+impl SubroutineKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubroutineKind::Function => "function",
+            SubroutineKind::Task => "task",
+        }
+    }
+}
+
+/// Argument of a function or task.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct Argument {
+    pub name: String,
+    /// Direction as written, `input`, `output`, `inout`, `ref` or `const ref`.
+    pub direction: String,
+    pub data_type: Option<String>,
+    pub default: Option<String>,
+    pub unpacked_dims: Vec<String>,
+}
+
+/// Function or task declaration, prototype or DPI import.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct Subroutine {
+    pub name: String,
+    pub kind: SubroutineKind,
+    pub scope: Scope,
+    /// Package where the scope is declared, like the package of a class.
+    pub package: Option<String>,
+    pub location: Location,
+    pub comment: Option<String>,
+    /// Return type of function, `None` for task and constructor.
+    pub return_type: Option<String>,
+    pub arguments: Vec<Argument>,
+    /// `automatic` or `static` if declared explicitly.
+    pub lifetime: Option<String>,
+    /// Qualifiers like `virtual`, `pure virtual`, `extern`, `static`, `local`.
+    pub qualifiers: Vec<String>,
+}
+
+/// SV module.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct Module {
+    pub name: String,
+    pub location: Location,
+    pub comment: Option<String>,
+    pub is_ansi: bool,
+    pub parameters: Vec<Parameter>,
+    pub ports: Vec<Port>,
+    pub instances: Vec<Instance>,
+    pub state_machines: Vec<StateMachine>,
+    /// Import items visible in the scope, including compilation unit imports.
+    pub imports: Vec<String>,
+}
+
+/// Transition of state machine, from `case` item to value assigned in it.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct StateTransition {
+    pub from: String,
+    pub to: String,
+    /// Conditions of `if` and `?:` inside the `case` item, `None` if unconditional.
+    pub condition: Option<String>,
+}
+
+/// State machine found as `case (state)` assigning the next state.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct StateMachine {
+    /// Variable holding current state.
+    pub state_var: String,
+    /// Variable assigned in `case` items, if it is not the state variable itself.
+    pub next_var: Option<String>,
+    /// Declared type of state variable, like `state_t`.
+    pub state_type: Option<String>,
+    /// States in order of `case` items, or enum members if type is known.
+    pub states: Vec<String>,
+    pub reset_state: Option<String>,
+    pub reset_condition: Option<String>,
+    pub transitions: Vec<StateTransition>,
+    /// Where the `case` statement is.
+    pub location: Location,
+}
+
+/// Kind of type declared with `typedef`.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TypedefKind {
+    Enum,
+    Struct,
+    Union,
+    /// Another name for some type, like `typedef logic [7:0] byte_t`.
+    Alias,
+    /// Forward declaration, like `typedef class C`.
+    Forward,
+}
+
+/// Member of enum, or field of struct or union.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct TypeMember {
+    pub name: String,
+    /// Field type, `None` for enum member.
+    pub data_type: Option<String>,
+    /// Enum member value, as written or computed from the previous one.
+    pub value: Option<String>,
+    /// Enum member value as sized binary literal, like `2'b01`.
+    pub encoding: Option<String>,
+    /// Width in bits, if it can be computed.
+    pub width: Option<u64>,
+    /// Offset of the least significant bit in packed struct or union.
+    pub offset: Option<u64>,
+    pub comment: Option<String>,
+}
+
+/// Type declared with `typedef`.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct Typedef {
+    pub name: String,
+    pub kind: TypedefKind,
+    /// Declared type, like `enum bit [1:0]` or `struct packed`.
+    pub data_type: String,
+    pub packed: bool,
+    /// Width in bits of packed type, if it can be computed.
+    pub width: Option<u64>,
+    pub members: Vec<TypeMember>,
+    pub location: Location,
+    pub comment: Option<String>,
+}
+
+/// SV package.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct Package {
+    pub name: String,
+    pub location: Location,
+    pub comment: Option<String>,
+    pub parameters: Vec<Parameter>,
+    pub typedefs: Vec<Typedef>,
+    /// Import items, like `OtherPkg::*` or `OtherPkg::name`.
+    pub imports: Vec<String>,
+    /// Export items, like `*::*` or `OtherPkg::name`.
+    pub exports: Vec<String>,
+}
+
+/// Net or variable declared inside interface.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct Signal {
+    pub name: String,
+    pub signal_type: PortType,
+    pub unpacked_dims: Vec<String>,
+    /// Width in bits of one element, if it can be computed.
+    pub width: Option<u64>,
+    pub comment: Option<String>,
+}
+
+/// Item of modport, like `input paddr` or `import read`.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct ModportPort {
+    pub name: String,
+    /// `input`, `output`, `inout`, `ref`, `import`, `export` or `clocking`.
+    pub direction: String,
+}
+
+/// Modport of interface.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct Modport {
+    pub name: String,
+    pub ports: Vec<ModportPort>,
+    pub comment: Option<String>,
+}
+
+/// Signal of clocking block.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct ClockingSignal {
+    pub name: String,
+    /// `input`, `output`, `input output` or `inout`.
+    pub direction: String,
+    /// Skew as written, like `#1step` or `negedge #2`.
+    pub skew: Option<String>,
+}
+
+/// Clocking block.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct ClockingBlock {
+    /// `None` for unnamed default clocking block.
+    pub name: Option<String>,
+    pub is_default: bool,
+    /// Clocking event, like `@(posedge clk)`.
+    pub event: String,
+    /// Default skews, like `input #1step output #0`.
+    pub default_skew: Option<String>,
+    pub signals: Vec<ClockingSignal>,
+    pub comment: Option<String>,
+}
+
+/// SV interface.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct Interface {
+    pub name: String,
+    pub location: Location,
+    pub comment: Option<String>,
+    pub parameters: Vec<Parameter>,
+    pub ports: Vec<Port>,
+    pub signals: Vec<Signal>,
+    pub modports: Vec<Modport>,
+    pub clocking_blocks: Vec<ClockingBlock>,
+    /// Import items visible in the scope, including compilation unit imports.
+    pub imports: Vec<String>,
+}
+
+/// Property (data member) of class.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct Property {
+    pub name: String,
+    pub data_type: Option<String>,
+    pub unpacked_dims: Vec<String>,
+    /// `rand`, `randc`, `local`, `protected`, `static`, `const`.
+    pub qualifiers: Vec<String>,
+    /// Value of `const` property.
+    pub value: Option<String>,
+    pub location: Location,
+    pub comment: Option<String>,
+}
+
+/// Constraint block or prototype of class.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct Constraint {
+    pub name: String,
+    /// `static`, `extern` or `pure`.
+    pub qualifiers: Vec<String>,
+    /// Text of constraint block, `None` for prototype.
+    pub body: Option<String>,
+    pub comment: Option<String>,
+}
+
+/// Covergroup declared in class.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct Covergroup {
+    pub name: String,
+    /// Sampling event, like `@(posedge clk)`.
+    pub event: Option<String>,
+    /// Names of labeled coverpoints and crosses.
+    pub items: Vec<String>,
+    pub comment: Option<String>,
+}
+
+/// SV class.
+///
+#[derive(Clone, Debug, Serialize)]
+pub struct Class {
+    pub name: String,
+    pub location: Location,
+    pub comment: Option<String>,
+    pub parameters: Vec<Parameter>,
+    pub is_virtual: bool,
+    /// Base class as written, like `Base #(T)`.
+    pub extends: Option<String>,
+    /// Name of base class without package and parameters.
+    pub base_name: Option<String>,
+    /// Implemented interface classes as written.
+    pub implements: Vec<String>,
+    pub properties: Vec<Property>,
+    pub constraints: Vec<Constraint>,
+    pub covergroups: Vec<Covergroup>,
+    /// Import items visible in the scope, including compilation unit imports.
+    pub imports: Vec<String>,
+}
+
+/// Everything extracted from the SV sources, in file traversal order.
+///
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Design {
+    pub modules: Vec<Module>,
+    pub packages: Vec<Package>,
+    pub interfaces: Vec<Interface>,
+    pub classes: Vec<Class>,
+    pub subroutines: Vec<Subroutine>,
+}
+
+/// Parse all SV files and build the design model.
+///
+/// `incdirs` are searched for `` `include `` files,
+/// `defines` are predefined macros as NAME and optional VALUE.
+/// Files that fail to parse are reported and skipped.
+///
+pub fn parse_sources(
+    sv_files: &FsNode,
+    incdirs: &[String],
+    defines: &[(String, Option<String>)]
+) -> Result<Design,String>
+{
+    let mut design = Design::default();
+
+    let defines = make_defines(defines);
+
+    let mut/*env*/ parse_files = |_node: &FsNode, path: &PathBuf, _level: usize| {
+        if path.is_file() {
+            if let Some(path_str) = path.to_str() {
+                parse_file(&mut design, path_str, incdirs, &defines);
+            }
+        }
+    };
+
+    sv_files.traverse_top(&mut parse_files);
+
+    resolve_interface_ports(&mut design);
+    merge_extern_methods(&mut design);
+
+    Ok(design)
+}
+
+/// Tell interface ports from ports of user defined type.
+///
+/// The parser can't tell them apart without modport: `ApbIf bus` may be
+/// parsed as port of type `ApbIf`, and `state_t s` as interface port.
+/// Known interfaces make interface ports, known typedefs, classes and
+/// type parameters make typed ports, which inherit the previous direction.
+///
+fn resolve_interface_ports(design: &mut Design) {
+    let iface_names: Vec<String> = design.interfaces.iter().map(|i| i.name.clone()).collect();
+    let type_names: Vec<String> = design.packages.iter()
+        .flat_map(|pkg| pkg.typedefs.iter().map(|t| t.name.clone()))
+        .chain(design.classes.iter().map(|c| c.name.clone()))
+        .collect();
+
+    let scopes = design.modules.iter_mut().map(|m| (&m.parameters, &mut m.ports))
+        .chain(design.interfaces.iter_mut().map(|i| (&i.parameters, &mut i.ports)));
+    for (parameters, ports) in scopes {
+        let type_params: Vec<String> = parameters.iter()
+            .filter(|p| matches!(p.kind, ParamKind::TypeParameter | ParamKind::TypeLocalparam))
+            .map(|p| p.name.clone())
+            .collect();
+        resolve_port_types(ports, &iface_names, &[&type_names, &type_params]);
+    }
+}
+
+fn resolve_port_types(ports: &mut [Port], iface_names: &[String], type_names: &[&Vec<String>]) {
+    for i in 0..ports.len() {
+        let prev_direction = match i {
+            0 => None,
+            _ => ports[i - 1].direction,
+        };
+        let port = &mut ports[i];
+        let port_type = &mut port.port_type;
+
+        match (&port_type.interface, &port_type.type_name) {
+            (Some(name), _) if port_type.modport.is_none() && !iface_names.contains(name)
+                && type_names.iter().any(|names| names.contains(name)) =>
+            {
+                port_type.data_type = Some(name.clone());
+                port_type.type_name = Some(name.clone());
+                port_type.interface = None;
+                port.direction = port.direction.or(prev_direction);
+            }
+            (None, Some(type_name)) if port_type.packed_dims.is_empty() && iface_names.contains(type_name) => {
+                port_type.interface = Some(type_name.clone());
+                port_type.data_type = None;
+                port_type.type_name = None;
+                port.direction = None;
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Merge out-of-block method definitions `function C::f()` into
+/// `extern` prototypes declared in the class.
+///
+/// Methods are matched by package and class, as classes
+/// of the same name may be declared in different packages.
+///
+/// The prototype is kept, the definition gives its comment
+/// if the prototype has none.
+///
+fn merge_extern_methods(design: &mut Design) {
+    let mut merged: Vec<Subroutine> = Vec::new();
+
+    for mut sub in design.subroutines.drain(..) {
+        let is_extern = sub.qualifiers.iter().any(|q| q == "extern");
+        let other = merged.iter().position(|m|
+            m.scope == sub.scope && m.package == sub.package && m.name == sub.name
+                && matches!(m.scope, Scope::Class(_)));
+
+        match other {
+            Some(i) if is_extern => {
+                let other = merged.remove(i);
+                sub.comment = sub.comment.or(other.comment);
+                merged.push(sub);
+            }
+            Some(i) => {
+                let other = &mut merged[i];
+                other.comment = other.comment.take().or(sub.comment);
+            }
+            None => merged.push(sub),
+        }
+    }
+
+    design.subroutines = merged;
+}
+
+/// Convert NAME[=VALUE] pairs to `sv_parser` predefined macros.
+///
+pub fn make_defines(defines: &[(String, Option<String>)]) -> Defines {
+    let mut sv_defines: Defines = HashMap::new();
+
+    for (name, value) in defines {
+        let text = value.as_ref().map(|value| DefineText::new(value.clone(), None));
+        sv_defines.insert(name.clone(), Some(Define::new(name.clone(), Vec::new(), text)));
+    }
+
+    sv_defines
+}
+
+/// Parse one SV file and add its entities to the design.
+///
+pub fn parse_file(
+    design: &mut Design,
+    file_path: &str,
+    incdirs: &[String],
+    defines: &Defines
+)
+{
+    // The list of include paths, the directory of the file is searched last.
+    let mut includes: Vec<PathBuf> = incdirs.iter().map(PathBuf::from).collect();
+    if let Some(dir) = Path::new(file_path).parent() {
+        includes.push(dir.to_path_buf());
+    }
+
+    let result = parse_sv(file_path, defines, &includes, false, true);
+
+    let syntax_tree = match result {
+        Ok((syntax_tree, _)) => syntax_tree,
+        Err(e) => { println!("parsing of '{}' failed: {}\n", file_path, e);
+                    return; },
+    };
+
+    extract::extract_entities(design, &syntax_tree, file_path);
+}
+
+/// Design of `source` in `test.sv`, with the same post-processing as `parse_sources`.
+///
+#[cfg(test)]
+pub(crate) fn parse_str(source: &str) -> Design {
+    let (syntax_tree, _) = sv_parser::parse_sv_str(source, PathBuf::from("test.sv"),
+        &HashMap::new(), &[] as &[PathBuf], false, false).unwrap();
+    let mut design = Design::default();
+    extract::extract_entities(&mut design, &syntax_tree, "test.sv");
+    resolve_interface_ports(&mut design);
+    merge_extern_methods(&mut design);
+
+    design
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn include_and_defines() {
+        let dir = std::env::temp_dir().join(format!("svdocgen-design-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("inc")).unwrap();
+        std::fs::write(dir.join("inc/defs.svh"), "// Definitions\n`define WIDTH 8\n\n").unwrap();
+        let top = dir.join("top.sv");
+        std::fs::write(&top, "\
+`include \"defs.svh\"
+`ifdef FAST
+module fast;
+endmodule
+`else
+module slow;
+endmodule
+`endif
+module top;
+    child #(.W(`WIDTH)) u_child();
+endmodule
+package P;
+    function void f(); endfunction
+    class C;
+        int x;
+    endclass
+endpackage
+module child #(parameter W = `DEPTH);
+endmodule
+").unwrap();
+
+        let mut design = Design::default();
+        let incdirs = [dir.join("inc").to_string_lossy().to_string()];
+        let defines = make_defines(&[(String::from("FAST"), None), (String::from("DEPTH"), Some(String::from("16")))]);
+        parse_file(&mut design, top.to_str().unwrap(), &incdirs, &defines);
+
+        let lines: Vec<(&str, u32)> = design.modules.iter().map(|m| (m.name.as_str(), m.location.line)).collect();
+        assert_eq!(lines, [("fast", 3), ("top", 9), ("child", 18)]);
+        assert_eq!(design.modules[1].instances[0].location.line, 10);
+        assert_eq!(design.modules[1].instances[0].parameters[0].value.as_deref(), Some("8"));
+        assert_eq!(design.modules[2].parameters[0].default.as_deref(), Some("16"));
+        assert_eq!(design.packages[0].location.line, 12);
+        assert_eq!(design.subroutines[0].location.line, 13);
+        assert_eq!(design.classes[0].location.line, 14);
+        assert_eq!(design.classes[0].properties[0].location.line, 15);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn interface_or_typed_ports() {
+        let design = parse_str("
+            package P;
+                typedef logic [1:0] state_t;
+            endpackage
+            interface ApbIf;
+            endinterface
+            class Txn;
+            endclass
+            module m #(parameter type T = int) (
+                input logic clk,
+                state_t s,
+                Txn t,
+                T v,
+                output logic y,
+                ApbIf bus,
+                Unknown x,
+                ApbIf.slave sb
+            );
+            endmodule");
+
+        let ports = design.modules[0].ports.iter()
+            .map(|p| (p.name.as_str(), p.direction, p.port_type.type_name.as_deref(), p.port_type.interface.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(ports, [
+            ("clk", Some(Direction::Input), None, None),
+            ("s", Some(Direction::Input), Some("state_t"), None),
+            ("t", Some(Direction::Input), Some("Txn"), None),
+            ("v", Some(Direction::Input), Some("T"), None),
+            ("y", Some(Direction::Output), None, None),
+            ("bus", None, None, Some("ApbIf")),
+            ("x", None, None, Some("Unknown")),
+            ("sb", None, None, Some("ApbIf")),
+        ]);
+        assert_eq!(design.modules[0].ports[1].port_type.data_type.as_deref(), Some("state_t"));
+    }
+
+    #[test]
+    fn interface_ports_of_interfaces() {
+        let design = parse_str("
+            package P;
+                typedef logic [1:0] state_t;
+            endpackage
+            interface ApbIf;
+            endinterface
+            interface BridgeIf(input logic clk, state_t s, ApbIf bus);
+            endinterface");
+
+        let ports = design.interfaces[1].ports.iter()
+            .map(|p| (p.name.as_str(), p.direction, p.port_type.type_name.as_deref(), p.port_type.interface.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(ports, [
+            ("clk", Some(Direction::Input), None, None),
+            ("s", Some(Direction::Input), Some("state_t"), None),
+            ("bus", None, None, Some("ApbIf")),
+        ]);
+    }
+
+    #[test]
+    fn extern_methods_by_package() {
+        let design = parse_str("
+            package A;
+                class C;
+                    extern function void f();
+                endclass
+                // A f
+                function void C::f(); endfunction
+            endpackage
+            package B;
+                class C;
+                    extern function void f();
+                    function void g(); endfunction
+                endclass
+                // B f
+                function void C::f(); endfunction
+            endpackage");
+
+        let methods: Vec<(Option<&str>, &str, Option<&str>)> = design.subroutines.iter()
+            .map(|s| (s.package.as_deref(), s.name.as_str(), s.comment.as_deref()))
+            .collect();
+        assert_eq!(methods, [
+            (Some("A"), "f", Some("A f")),
+            (Some("B"), "f", Some("B f")),
+            (Some("B"), "g", None),
+        ]);
+        assert!(design.subroutines.iter().all(|s| s.scope == Scope::Class(String::from("C"))));
+    }
+}
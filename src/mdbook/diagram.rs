@@ -0,0 +1,181 @@
+//! Render diagram blocks of markdown pages as inline SVG at build time.
+//!
+//! ```` ```svgbob ```` blocks are drawn with svgbob,
+//! ```` ```wavedrom ```` blocks and `<script type="WaveDrom">` elements
+//! with `wavedrom.rs`, so pages show them without JavaScript
+//! and in every output format. Other code blocks are not touched,
+//! blocks that can't be rendered are kept as they are.
+//!
+
+use crate::mdbook::wavedrom;
+
+/// Page text with diagram blocks replaced by SVG.
+///
+pub fn render_diagrams(text: &str) -> String {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim_start();
+
+        if let Some((fence, info)) = fence_start(trimmed) {
+            let end = (i + 1..lines.len())
+                .find(|&j| is_fence_end(lines[j], fence))
+                .unwrap_or(lines.len());
+            let body = lines[(i + 1).min(end)..end].concat();
+
+            let svg = match (info, end < lines.len()) {
+                ("svgbob", true) => Some(svgbob_svg(&body)),
+                ("wavedrom", true) => wavedrom_svg(&body),
+                _ => None,
+            };
+            match svg {
+                Some(svg) => push_svg(&mut out, info, &svg),
+                None => for line in &lines[i..(end + 1).min(lines.len())] {
+                    out.push_str(line);
+                },
+            }
+
+            i = end + 1;
+            continue;
+        }
+
+        if trimmed.to_lowercase().starts_with("<script type=\"wavedrom\">") {
+            if let Some(end) = (i..lines.len()).find(|&j| lines[j].contains("</script>")) {
+                let source = lines[i..=end].concat();
+                if let Some((body, rest)) = script_body(&source) {
+                    if let Some(svg) = wavedrom_svg(body) {
+                        push_svg(&mut out, "wavedrom", &svg);
+                        out.push_str(rest.trim_start_matches([' ', '\t']));
+                        i = end + 1;
+                        continue;
+                    }
+                }
+            }
+        }
+
+        out.push_str(line);
+        i += 1;
+    }
+
+    out
+}
+
+/// Fence and info word of code block start like ```` ```svgbob ````.
+///
+fn fence_start(line: &str) -> Option<(&str, &str)> {
+    let fence_char = line.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let fence_len = line.chars().take_while(|&c| c == fence_char).count();
+    if fence_len < 3 {
+        return None;
+    }
+    let info = line[fence_len..].split_whitespace().next().unwrap_or("");
+
+    Some((&line[..fence_len], info))
+}
+
+fn is_fence_end(line: &str, fence: &str) -> bool {
+    let line = line.trim();
+    let fence_char = fence.chars().next().unwrap();
+
+    line.len() >= fence.len() && line.chars().all(|c| c == fence_char)
+}
+
+/// Body of `<script>` element and the text following it.
+///
+fn script_body(source: &str) -> Option<(&str, &str)> {
+    let start = source.find('>')? + 1;
+    let stop = source.rfind("</script>")?;
+    let body = source.get(start..stop)?;
+
+    Some((body, &source[stop + "</script>".len()..]))
+}
+
+fn svgbob_svg(source: &str) -> String {
+    svgbob::to_svg(source).to_string()
+}
+
+fn wavedrom_svg(source: &str) -> Option<String> {
+    match wavedrom::to_svg(source) {
+        Ok(svg) => svg,
+        Err(e) => {
+            println!("Warning: can't render WaveDrom diagram: {}", e);
+            None
+        }
+    }
+}
+
+/// Append SVG as one HTML block, blank lines would end the block in markdown.
+///
+fn push_svg(out: &mut String, kind: &str, svg: &str) {
+    if !out.is_empty() && !out.ends_with("\n\n") {
+        out.push('\n');
+    }
+    out.push_str(format!("<div class=\"{}\">\n", kind).as_str());
+    for line in svg.lines().filter(|l| !l.trim().is_empty()) {
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.push_str("</div>\n\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WAVE: &str = "{ signal: [{ name: 'clk', wave: 'p..' }] }\n";
+
+    #[test]
+    fn fences() {
+        let page = format!("# Title\n```wavedrom\n{}```\ntext\n", WAVE);
+        let out = render_diagrams(&page);
+        assert!(out.starts_with("# Title\n\n<div class=\"wavedrom\">\n<svg "));
+        assert!(out.ends_with("</svg>\n</div>\n\ntext\n"));
+        assert!(!out.contains("```"));
+
+        let out = render_diagrams(&format!("~~~wavedrom\n{}~~~\n", WAVE));
+        assert!(out.starts_with("<div class=\"wavedrom\">\n<svg "));
+
+        let out = render_diagrams("```svgbob\n+--+\n|  |\n+--+\n```\n");
+        assert!(out.starts_with("<div class=\"svgbob\">\n<svg "));
+        assert!(!out.contains("\n\n<"));
+    }
+
+    #[test]
+    fn fences_kept() {
+        let nested = format!("````markdown\n```wavedrom\n{}```\n````\n", WAVE);
+        assert_eq!(render_diagrams(&nested), nested);
+
+        let unterminated = format!("```wavedrom\n{}", WAVE);
+        assert_eq!(render_diagrams(&unterminated), unterminated);
+
+        let other = "```systemverilog\nmodule m; endmodule\n```\n";
+        assert_eq!(render_diagrams(other), other);
+
+        let bad = "```wavedrom\n{ signal: [\n```\n";
+        assert_eq!(render_diagrams(bad), bad);
+
+        let unsupported = "```wavedrom\n{ assign: [] }\n```\n";
+        assert_eq!(render_diagrams(unsupported), unsupported);
+    }
+
+    #[test]
+    fn scripts() {
+        let page = "before\n<script type=\"WaveDrom\">\n{ signal: [\n  { name: 'clk', wave: 'p..' },\n]}\n</script> after\n";
+        let out = render_diagrams(page);
+        assert!(out.starts_with("before\n\n<div class=\"wavedrom\">\n<svg "));
+        assert!(out.ends_with("</svg>\n</div>\n\nafter\n"));
+
+        let inline = format!("<script type=\"wavedrom\">{}</script>\n", WAVE.trim());
+        assert!(render_diagrams(&inline).starts_with("<div class=\"wavedrom\">"));
+
+        let bad = "<script type=\"WaveDrom\">\n{ signal: \n</script>\n";
+        assert_eq!(render_diagrams(bad), bad);
+
+        let unterminated = "<script type=\"WaveDrom\">\n{ signal: [] }\n";
+        assert_eq!(render_diagrams(unterminated), unterminated);
+    }
+}
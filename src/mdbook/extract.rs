@@ -0,0 +1,1719 @@
+//! Extract design model entities from SV syntax tree.
+//!
+//!
+//!
+
+use sv_parser::{unwrap_node, unwrap_locate, Locate, NodeEvent, RefNode, SyntaxTree};
+use sv_parser::{AnsiPortDeclaration, InterfacePortHeader, PortDirection, PortExpression};
+use sv_parser::{PortDeclaration, InputDeclaration, OutputDeclaration};
+use sv_parser::{ClassMethod, DpiImportExport, FunctionBodyDeclaration, TaskBodyDeclaration};
+use sv_parser::{FunctionDeclaration, TaskDeclaration, FunctionPrototype, TaskPrototype};
+use sv_parser::{ClassConstructorDeclaration, InterfaceIdentifierOrClassScope, MethodPrototype};
+use sv_parser::{InterfaceDeclaration, ModportPortsDeclaration, ModportSimplePort, ModportTfPort};
+use sv_parser::{ClockingItem, ClockingDirection};
+use sv_parser::{ClassDeclaration, ClassItem, ClassProperty, ClassConstraint, DataDeclaration};
+use sv_parser::{HierarchicalInstance, ListOfPortConnections, NamedPortConnection, Expression};
+use sv_parser::{ParameterValueAssignment, ListOfParameterAssignments};
+use std::collections::HashMap;
+
+use crate::mdbook::design::*;
+use crate::mdbook::fsm;
+use crate::mdbook::svpar;
+use crate::mdbook::typedef;
+
+/// Find modules, packages, interfaces and classes in syntax tree
+/// and add them to the design.
+///
+pub fn extract_entities(
+    design: &mut Design,
+    syntax_tree: &SyntaxTree,
+    file_path: &str
+)
+{
+    let source = &svpar::SourceFile::new(file_path);
+    // All comments of the file, declaration comments are outside of declarations.
+    let mut file_comments: Option<svpar::Comments> = None;
+    // Packages imported into compilation unit scope.
+    let mut unit_imports: Vec<String> = Vec::new();
+    // Package of classes declared in packages, by offset of class identifier.
+    let mut class_packages: HashMap<usize, String> = HashMap::new();
+    // &SyntaxTree is iterable
+    for node in syntax_tree {
+        // The type of each node is RefNode
+        match node {
+            RefNode::ModuleDeclarationNonansi(x) => {
+                // unwrap_node! gets the nearest ModuleIdentifier from x
+                let id = unwrap_node!(x, ModuleIdentifier).unwrap();
+                let mut module = extract_module(syntax_tree, source, id, false,
+                    &node, file_comments.as_ref());
+                module.imports = visible_imports(syntax_tree, &node, &unit_imports);
+                extract_subroutines(design, syntax_tree, source, &node,
+                    Scope::Module(module.name.clone()), None);
+                design.modules.push(module);
+            }
+            RefNode::ModuleDeclarationAnsi(x) => {
+                let id = unwrap_node!(x, ModuleIdentifier).unwrap();
+                let mut module = extract_module(syntax_tree, source, id, true,
+                    &node, file_comments.as_ref());
+                module.imports = visible_imports(syntax_tree, &node, &unit_imports);
+                extract_subroutines(design, syntax_tree, source, &node,
+                    Scope::Module(module.name.clone()), None);
+                design.modules.push(module);
+            }
+            RefNode::PackageDeclaration(x) => {
+                let id = unwrap_node!(x, PackageIdentifier).unwrap();
+                let (name, location) = get_name_and_location(syntax_tree, source, id);
+                let comments = svpar::Comments::new(syntax_tree, &node);
+                let parameters = extract_parameters(syntax_tree, &node, &comments);
+                let (imports, exports) = extract_imports_exports(syntax_tree, &node);
+
+                let mut env = make_type_env(design, &[&unit_imports, &imports]);
+                env.add_parameters(&parameters);
+
+                for class in node.clone().into_iter() {
+                    if let RefNode::ClassDeclaration(class) = class {
+                        if let Some(class_id) = svpar::get_identifier(RefNode::from(&class.nodes.3)) {
+                            class_packages.insert(class_id.offset, name.clone());
+                        }
+                    }
+                }
+
+                extract_subroutines(design, syntax_tree, source, &node,
+                    Scope::Package(name.clone()), Some(&name));
+                design.packages.push(Package {
+                    name, location, comment: get_comment(file_comments.as_ref(), &node),
+                    typedefs: typedef::extract_typedefs(syntax_tree, source, &node, &comments, &mut env),
+                    parameters,
+                    imports,
+                    exports,
+                });
+            }
+            RefNode::InterfaceDeclaration(x) => {
+                let id = unwrap_node!(x, InterfaceIdentifier).unwrap();
+                let (name, location) = get_name_and_location(syntax_tree, source, id);
+                let comments = svpar::Comments::new(syntax_tree, &node);
+                let parameters = extract_parameters(syntax_tree, &node, &comments);
+                let ports = match x {
+                    InterfaceDeclaration::Ansi(_) | InterfaceDeclaration::ExternAnsi(_) =>
+                        extract_ansi_ports(syntax_tree, &node, &comments),
+                    _ => extract_nonansi_ports(syntax_tree, &node, &comments),
+                };
+
+                let imports = visible_imports(syntax_tree, &node, &unit_imports);
+                let mut env = make_type_env(design, &[&imports]);
+                env.add_parameters(&parameters);
+
+                let signals = extract_signals(syntax_tree, &node, &comments, &ports, &env);
+
+                extract_subroutines(design, syntax_tree, source, &node,
+                    Scope::Interface(name.clone()), None);
+                design.interfaces.push(Interface {
+                    name, location, comment: get_comment(file_comments.as_ref(), &node),
+                    parameters,
+                    ports,
+                    signals,
+                    modports: extract_modports(syntax_tree, &node, &comments),
+                    clocking_blocks: extract_clocking_blocks(syntax_tree, &node, &comments),
+                    imports,
+                });
+            }
+            RefNode::ClassDeclaration(x) => {
+                let id = unwrap_node!(x, ClassIdentifier).unwrap();
+                let package = svpar::get_identifier(id.clone())
+                    .and_then(|id| class_packages.get(&id.offset))
+                    .cloned();
+                let (name, location) = get_name_and_location(syntax_tree, source, id);
+                let comments = svpar::Comments::new(syntax_tree, &node);
+                extract_subroutines(design, syntax_tree, source, &node,
+                    Scope::Class(name.clone()), package.as_deref());
+                let mut class = Class {
+                    name, location, comment: get_comment(file_comments.as_ref(), &node),
+                    parameters: extract_parameters(syntax_tree, &node, &comments),
+                    is_virtual: x.nodes.0.is_some(),
+                    extends: None,
+                    base_name: None,
+                    implements: Vec::new(),
+                    properties: Vec::new(),
+                    constraints: Vec::new(),
+                    covergroups: Vec::new(),
+                    imports: visible_imports(syntax_tree, &node, &unit_imports),
+                };
+                extract_class_items(syntax_tree, source, x, &comments, &mut class);
+                design.classes.push(class);
+            }
+            RefNode::SourceText(_) => {
+                unit_imports = extract_imports_exports(syntax_tree, &node).0;
+                extract_subroutines(design, syntax_tree, source, &node,
+                    Scope::CompilationUnit, None);
+                file_comments = Some(svpar::Comments::new(syntax_tree, &node));
+            }
+            _ => (),
+        }
+    }
+}
+
+fn get_name_and_location(
+    syntax_tree: &SyntaxTree,
+    source: &svpar::SourceFile,
+    id: RefNode
+) -> (String, Location)
+{
+    let id: Locate = svpar::get_identifier(id).unwrap();
+    let name = syntax_tree.get_str(&id).unwrap().to_string();
+    let location = source.location(syntax_tree, &id);
+
+    (name, location)
+}
+
+/// Comment block right above the first token of declaration.
+///
+fn get_comment(comments: Option<&svpar::Comments>, node: &RefNode) -> Option<String> {
+    let first = node.clone().into_iter().find_map(|n| match n {
+        RefNode::Locate(x) => Some(*x),
+        _ => None,
+    })?;
+
+    comments?.preceding_text(&first)
+}
+
+fn extract_module(
+    syntax_tree: &SyntaxTree,
+    source: &svpar::SourceFile,
+    id: RefNode,
+    is_ansi: bool,
+    module_node: &RefNode,
+    file_comments: Option<&svpar::Comments>
+) -> Module
+{
+    let (name, location) = get_name_and_location(syntax_tree, source, id);
+
+    let comments = svpar::Comments::new(syntax_tree, module_node);
+
+    let ports = if is_ansi {
+        extract_ansi_ports(syntax_tree, module_node, &comments)
+    }
+    else {
+        extract_nonansi_ports(syntax_tree, module_node, &comments)
+    };
+
+    Module {
+        name,
+        location,
+        comment: get_comment(file_comments, module_node),
+        is_ansi,
+        parameters: extract_parameters(syntax_tree, module_node, &comments),
+        ports,
+        instances: extract_instances(syntax_tree, source, module_node),
+        state_machines: fsm::extract_state_machines(syntax_tree, source, module_node),
+        imports: Vec::new(),
+    }
+}
+
+/// Extract ports of ANSI module.
+///
+/// Port without direction and type inherits them from the previous port,
+/// port with type but without direction inherits only the direction,
+/// unless it is an interface port.
+/// Port description is the comment on the same line or on the lines above.
+///
+fn extract_ansi_ports(
+    syntax_tree: &SyntaxTree,
+    module_node: &RefNode,
+    comments: &svpar::Comments
+) -> Vec<Port>
+{
+    let mut ports: Vec<Port> = Vec::new();
+
+    for node in svpar::scope_nodes(module_node) {
+        if let RefNode::AnsiPortDeclaration(x) = node {
+            let prev = ports.last();
+
+            let (has_header, id) = match x {
+                AnsiPortDeclaration::Net(p) => (p.nodes.0.is_some(), RefNode::from(&p.nodes.1)),
+                AnsiPortDeclaration::Variable(p) => (p.nodes.0.is_some(), RefNode::from(&p.nodes.1)),
+                AnsiPortDeclaration::Paren(p) => (true, RefNode::from(&p.nodes.2)),
+            };
+
+            let id = svpar::get_identifier(id).unwrap();
+            let name = syntax_tree.get_str(&id).unwrap().to_string();
+
+            let port_type = match (has_header, prev) {
+                (false, Some(prev)) => prev.port_type.clone(),
+                _ => get_port_type(syntax_tree, node.clone()),
+            };
+
+            // Interface ports have no direction.
+            let direction = match get_direction(unwrap_node!(x, PortDirection)) {
+                Some(direction) => Some(direction),
+                None if port_type.interface.is_some() => None,
+                None => prev.and_then(|p| p.direction),
+            };
+
+            let unpacked_dims = match x {
+                AnsiPortDeclaration::Paren(_) => Vec::new(),
+                _ => get_dimensions(syntax_tree, node.clone()),
+            };
+
+            let comment = comments.attached(&id);
+
+            ports.push(Port { name, direction, port_type, unpacked_dims, comment });
+        }
+    }
+
+    ports
+}
+
+/// Reconstruct ports of non-ANSI module.
+///
+/// Port names are taken from the module header in header order,
+/// direction and type come from `input/output/inout` declarations
+/// in the module body, and, if the declaration has no type,
+/// from net or variable declaration with the same name.
+/// Comment of port declaration is preferred over comment in the header.
+///
+fn extract_nonansi_ports(
+    syntax_tree: &SyntaxTree,
+    module_node: &RefNode,
+    comments: &svpar::Comments
+) -> Vec<Port>
+{
+    // External name, internal name and comment of header ports.
+    let mut header_ports: Vec<(String, String, Option<String>)> = Vec::new();
+    let mut declarations: Vec<Port> = Vec::new();
+    let mut net_var_types: Vec<(String, PortType, Vec<String>)> = Vec::new();
+
+    let nodes = svpar::scope_nodes(module_node);
+
+    for node in &nodes {
+        match node {
+            RefNode::ListOfPorts(x) => {
+                for port in RefNode::from(*x).into_iter() {
+                    match port {
+                        // `.name(expr)`, internal name is the referenced one.
+                        RefNode::PortNamed(p) => {
+                            let id = svpar::get_identifier(RefNode::from(&p.nodes.1)).unwrap();
+                            let name = syntax_tree.get_str(&id).unwrap().to_string();
+                            let internal = (p.nodes.2).nodes.1.as_ref()
+                                .and_then(|expr| unwrap_node!(RefNode::from(expr), PortIdentifier))
+                                .map(|id| get_identifier_str(syntax_tree, id))
+                                .unwrap_or_else(|| name.clone());
+                            header_ports.push((name, internal, comments.attached(&id)));
+                        }
+                        RefNode::PortNonNamed(p) => {
+                            match &p.nodes.0 {
+                                Some(PortExpression::PortReference(r)) => {
+                                    let id = svpar::get_identifier(RefNode::from(&r.nodes.0)).unwrap();
+                                    let name = syntax_tree.get_str(&id).unwrap().to_string();
+                                    header_ports.push((name.clone(), name, comments.attached(&id)));
+                                }
+                                Some(expr) => {
+                                    let name = syntax_tree.get_str_trim(expr).unwrap_or("").to_string();
+                                    header_ports.push((name.clone(), name, None));
+                                }
+                                None => (),
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            RefNode::PortDeclaration(x) => {
+                let (direction, ids) = match x {
+                    PortDeclaration::Input(d) => (Some(Direction::Input), match &d.nodes.1 {
+                        InputDeclaration::Net(n) => RefNode::from(&n.nodes.2),
+                        InputDeclaration::Variable(v) => RefNode::from(&v.nodes.2),
+                    }),
+                    PortDeclaration::Output(d) => (Some(Direction::Output), match &d.nodes.1 {
+                        OutputDeclaration::Net(n) => RefNode::from(&n.nodes.2),
+                        OutputDeclaration::Variable(v) => RefNode::from(&v.nodes.2),
+                    }),
+                    PortDeclaration::Inout(d) => (Some(Direction::Inout), RefNode::from(&d.nodes.1.nodes.2)),
+                    PortDeclaration::Ref(d) => (Some(Direction::Ref), RefNode::from(&d.nodes.1.nodes.2)),
+                    PortDeclaration::Interface(d) => (None, RefNode::from(&d.nodes.1.nodes.2)),
+                };
+                let port_type = get_port_type(syntax_tree, node.clone());
+                for (name, id, unpacked_dims) in get_identifiers_and_dims(syntax_tree, ids) {
+                    declarations.push(Port {
+                        name,
+                        direction,
+                        port_type: port_type.clone(),
+                        unpacked_dims,
+                        comment: comments.attached(&id),
+                    });
+                }
+            }
+            RefNode::NetDeclaration(_) | RefNode::DataDeclaration(_) => {
+                let port_type = get_port_type(syntax_tree, node.clone());
+                for (name, _, unpacked_dims) in get_identifiers_and_dims(syntax_tree, node.clone()) {
+                    net_var_types.push((name, port_type.clone(), unpacked_dims));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let mut ports: Vec<Port> = Vec::new();
+
+    for (name, internal, header_comment) in header_ports {
+        let mut port = match declarations.iter().find(|p| p.name == internal) {
+            Some(decl) => decl.clone(),
+            None => Port { name: internal.clone(), direction: None,
+                           port_type: PortType::default(), unpacked_dims: Vec::new(),
+                           comment: None },
+        };
+        port.name = name;
+        port.comment = port.comment.or(header_comment);
+
+        if port.port_type.data_type.is_none() {
+            if let Some((_, port_type, unpacked_dims)) =
+                net_var_types.iter().find(|(n, _, _)| *n == internal)
+            {
+                let net_type = port.port_type.net_type.take();
+                port.port_type = port_type.clone();
+                port.port_type.net_type = port.port_type.net_type.take().or(net_type);
+                if port.unpacked_dims.is_empty() {
+                    port.unpacked_dims = unpacked_dims.clone();
+                }
+            }
+        }
+
+        ports.push(port);
+    }
+
+    ports
+}
+
+fn get_identifier_str(syntax_tree: &SyntaxTree, node: RefNode) -> String {
+    let id = svpar::get_identifier(node).unwrap();
+    syntax_tree.get_str(&id).unwrap().to_string()
+}
+
+fn get_direction(node: Option<RefNode>) -> Option<Direction> {
+    match node {
+        Some(RefNode::PortDirection(PortDirection::Input(_))) => Some(Direction::Input),
+        Some(RefNode::PortDirection(PortDirection::Output(_))) => Some(Direction::Output),
+        Some(RefNode::PortDirection(PortDirection::Inout(_))) => Some(Direction::Inout),
+        Some(RefNode::PortDirection(PortDirection::Ref(_))) => Some(Direction::Ref),
+        _ => None,
+    }
+}
+
+/// Get text of all unpacked or variable dimensions in the node.
+///
+fn get_dimensions(syntax_tree: &SyntaxTree, node: RefNode) -> Vec<String> {
+    node.into_iter()
+        .filter(|n| matches!(n,
+            RefNode::UnpackedDimension(_) | RefNode::UnsizedDimension(_) |
+            RefNode::AssociativeDimension(_) | RefNode::QueueDimension(_)))
+        .filter_map(|n| svpar::get_str_trim(syntax_tree, &n))
+        .collect()
+}
+
+/// Get declared identifiers with their location and unpacked dimensions.
+///
+pub fn get_identifiers_and_dims(syntax_tree: &SyntaxTree, node: RefNode) -> Vec<(String, Locate, Vec<String>)> {
+    let mut ids: Vec<(String, Locate, Vec<String>)> = Vec::new();
+
+    for n in node.into_iter() {
+        match n {
+            RefNode::PortIdentifier(_) | RefNode::VariableIdentifier(_) |
+            RefNode::NetIdentifier(_) | RefNode::InterfaceIdentifier(_) => {
+                let id = svpar::get_identifier(n).unwrap();
+                ids.push((syntax_tree.get_str(&id).unwrap().to_string(), id, Vec::new()));
+            }
+            RefNode::UnpackedDimension(_) | RefNode::UnsizedDimension(_) |
+            RefNode::AssociativeDimension(_) | RefNode::QueueDimension(_) => {
+                if let (Some(last), Some(dim)) = (ids.last_mut(), svpar::get_str_trim(syntax_tree, &n)) {
+                    last.2.push(dim);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    ids
+}
+
+/// Get declared type of a port, net or variable declaration.
+///
+pub fn get_port_type(
+    syntax_tree: &SyntaxTree,
+    node: RefNode
+) -> PortType
+{
+    let mut port_type = PortType {
+        net_type: unwrap_node!(node.clone(), NetType)
+            .and_then(|n| svpar::get_str_trim(syntax_tree, &n)),
+        ..Default::default()
+    };
+
+    // Interface port `ApbIf.slave bus` or `interface.slave bus`.
+    match unwrap_node!(node.clone(), InterfacePortHeader, InterfacePortDeclaration) {
+        Some(RefNode::InterfacePortHeader(InterfacePortHeader::Identifier(x))) => {
+            port_type.interface = Some(get_identifier_str(syntax_tree, RefNode::from(&x.nodes.0)));
+            port_type.modport = x.nodes.1.as_ref()
+                .map(|(_, modport)| get_identifier_str(syntax_tree, RefNode::from(modport)));
+            return port_type;
+        }
+        Some(RefNode::InterfacePortHeader(InterfacePortHeader::Interface(x))) => {
+            port_type.interface = Some("interface".to_string());
+            port_type.modport = x.nodes.1.as_ref()
+                .map(|(_, modport)| get_identifier_str(syntax_tree, RefNode::from(modport)));
+            return port_type;
+        }
+        Some(RefNode::InterfacePortDeclaration(x)) => {
+            port_type.interface = Some(get_identifier_str(syntax_tree, RefNode::from(&x.nodes.0)));
+            port_type.modport = x.nodes.1.as_ref()
+                .map(|(_, modport)| get_identifier_str(syntax_tree, RefNode::from(modport)));
+            return port_type;
+        }
+        _ => (),
+    }
+
+    let data_type = match unwrap_node!(node, DataTypeOrImplicit, DataType, NetTypeIdentifier) {
+        Some(data_type) => data_type,
+        None => return port_type,
+    };
+
+    port_type.data_type = svpar::get_str_trim(syntax_tree, &data_type);
+
+    port_type.type_name = match unwrap_node!(data_type.clone(), DataTypeType, ClassType, NetTypeIdentifier) {
+        Some(RefNode::DataTypeType(x)) => {
+            let scope = x.nodes.0.as_ref()
+                .and_then(|scope| syntax_tree.get_str_trim(scope))
+                .unwrap_or("");
+            Some(format!("{}{}", scope, get_identifier_str(syntax_tree, RefNode::from(&x.nodes.1))))
+        }
+        Some(RefNode::ClassType(x)) => syntax_tree.get_str_trim(x).map(|s| s.to_string()),
+        Some(RefNode::NetTypeIdentifier(x)) => syntax_tree.get_str_trim(x).map(|s| s.to_string()),
+        _ => None,
+    };
+
+    // Packed dimensions of the port type itself, not of nested struct members.
+    let mut nested_depth: usize = 0;
+    for event in data_type.into_iter().event() {
+        match event {
+            NodeEvent::Enter(RefNode::StructUnionMember(_)) => nested_depth += 1,
+            NodeEvent::Leave(RefNode::StructUnionMember(_)) => nested_depth -= 1,
+            NodeEvent::Enter(n @ RefNode::PackedDimension(_)) if nested_depth == 0 => {
+                if let Some(dim) = svpar::get_str_trim(syntax_tree, &n) {
+                    port_type.packed_dims.push(dim);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    port_type
+}
+
+/// Constants and types of already parsed packages,
+/// visible unscoped if the package is imported with `Pkg::*`.
+///
+fn make_type_env(design: &Design, imports: &[&Vec<String>]) -> typedef::TypeEnv {
+    let mut env = typedef::TypeEnv::default();
+
+    for pkg in &design.packages {
+        let wildcard = format!("{}::*", pkg.name);
+        env.add_package(pkg, imports.iter().any(|items| items.contains(&wildcard)));
+    }
+
+    env
+}
+
+/// Nets and variables declared in the scope, except ports.
+///
+fn extract_signals(
+    syntax_tree: &SyntaxTree,
+    scope_node: &RefNode,
+    comments: &svpar::Comments,
+    ports: &[Port],
+    env: &typedef::TypeEnv
+) -> Vec<Signal>
+{
+    let mut signals: Vec<Signal> = Vec::new();
+
+    for node in svpar::scope_nodes(scope_node) {
+        match node {
+            RefNode::NetDeclaration(_) | RefNode::DataDeclarationVariable(_) => {
+                let signal_type = get_port_type(syntax_tree, node.clone());
+                let width = typedef::declaration_width(syntax_tree, &node, env);
+                for (name, id, unpacked_dims) in get_identifiers_and_dims(syntax_tree, node.clone()) {
+                    if ports.iter().any(|p| p.name == name) {
+                        continue;
+                    }
+                    signals.push(Signal {
+                        name,
+                        signal_type: signal_type.clone(),
+                        unpacked_dims,
+                        width,
+                        comment: comments.attached(&id),
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+
+    signals
+}
+
+/// Modports with direction of every item.
+///
+fn extract_modports(
+    syntax_tree: &SyntaxTree,
+    scope_node: &RefNode,
+    comments: &svpar::Comments
+) -> Vec<Modport>
+{
+    let mut modports: Vec<Modport> = Vec::new();
+
+    for node in svpar::scope_nodes(scope_node) {
+        let item = match node {
+            RefNode::ModportItem(x) => x,
+            _ => continue,
+        };
+
+        let id = svpar::get_identifier(RefNode::from(&item.nodes.0)).unwrap();
+        let mut modport = Modport {
+            name: syntax_tree.get_str(&id).unwrap().to_string(),
+            ports: Vec::new(),
+            comment: comments.attached(&id),
+        };
+
+        for decl in (item.nodes.1).nodes.1.contents() {
+            match decl {
+                ModportPortsDeclaration::Simple(x) => {
+                    let direction = syntax_tree.get_str_trim(&(x.nodes.1).nodes.0).unwrap_or("").to_string();
+                    for port in (x.nodes.1).nodes.1.contents() {
+                        let id = match port {
+                            ModportSimplePort::Ordered(p) => RefNode::from(&p.nodes.0),
+                            ModportSimplePort::Named(p) => RefNode::from(&p.nodes.1),
+                        };
+                        modport.ports.push(ModportPort {
+                            name: get_identifier_str(syntax_tree, id),
+                            direction: direction.clone(),
+                        });
+                    }
+                }
+                ModportPortsDeclaration::Tf(x) => {
+                    let direction = syntax_tree.get_str_trim(&(x.nodes.1).nodes.0).unwrap_or("").to_string();
+                    for port in (x.nodes.1).nodes.1.contents() {
+                        let id = match port {
+                            ModportTfPort::MethodPrototype(p) =>
+                                unwrap_node!(p.as_ref(), FunctionIdentifier, TaskIdentifier).unwrap(),
+                            ModportTfPort::TfIdentifier(p) => RefNode::from(p.as_ref()),
+                        };
+                        modport.ports.push(ModportPort {
+                            name: get_identifier_str(syntax_tree, id),
+                            direction: direction.clone(),
+                        });
+                    }
+                }
+                ModportPortsDeclaration::Clocking(x) => {
+                    modport.ports.push(ModportPort {
+                        name: get_identifier_str(syntax_tree, RefNode::from(&(x.nodes.1).nodes.1)),
+                        direction: String::from("clocking"),
+                    });
+                }
+            }
+        }
+
+        modports.push(modport);
+    }
+
+    modports
+}
+
+/// Clocking blocks with skews of their signals.
+///
+fn extract_clocking_blocks(
+    syntax_tree: &SyntaxTree,
+    scope_node: &RefNode,
+    comments: &svpar::Comments
+) -> Vec<ClockingBlock>
+{
+    let mut blocks: Vec<ClockingBlock> = Vec::new();
+
+    let text = |node: RefNode| svpar::get_str_trim(syntax_tree, &node)
+        .map(|t| t.split_whitespace().collect::<Vec<&str>>().join(" "));
+
+    for node in svpar::scope_nodes(scope_node) {
+        let x = match node {
+            RefNode::ClockingDeclarationLocal(x) => x,
+            _ => continue,
+        };
+
+        let mut block = ClockingBlock {
+            name: x.nodes.2.as_ref().map(|id| get_identifier_str(syntax_tree, RefNode::from(id))),
+            is_default: x.nodes.0.is_some(),
+            event: text(RefNode::from(&x.nodes.3)).unwrap_or_default(),
+            default_skew: None,
+            signals: Vec::new(),
+            comment: None,
+        };
+
+        if let Some(first) = unwrap_locate!(node.clone()) {
+            block.comment = comments.preceding(first).or_else(|| comments.trailing(first));
+        }
+
+        for item in &x.nodes.5 {
+            match item {
+                ClockingItem::Default(d) => {
+                    block.default_skew = text(RefNode::from(&d.nodes.1));
+                }
+                ClockingItem::Direction(d) => {
+                    let (direction, skew) = match &d.nodes.0 {
+                        ClockingDirection::Input(c) => (
+                            String::from("input"),
+                            c.nodes.1.as_ref().and_then(|skew| text(RefNode::from(skew)))),
+                        ClockingDirection::Output(c) => (
+                            String::from("output"),
+                            c.nodes.1.as_ref().and_then(|skew| text(RefNode::from(skew)))),
+                        ClockingDirection::InputOutput(c) => {
+                            let input = c.nodes.1.as_ref().and_then(|skew| text(RefNode::from(skew)));
+                            let output = c.nodes.3.as_ref().and_then(|skew| text(RefNode::from(skew)));
+                            let skew = match (input, output) {
+                                (None, None) => None,
+                                (input, output) => Some(format!("input {}, output {}",
+                                    input.unwrap_or_default(), output.unwrap_or_default())),
+                            };
+                            (String::from("input output"), skew)
+                        }
+                        ClockingDirection::Inout(_) => (String::from("inout"), None),
+                    };
+                    for assign in (d.nodes.1).nodes.0.contents() {
+                        block.signals.push(ClockingSignal {
+                            name: get_identifier_str(syntax_tree, RefNode::from(&assign.nodes.0)),
+                            direction: direction.clone(),
+                            skew: skew.clone(),
+                        });
+                    }
+                }
+                ClockingItem::Assertion(_) => (),
+            }
+        }
+
+        blocks.push(block);
+    }
+
+    blocks
+}
+
+/// Base classes, properties, constraints and covergroups of class.
+///
+fn extract_class_items(
+    syntax_tree: &SyntaxTree,
+    source: &svpar::SourceFile,
+    decl: &ClassDeclaration,
+    comments: &svpar::Comments,
+    class: &mut Class
+)
+{
+    let text = |node: RefNode| svpar::get_str_trim(syntax_tree, &node)
+        .map(|t| t.split_whitespace().collect::<Vec<&str>>().join(" "));
+
+    if let Some((_, base, _)) = &decl.nodes.5 {
+        class.extends = text(RefNode::from(base));
+        class.base_name = base.nodes.2.last()
+            .map(|(_, id, _)| get_identifier_str(syntax_tree, RefNode::from(id)))
+            .or_else(|| unwrap_node!(&base.nodes.0, ClassIdentifier)
+                .map(|id| get_identifier_str(syntax_tree, id)));
+    }
+    if let Some((_, list)) = &decl.nodes.6 {
+        class.implements = list.contents().into_iter()
+            .filter_map(|t| text(RefNode::from(t)))
+            .collect();
+    }
+
+    for item in &decl.nodes.8 {
+        match item {
+            ClassItem::Property(x) => match &x.nodes.1 {
+                ClassProperty::NonConst(p) => {
+                    let mut qualifiers = get_qualifiers(syntax_tree,
+                        p.nodes.0.iter().map(RefNode::from).collect(), &[]);
+                    let var = match &p.nodes.1 {
+                        DataDeclaration::Variable(var) => var,
+                        _ => continue,
+                    };
+                    if var.nodes.0.is_some() {
+                        qualifiers.push(String::from("const"));
+                    }
+                    if let Some(lifetime) = var.nodes.2.as_ref().and_then(|l| text(RefNode::from(l))) {
+                        qualifiers.push(lifetime);
+                    }
+                    let data_type = text(RefNode::from(&var.nodes.3));
+                    for (name, id, unpacked_dims) in get_identifiers_and_dims(syntax_tree, RefNode::from(&var.nodes.4)) {
+                        class.properties.push(Property {
+                            name,
+                            data_type: data_type.clone(),
+                            unpacked_dims,
+                            qualifiers: qualifiers.clone(),
+                            value: None,
+                            location: source.location(syntax_tree, &id),
+                            comment: comments.attached(&id),
+                        });
+                    }
+                }
+                ClassProperty::Const(p) => {
+                    let id = svpar::get_identifier(RefNode::from(&p.nodes.3)).unwrap();
+                    class.properties.push(Property {
+                        name: syntax_tree.get_str(&id).unwrap().to_string(),
+                        data_type: text(RefNode::from(&p.nodes.2)),
+                        unpacked_dims: Vec::new(),
+                        qualifiers: get_qualifiers(syntax_tree,
+                            p.nodes.1.iter().map(RefNode::from).collect(), &["const"]),
+                        value: p.nodes.4.as_ref().and_then(|(_, v)| text(RefNode::from(v))),
+                        location: source.location(syntax_tree, &id),
+                        comment: comments.attached(&id),
+                    });
+                }
+            },
+            ClassItem::Constraint(x) => {
+                let (id, qualifiers, body) = match &x.nodes.1 {
+                    ClassConstraint::ConstraintPrototype(c) => {
+                        let mut qualifiers = Vec::new();
+                        qualifiers.extend(c.nodes.0.as_ref().and_then(|q| text(RefNode::from(q))));
+                        qualifiers.extend(c.nodes.1.as_ref().and_then(|q| text(RefNode::from(q))));
+                        (RefNode::from(&c.nodes.3), qualifiers, None)
+                    }
+                    ClassConstraint::ConstraintDeclaration(c) => {
+                        let qualifiers = c.nodes.0.iter().filter_map(|q| text(RefNode::from(q))).collect();
+                        (RefNode::from(&c.nodes.2), qualifiers,
+                            svpar::get_str_trim(syntax_tree, &RefNode::from(&c.nodes.3)))
+                    }
+                };
+                let id = svpar::get_identifier(id).unwrap();
+                class.constraints.push(Constraint {
+                    name: syntax_tree.get_str(&id).unwrap().to_string(),
+                    qualifiers,
+                    body,
+                    comment: comments.attached(&id),
+                });
+            }
+            ClassItem::Covergroup(x) => {
+                let cg = &x.nodes.1;
+                let id = svpar::get_identifier(RefNode::from(&cg.nodes.1)).unwrap();
+                let items = cg.nodes.5.iter()
+                    .flat_map(|spec| RefNode::from(spec).into_iter())
+                    .filter(|n| matches!(n, RefNode::CoverPointIdentifier(_) | RefNode::CrossIdentifier(_)))
+                    .map(|n| get_identifier_str(syntax_tree, n))
+                    .fold(Vec::new(), |mut items: Vec<String>, name| {
+                        if !items.contains(&name) { items.push(name); }
+                        items
+                    });
+                class.covergroups.push(Covergroup {
+                    name: syntax_tree.get_str(&id).unwrap().to_string(),
+                    event: cg.nodes.3.as_ref().and_then(|e| text(RefNode::from(e))),
+                    items,
+                    comment: comments.attached(&id),
+                });
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Import items of compilation unit followed by import items of the scope.
+///
+fn visible_imports(
+    syntax_tree: &SyntaxTree,
+    scope_node: &RefNode,
+    unit_imports: &[String]
+) -> Vec<String>
+{
+    let mut imports = unit_imports.to_vec();
+
+    for item in extract_imports_exports(syntax_tree, scope_node).0 {
+        if !imports.contains(&item) {
+            imports.push(item);
+        }
+    }
+
+    imports
+}
+
+/// Items of `import` and `export` declarations in the scope.
+///
+fn extract_imports_exports(
+    syntax_tree: &SyntaxTree,
+    scope_node: &RefNode
+) -> (Vec<String>, Vec<String>)
+{
+    let mut imports: Vec<String> = Vec::new();
+    let mut exports: Vec<String> = Vec::new();
+
+    for node in svpar::scope_nodes(scope_node) {
+        match node {
+            RefNode::PackageImportDeclaration(x) => {
+                for item in x.nodes.1.contents() {
+                    if let Some(item) = syntax_tree.get_str_trim(item) {
+                        imports.push(item.to_string());
+                    }
+                }
+            }
+            RefNode::PackageExportDeclarationAsterisk(_) => {
+                exports.push(String::from("*::*"));
+            }
+            RefNode::PackageExportDeclarationItem(x) => {
+                for item in RefNode::from(x).into_iter() {
+                    if let RefNode::PackageImportItem(item) = item {
+                        if let Some(item) = syntax_tree.get_str_trim(item) {
+                            exports.push(item.to_string());
+                        }
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    (imports, exports)
+}
+
+/// Extract functions and tasks declared directly in the scope.
+///
+/// Out-of-block definitions like `function void C::f()` belong to class `C`.
+/// `package` is the package the scope is declared in, if any.
+///
+fn extract_subroutines(
+    design: &mut Design,
+    syntax_tree: &SyntaxTree,
+    source: &svpar::SourceFile,
+    scope_node: &RefNode,
+    scope: Scope,
+    package: Option<&str>
+)
+{
+    let comments = svpar::Comments::new(syntax_tree, scope_node);
+
+    let mut subs: Vec<(RefNode, Subroutine)> = Vec::new();
+
+    for node in svpar::scope_nodes(scope_node) {
+        match node {
+            RefNode::ClassMethod(x) => {
+                let (qualifiers, mut sub) = match x {
+                    ClassMethod::Task(m) => (
+                        get_qualifiers(syntax_tree, m.nodes.0.iter().map(RefNode::from).collect(), &[]),
+                        task_declaration(syntax_tree, source, &m.nodes.1)),
+                    ClassMethod::Function(m) => (
+                        get_qualifiers(syntax_tree, m.nodes.0.iter().map(RefNode::from).collect(), &[]),
+                        function_declaration(syntax_tree, source, &m.nodes.1)),
+                    ClassMethod::PureVirtual(m) => (
+                        get_qualifiers(syntax_tree, m.nodes.2.iter().map(RefNode::from).collect(), &["pure virtual"]),
+                        method_prototype(syntax_tree, source, &m.nodes.3)),
+                    ClassMethod::ExternMethod(m) => (
+                        get_qualifiers(syntax_tree, m.nodes.1.iter().map(RefNode::from).collect(), &["extern"]),
+                        method_prototype(syntax_tree, source, &m.nodes.2)),
+                    ClassMethod::Constructor(m) => (
+                        get_qualifiers(syntax_tree, m.nodes.0.iter().map(RefNode::from).collect(), &[]),
+                        constructor_declaration(syntax_tree, source, &m.nodes.1)),
+                    ClassMethod::ExternConstructor(m) => {
+                        let (_, location) = get_name_and_location(syntax_tree, source, RefNode::from(&m.nodes.2));
+                        let mut sub = new_subroutine("new", SubroutineKind::Function, location);
+                        if let Some(ports) = &m.nodes.2.nodes.2 {
+                            if let Some(ports) = &ports.nodes.1 {
+                                sub.arguments = get_arguments(syntax_tree, RefNode::from(ports));
+                            }
+                        }
+                        (get_qualifiers(syntax_tree, m.nodes.1.iter().map(RefNode::from).collect(), &["extern"]), sub)
+                    }
+                };
+                sub.qualifiers = qualifiers;
+                sub.scope = scope.clone();
+                subs.push((node.clone(), sub));
+            }
+            RefNode::DpiImportExport(DpiImportExport::ImportFunction(x)) => {
+                let mut sub = function_prototype(syntax_tree, source, &x.nodes.4.nodes.0);
+                sub.qualifiers = get_qualifiers(syntax_tree, x.nodes.2.iter().map(RefNode::from).collect(),
+                    &[&format!("import {}", syntax_tree.get_str_trim(&x.nodes.1).unwrap_or(""))]);
+                sub.scope = scope.clone();
+                subs.push((node.clone(), sub));
+            }
+            RefNode::DpiImportExport(DpiImportExport::ImportTask(x)) => {
+                let mut sub = task_prototype(syntax_tree, source, &x.nodes.4.nodes.0);
+                sub.qualifiers = get_qualifiers(syntax_tree, x.nodes.2.iter().map(RefNode::from).collect(),
+                    &[&format!("import {}", syntax_tree.get_str_trim(&x.nodes.1).unwrap_or(""))]);
+                sub.scope = scope.clone();
+                subs.push((node.clone(), sub));
+            }
+            _ => (),
+        }
+    }
+
+    // Class methods are all wrapped in `ClassMethod`, found above.
+    if !matches!(scope, Scope::Class(_)) {
+        for node in svpar::nested_scopes(scope_node) {
+            let (class_scope, mut sub) = match node {
+                RefNode::FunctionDeclaration(x) => (
+                    get_class_scope(syntax_tree, &node),
+                    function_declaration(syntax_tree, source, x)),
+                RefNode::TaskDeclaration(x) => (
+                    get_class_scope(syntax_tree, &node),
+                    task_declaration(syntax_tree, source, x)),
+                _ => continue,
+            };
+            sub.scope = match class_scope {
+                Some(class_name) => Scope::Class(class_name),
+                None => scope.clone(),
+            };
+            subs.push((node.clone(), sub));
+        }
+    }
+
+    for (node, mut sub) in subs {
+        sub.package = package.map(String::from);
+        if let Some(first) = unwrap_locate!(node) {
+            sub.comment = comments.preceding(first).or_else(|| comments.trailing(first));
+        }
+        design.subroutines.push(sub);
+    }
+}
+
+fn new_subroutine(name: &str, kind: SubroutineKind, location: Location) -> Subroutine {
+    Subroutine {
+        name: name.to_string(),
+        kind,
+        scope: Scope::CompilationUnit,
+        package: None,
+        location,
+        comment: None,
+        return_type: None,
+        arguments: Vec::new(),
+        lifetime: None,
+        qualifiers: Vec::new(),
+    }
+}
+
+/// Texts of qualifier nodes, after the `first` ones.
+///
+fn get_qualifiers(syntax_tree: &SyntaxTree, nodes: Vec<RefNode>, first: &[&str]) -> Vec<String> {
+    let mut qualifiers: Vec<String> = first.iter().map(|q| q.to_string()).collect();
+
+    for node in nodes {
+        if let Some(q) = svpar::get_str_trim(syntax_tree, &node) {
+            let q = q.split_whitespace().collect::<Vec<&str>>().join(" ");
+            if !qualifiers.contains(&q) {
+                qualifiers.push(q);
+            }
+        }
+    }
+
+    qualifiers
+}
+
+/// Class name of out-of-block method definition `C::f`.
+///
+fn get_class_scope(syntax_tree: &SyntaxTree, node: &RefNode) -> Option<String> {
+    let scope = match node {
+        RefNode::FunctionDeclaration(x) => match &x.nodes.2 {
+            FunctionBodyDeclaration::WithoutPort(b) => &b.nodes.1,
+            FunctionBodyDeclaration::WithPort(b) => &b.nodes.1,
+        },
+        RefNode::TaskDeclaration(x) => match &x.nodes.2 {
+            TaskBodyDeclaration::WithoutPort(b) => &b.nodes.0,
+            TaskBodyDeclaration::WithPort(b) => &b.nodes.0,
+        },
+        _ => return None,
+    };
+
+    match scope {
+        Some(InterfaceIdentifierOrClassScope::ClassScope(x)) =>
+            Some(get_identifier_str(syntax_tree, RefNode::from(&x.nodes.0))),
+        _ => None,
+    }
+}
+
+fn get_lifetime(syntax_tree: &SyntaxTree, node: RefNode) -> Option<String> {
+    unwrap_node!(node, Lifetime).and_then(|n| svpar::get_str_trim(syntax_tree, &n))
+}
+
+fn function_declaration(
+    syntax_tree: &SyntaxTree,
+    source: &svpar::SourceFile,
+    x: &FunctionDeclaration
+) -> Subroutine
+{
+    let (return_type, id) = match &x.nodes.2 {
+        FunctionBodyDeclaration::WithoutPort(b) => (RefNode::from(&b.nodes.0), RefNode::from(&b.nodes.2)),
+        FunctionBodyDeclaration::WithPort(b) => (RefNode::from(&b.nodes.0), RefNode::from(&b.nodes.2)),
+    };
+
+    let (name, location) = get_name_and_location(syntax_tree, source, id);
+    let mut sub = new_subroutine(&name, SubroutineKind::Function, location);
+    sub.return_type = svpar::get_str_trim(syntax_tree, &return_type).filter(|t| !t.is_empty());
+    sub.lifetime = x.nodes.1.as_ref().and_then(|l| syntax_tree.get_str_trim(l)).map(|l| l.to_string());
+    sub.arguments = get_arguments(syntax_tree, RefNode::from(&x.nodes.2));
+
+    sub
+}
+
+fn task_declaration(
+    syntax_tree: &SyntaxTree,
+    source: &svpar::SourceFile,
+    x: &TaskDeclaration
+) -> Subroutine
+{
+    let id = match &x.nodes.2 {
+        TaskBodyDeclaration::WithoutPort(b) => RefNode::from(&b.nodes.1),
+        TaskBodyDeclaration::WithPort(b) => RefNode::from(&b.nodes.1),
+    };
+
+    let (name, location) = get_name_and_location(syntax_tree, source, id);
+    let mut sub = new_subroutine(&name, SubroutineKind::Task, location);
+    sub.lifetime = get_lifetime(syntax_tree, RefNode::from(x));
+    sub.arguments = get_arguments(syntax_tree, RefNode::from(&x.nodes.2));
+
+    sub
+}
+
+fn constructor_declaration(
+    syntax_tree: &SyntaxTree,
+    source: &svpar::SourceFile,
+    x: &ClassConstructorDeclaration
+) -> Subroutine
+{
+    let new_keyword = &x.nodes.2.nodes.0;
+    let location = source.location(syntax_tree, new_keyword);
+
+    let mut sub = new_subroutine("new", SubroutineKind::Function, location);
+    if let Some(ports) = &x.nodes.3 {
+        if let Some(ports) = &ports.nodes.1 {
+            sub.arguments = get_arguments(syntax_tree, RefNode::from(ports));
+        }
+    }
+
+    sub
+}
+
+fn method_prototype(
+    syntax_tree: &SyntaxTree,
+    source: &svpar::SourceFile,
+    x: &MethodPrototype
+) -> Subroutine
+{
+    match x {
+        MethodPrototype::FunctionPrototype(p) => function_prototype(syntax_tree, source, p),
+        MethodPrototype::TaskPrototype(p) => task_prototype(syntax_tree, source, p),
+    }
+}
+
+fn function_prototype(
+    syntax_tree: &SyntaxTree,
+    source: &svpar::SourceFile,
+    x: &FunctionPrototype
+) -> Subroutine
+{
+    let (name, location) = get_name_and_location(syntax_tree, source, RefNode::from(&x.nodes.2));
+    let mut sub = new_subroutine(&name, SubroutineKind::Function, location);
+    sub.return_type = syntax_tree.get_str_trim(&x.nodes.1).map(|t| t.to_string());
+    sub.lifetime = get_lifetime(syntax_tree, RefNode::from(x));
+    if let Some(ports) = &x.nodes.3 {
+        if let Some(ports) = &ports.nodes.1 {
+            sub.arguments = get_arguments(syntax_tree, RefNode::from(ports));
+        }
+    }
+
+    sub
+}
+
+fn task_prototype(
+    syntax_tree: &SyntaxTree,
+    source: &svpar::SourceFile,
+    x: &TaskPrototype
+) -> Subroutine
+{
+    let (name, location) = get_name_and_location(syntax_tree, source, RefNode::from(&x.nodes.1));
+    let mut sub = new_subroutine(&name, SubroutineKind::Task, location);
+    if let Some(ports) = &x.nodes.2 {
+        if let Some(ports) = &ports.nodes.1 {
+            sub.arguments = get_arguments(syntax_tree, RefNode::from(ports));
+        }
+    }
+
+    sub
+}
+
+/// Get arguments from ANSI style port list or from old style
+/// `input`/`output` declarations in function or task body.
+///
+/// Argument without direction inherits the direction of the previous one,
+/// `input` for the first argument; without direction and type it also
+/// inherits the type.
+///
+fn get_arguments(syntax_tree: &SyntaxTree, node: RefNode) -> Vec<Argument> {
+    let mut args: Vec<Argument> = Vec::new();
+
+    for n in node.into_iter() {
+        match n {
+            RefNode::TfPortItem(x) => {
+                let (id, default) = match &x.nodes.4 {
+                    Some((id, _, default)) => (id, default),
+                    None => continue, // only type, like in DPI prototypes
+                };
+                let prev = args.last();
+                let direction = match &x.nodes.1 {
+                    Some(direction) => syntax_tree.get_str_trim(direction).map(|d| d.to_string()),
+                    None => prev.map(|p| p.direction.clone()),
+                };
+                let data_type = syntax_tree.get_str_trim(&x.nodes.3)
+                    .filter(|t| !t.is_empty())
+                    .map(|t| t.to_string());
+                let data_type = match (&x.nodes.1, &data_type, prev) {
+                    (None, None, Some(prev)) => prev.data_type.clone(),
+                    _ => data_type,
+                };
+                args.push(Argument {
+                    name: get_identifier_str(syntax_tree, RefNode::from(id)),
+                    direction: normalize_direction(direction),
+                    data_type,
+                    default: default.as_ref()
+                        .and_then(|(_, expr)| syntax_tree.get_str_trim(expr))
+                        .map(|d| d.to_string()),
+                    unpacked_dims: get_dimensions(syntax_tree, n.clone()),
+                });
+            }
+            RefNode::TfPortDeclaration(x) => {
+                let direction = syntax_tree.get_str_trim(&x.nodes.1).map(|d| d.to_string());
+                let data_type = syntax_tree.get_str_trim(&x.nodes.3)
+                    .filter(|t| !t.is_empty())
+                    .map(|t| t.to_string());
+                for (name, _, unpacked_dims) in get_identifiers_and_dims(syntax_tree, RefNode::from(&x.nodes.4)) {
+                    args.push(Argument {
+                        name,
+                        direction: normalize_direction(direction.clone()),
+                        data_type: data_type.clone(),
+                        default: None,
+                        unpacked_dims,
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+
+    args
+}
+
+fn normalize_direction(direction: Option<String>) -> String {
+    match direction {
+        Some(direction) => direction.split_whitespace().collect::<Vec<&str>>().join(" "),
+        None => String::from("input"),
+    }
+}
+
+/// Extract parameters declared in header and body of a module, interface or class.
+///
+fn extract_parameters(
+    syntax_tree: &SyntaxTree,
+    node: &RefNode,
+    comments: &svpar::Comments
+) -> Vec<Parameter>
+{
+    let mut params: Vec<Parameter> = Vec::new();
+
+    let mut/*env*/ add_params = |kind: ParamKind, data_type: Option<String>, list: RefNode| {
+        for param in list.into_iter() {
+            let (id, default) = match param {
+                RefNode::ParamAssignment(x) => (
+                    RefNode::from(&x.nodes.0),
+                    x.nodes.2.as_ref().and_then(|(_, expr)| syntax_tree.get_str_trim(expr))),
+                RefNode::TypeAssignment(x) => (
+                    RefNode::from(&x.nodes.0),
+                    x.nodes.1.as_ref().and_then(|(_, data_type)| syntax_tree.get_str_trim(data_type))),
+                _ => continue,
+            };
+            let id = svpar::get_identifier(id).unwrap();
+            params.push(Parameter {
+                name: syntax_tree.get_str(&id).unwrap().to_string(),
+                kind,
+                data_type: data_type.clone(),
+                default: default.map(|s| s.to_string()),
+                comment: comments.attached(&id),
+            });
+        }
+    };
+
+    for node in svpar::scope_nodes(node) {
+        match node {
+            RefNode::ParameterDeclarationParam(x) => {
+                let data_type = syntax_tree.get_str_trim(&x.nodes.1).map(|s| s.to_string());
+                add_params(ParamKind::Parameter, data_type, RefNode::from(&x.nodes.2));
+            }
+            RefNode::ParameterDeclarationType(x) => {
+                add_params(ParamKind::TypeParameter, None, RefNode::from(&x.nodes.2));
+            }
+            RefNode::LocalParameterDeclarationParam(x) => {
+                let data_type = syntax_tree.get_str_trim(&x.nodes.1).map(|s| s.to_string());
+                add_params(ParamKind::Localparam, data_type, RefNode::from(&x.nodes.2));
+            }
+            RefNode::LocalParameterDeclarationType(x) => {
+                add_params(ParamKind::TypeLocalparam, None, RefNode::from(&x.nodes.2));
+            }
+            RefNode::ParameterPortDeclarationParamList(x) => {
+                let data_type = syntax_tree.get_str_trim(&x.nodes.0).map(|s| s.to_string());
+                add_params(ParamKind::Parameter, data_type, RefNode::from(&x.nodes.1));
+            }
+            RefNode::ParameterPortDeclarationTypeList(x) => {
+                add_params(ParamKind::TypeParameter, None, RefNode::from(&x.nodes.1));
+            }
+            RefNode::ParameterPortListAssignment(x) => {
+                // Parameters before the first `parameter` keyword, like `#(WIDTH = 1)`.
+                let (list, _) = &(x.nodes.1).nodes.1;
+                add_params(ParamKind::Parameter, None, RefNode::from(list));
+            }
+            _ => (),
+        }
+    }
+
+    params
+}
+
+fn extract_instances(
+    syntax_tree: &SyntaxTree,
+    source: &svpar::SourceFile,
+    module_node: &RefNode
+) -> Vec<Instance>
+{
+    let mut instances: Vec<Instance> = Vec::new();
+
+    for node in module_node.clone().into_iter() {
+        if let RefNode::ModuleInstantiation(x) = node {
+            let mod_name = get_identifier_str(syntax_tree, RefNode::from(&x.nodes.0));
+            let parameters = match &x.nodes.1 {
+                Some(assignment) => extract_parameter_overrides(syntax_tree, assignment),
+                None => Vec::new(),
+            };
+
+            // `Mod u1 (...), u2 (...);` declares several instances.
+            for inst in x.nodes.2.contents() {
+                let inst_name = svpar::get_identifier(RefNode::from(&(inst.nodes.0).nodes.0)).unwrap();
+
+                instances.push(Instance {
+                    module_name: mod_name.clone(),
+                    instance_name: syntax_tree.get_str(&inst_name).unwrap().to_string(),
+                    location: source.location(syntax_tree, &inst_name),
+                    parameters: parameters.clone(),
+                    connections: extract_connections(syntax_tree, inst),
+                });
+            }
+        }
+    }
+
+    instances
+}
+
+/// Parameter values given to instances, in order of appearance.
+///
+fn extract_parameter_overrides(
+    syntax_tree: &SyntaxTree,
+    assignment: &ParameterValueAssignment
+) -> Vec<ParameterOverride>
+{
+    let mut parameters: Vec<ParameterOverride> = Vec::new();
+
+    let value_str = |node: RefNode| svpar::get_str_trim(syntax_tree, &node)
+        .map(|v| v.split_whitespace().collect::<Vec<&str>>().join(" "));
+
+    match &(assignment.nodes.1).nodes.1 {
+        Some(ListOfParameterAssignments::Ordered(list)) => {
+            for item in list.nodes.0.contents() {
+                parameters.push(ParameterOverride {
+                    name: None,
+                    value: value_str(RefNode::from(&item.nodes.0)),
+                });
+            }
+        }
+        Some(ListOfParameterAssignments::Named(list)) => {
+            for item in list.nodes.0.contents() {
+                parameters.push(ParameterOverride {
+                    name: Some(get_identifier_str(syntax_tree, RefNode::from(&item.nodes.1))),
+                    value: (item.nodes.2).nodes.1.as_ref().and_then(|v| value_str(RefNode::from(v))),
+                });
+            }
+        }
+        None => (),
+    }
+
+    parameters
+}
+
+/// Port connections of an instance, in order of appearance.
+///
+fn extract_connections(
+    syntax_tree: &SyntaxTree,
+    inst: &HierarchicalInstance
+) -> Vec<Connection>
+{
+    let mut connections: Vec<Connection> = Vec::new();
+
+    let expr_connection = |kind: ConnectionKind, port: Option<String>, expr: Option<&Expression>| {
+        let expr_node = expr.map(RefNode::from);
+        Connection {
+            kind,
+            port,
+            expr: expr_node.as_ref().and_then(|e| svpar::get_str_trim(syntax_tree, e))
+                .map(|e| e.split_whitespace().collect::<Vec<&str>>().join(" ")),
+            signals: expr_node.map(|e| get_expression_signals(syntax_tree, e)).unwrap_or_default(),
+        }
+    };
+
+    match &(inst.nodes.1).nodes.1 {
+        Some(ListOfPortConnections::Ordered(list)) => {
+            let items = list.nodes.0.contents();
+            // Empty parentheses `u1 ()` parse as one empty positional connection.
+            if items.len() == 1 && items[0].nodes.1.is_none() {
+                return connections;
+            }
+            for item in items {
+                connections.push(expr_connection(ConnectionKind::Positional, None, item.nodes.1.as_ref()));
+            }
+        }
+        Some(ListOfPortConnections::Named(list)) => {
+            for item in list.nodes.0.contents() {
+                match item {
+                    NamedPortConnection::Identifier(c) => {
+                        let port = get_identifier_str(syntax_tree, RefNode::from(&c.nodes.2));
+                        match &c.nodes.3 {
+                            Some(paren) => connections.push(expr_connection(
+                                ConnectionKind::Named, Some(port), paren.nodes.1.as_ref())),
+                            None => connections.push(Connection {
+                                kind: ConnectionKind::Implicit,
+                                port: Some(port.clone()),
+                                expr: Some(port.clone()),
+                                signals: vec![port],
+                            }),
+                        }
+                    }
+                    NamedPortConnection::Asterisk(_) => {
+                        connections.push(Connection {
+                            kind: ConnectionKind::Wildcard,
+                            port: None,
+                            expr: None,
+                            signals: Vec::new(),
+                        });
+                    }
+                }
+            }
+        }
+        None => (),
+    }
+
+    connections
+}
+
+/// Names of signals used in expression, not counting indexes of selects.
+///
+fn get_expression_signals(syntax_tree: &SyntaxTree, expr: RefNode) -> Vec<String> {
+    let mut signals: Vec<String> = Vec::new();
+    let mut select_depth: usize = 0;
+
+    for event in expr.into_iter().event() {
+        match event {
+            NodeEvent::Enter(RefNode::Select(_)) | NodeEvent::Enter(RefNode::ConstantSelect(_)) =>
+                select_depth += 1,
+            NodeEvent::Leave(RefNode::Select(_)) | NodeEvent::Leave(RefNode::ConstantSelect(_)) =>
+                select_depth -= 1,
+            NodeEvent::Enter(n @ RefNode::HierarchicalIdentifier(_)) if select_depth == 0 => {
+                if let Some(name) = svpar::get_str_trim(syntax_tree, &n) {
+                    if !signals.contains(&name) {
+                        signals.push(name);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    signals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sv_parser::parse_sv_str;
+    use std::path::PathBuf;
+
+    /// Entities of `source`.
+    ///
+    fn design(source: &str) -> Design {
+        let (syntax_tree, _) = parse_sv_str(source, PathBuf::from("test.sv"),
+            &HashMap::new(), &[] as &[PathBuf], false, false).unwrap();
+        let mut design = Design::default();
+        extract_entities(&mut design, &syntax_tree, "test.sv");
+
+        design
+    }
+
+    fn params(parameters: &[Parameter]) -> Vec<(&str, ParamKind, Option<&str>, Option<&str>)> {
+        parameters.iter()
+            .map(|p| (p.name.as_str(), p.kind, p.data_type.as_deref(), p.default.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn parameters() {
+        let design = design("
+            module M #(
+                parameter int WIDTH = 8, // data width
+                parameter DEPTH = WIDTH * 2,
+                parameter type T = logic [WIDTH-1:0]
+            ) ();
+                localparam AW = $clog2(DEPTH);
+            endmodule
+            interface I #(N = 4);
+            endinterface
+            class C #(type T = int, int SIZE = 2);
+            endclass");
+
+        assert_eq!(params(&design.modules[0].parameters), [
+            ("WIDTH", ParamKind::Parameter, Some("int"), Some("8")),
+            ("DEPTH", ParamKind::Parameter, None, Some("WIDTH * 2")),
+            ("T", ParamKind::TypeParameter, None, Some("logic [WIDTH-1:0]")),
+            ("AW", ParamKind::Localparam, None, Some("$clog2(DEPTH)")),
+        ]);
+        assert_eq!(design.modules[0].parameters[0].comment.as_deref(), Some("data width"));
+        assert_eq!(params(&design.interfaces[0].parameters), [
+            ("N", ParamKind::Parameter, None, Some("4")),
+        ]);
+        assert_eq!(params(&design.classes[0].parameters), [
+            ("T", ParamKind::TypeParameter, None, Some("int")),
+            ("SIZE", ParamKind::Parameter, Some("int"), Some("2")),
+        ]);
+    }
+
+    type PortSummary<'a> = (&'a str, Option<Direction>, Option<&'a str>, Option<&'a str>, Vec<&'a str>);
+
+    /// Name, direction, net type, data type and packed dimensions of ports.
+    ///
+    fn ports(ports: &[Port]) -> Vec<PortSummary<'_>> {
+        ports.iter()
+            .map(|p| (p.name.as_str(), p.direction, p.port_type.net_type.as_deref(),
+                p.port_type.data_type.as_deref(), p.port_type.packed_dims.iter().map(String::as_str).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn nonansi_ports() {
+        let design = design("
+            module m(a, b, .c(c_int), d, e);
+                input [3:0] a;      // input a
+                output reg b;
+                output c_int;
+                inout wire signed [1:0] d [2];
+                input e;
+                logic [7:0] e;
+            endmodule");
+
+        let module = &design.modules[0];
+        assert!(!module.is_ansi);
+        assert_eq!(ports(&module.ports), [
+            ("a", Some(Direction::Input), None, Some("[3:0]"), vec!["[3:0]"]),
+            ("b", Some(Direction::Output), None, Some("reg"), vec![]),
+            ("c", Some(Direction::Output), None, None, vec![]),
+            ("d", Some(Direction::Inout), Some("wire"), Some("signed [1:0]"), vec!["[1:0]"]),
+            ("e", Some(Direction::Input), None, Some("logic [7:0]"), vec!["[7:0]"]),
+        ]);
+        assert_eq!(module.ports[0].comment.as_deref(), Some("input a"));
+        assert_eq!(module.ports[3].unpacked_dims, ["[2]"]);
+    }
+
+    #[test]
+    fn ansi_port_types() {
+        let design = design("
+            module m(
+                input  logic [7:0] a, b,
+                output Pkg::state_t [1:0] s,
+                ApbIf.slave bus,
+                interface.master mst,
+                input wire [3:0] w [4],
+                user_t u
+            );
+            endmodule");
+
+        let ports = &design.modules[0].ports;
+        assert_eq!(ports.iter().map(|p| (p.name.as_str(), p.direction)).collect::<Vec<_>>(), [
+            ("a", Some(Direction::Input)),
+            ("b", Some(Direction::Input)),
+            ("s", Some(Direction::Output)),
+            ("bus", None),
+            ("mst", None),
+            ("w", Some(Direction::Input)),
+            ("u", None),
+        ]);
+
+        assert_eq!(ports[1].port_type.data_type.as_deref(), Some("logic [7:0]"));
+        assert_eq!(ports[1].port_type.packed_dims, ["[7:0]"]);
+
+        assert_eq!(ports[2].port_type.data_type.as_deref(), Some("Pkg::state_t [1:0]"));
+        assert_eq!(ports[2].port_type.type_name.as_deref(), Some("Pkg::state_t"));
+        assert_eq!(ports[2].port_type.packed_dims, ["[1:0]"]);
+
+        assert_eq!(ports[3].port_type.interface.as_deref(), Some("ApbIf"));
+        assert_eq!(ports[3].port_type.modport.as_deref(), Some("slave"));
+        assert_eq!(ports[4].port_type.interface.as_deref(), Some("interface"));
+        assert_eq!(ports[4].port_type.modport.as_deref(), Some("master"));
+
+        assert_eq!(ports[5].port_type.net_type.as_deref(), Some("wire"));
+        assert_eq!(ports[5].port_type.data_type.as_deref(), Some("[3:0]"));
+        assert_eq!(ports[5].unpacked_dims, ["[4]"]);
+
+        // Interface port for the parser, `design::resolve_interface_ports` tells them apart.
+        assert_eq!(ports[6].port_type.interface.as_deref(), Some("user_t"));
+    }
+
+    #[test]
+    fn port_comments() {
+        let design = design("
+            module m(
+                // clock
+                input clk,
+                input rst,  // reset, active high
+                /* data */
+                // bus
+                input [7:0] d,
+                output q,
+
+                output r
+            );
+            endmodule
+            module n(a, b /* header b */, c);
+                input a;
+                input b;
+                input c;    // declared c
+            endmodule");
+
+        let comments = |module: &Module| module.ports.iter()
+            .map(|p| (p.name.clone(), p.comment.clone()))
+            .collect::<Vec<_>>();
+        let port = |name: &str, comment: Option<&str>| (name.to_string(), comment.map(String::from));
+
+        assert_eq!(comments(&design.modules[0]), [
+            port("clk", Some("clock")),
+            port("rst", Some("reset, active high")),
+            port("d", Some("data bus")),
+            port("q", None),
+            port("r", None),
+        ]);
+        assert_eq!(comments(&design.modules[1]), [
+            port("a", None),
+            port("b", Some("header b")),
+            port("c", Some("declared c")),
+        ]);
+    }
+
+    #[test]
+    fn subroutines() {
+        let design = design("
+            // Add numbers.
+            function automatic int add(input int a, b = 1, output logic [3:0] c [2]);
+                return a + b;
+            endfunction
+            import \"DPI-C\" context function int c_func(int x);
+            package P;
+                task static wait_cycles(ref int n, const ref bit done);
+                endtask
+            endpackage
+            module M;
+                function void f(); endfunction
+            endmodule
+            virtual class C;
+                function new(int size = 2); endfunction
+                static function C create(); endfunction
+                pure virtual protected task run();
+                extern virtual function void g(string s);
+            endclass
+            function void C::g(string s); endfunction");
+
+        let summary = design.subroutines.iter()
+            .map(|s| (s.name.as_str(), s.kind, s.scope.clone(), s.return_type.as_deref(), s.qualifiers.join(" ")))
+            .collect::<Vec<_>>();
+        let class = Scope::Class(String::from("C"));
+        assert_eq!(summary, [
+            ("c_func", SubroutineKind::Function, Scope::CompilationUnit, Some("int"), String::from("import \"DPI-C\" context")),
+            ("add", SubroutineKind::Function, Scope::CompilationUnit, Some("int"), String::new()),
+            ("g", SubroutineKind::Function, class.clone(), Some("void"), String::new()),
+            ("wait_cycles", SubroutineKind::Task, Scope::Package(String::from("P")), None, String::new()),
+            ("f", SubroutineKind::Function, Scope::Module(String::from("M")), Some("void"), String::new()),
+            ("new", SubroutineKind::Function, class.clone(), None, String::new()),
+            ("create", SubroutineKind::Function, class.clone(), Some("C"), String::from("static")),
+            ("run", SubroutineKind::Task, class.clone(), None, String::from("pure virtual protected")),
+            ("g", SubroutineKind::Function, class, Some("void"), String::from("extern virtual")),
+        ]);
+
+        let add = &design.subroutines[1];
+        assert_eq!(add.comment.as_deref(), Some("Add numbers."));
+        assert_eq!(add.lifetime.as_deref(), Some("automatic"));
+        assert_eq!(add.location.line, 3);
+        let arguments = add.arguments.iter()
+            .map(|a| (a.name.as_str(), a.direction.as_str(), a.data_type.as_deref(), a.default.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(arguments, [
+            ("a", "input", Some("int"), None),
+            ("b", "input", Some("int"), Some("1")),
+            ("c", "output", Some("logic [3:0]"), None),
+        ]);
+        assert_eq!(add.arguments[2].unpacked_dims, ["[2]"]);
+
+        let wait_cycles = &design.subroutines[3];
+        assert_eq!(wait_cycles.lifetime.as_deref(), Some("static"));
+        assert_eq!(wait_cycles.arguments.iter().map(|a| a.direction.as_str()).collect::<Vec<_>>(), ["ref", "const ref"]);
+        assert_eq!(design.subroutines[5].arguments[0].default.as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn interface_items() {
+        let design = design("
+            interface ApbIf #(AW = 8) (input logic pclk);
+                logic [AW-1:0] paddr;   // address
+                logic          psel, penable;
+                wire [31:0]    prdata [2];
+                // Requester side.
+                modport master(output paddr, psel, input prdata, import read);
+                modport slave(input paddr, psel, output prdata, clocking cb);
+                default clocking cb @(posedge pclk);
+                    default input #1step output #2;
+                    input prdata;
+                    output #1 paddr, psel;
+                endclocking
+                function automatic logic [31:0] read(); endfunction
+            endinterface");
+
+        let iface = &design.interfaces[0];
+        let signals = iface.signals.iter()
+            .map(|s| (s.name.as_str(), s.width, s.unpacked_dims.join(""), s.comment.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(signals, [
+            ("paddr", Some(8), String::new(), Some("address")),
+            ("psel", Some(1), String::new(), None),
+            ("penable", Some(1), String::new(), None),
+            ("prdata", Some(32), "[2]".to_string(), None),
+        ]);
+        assert_eq!(iface.signals[0].signal_type.packed_dims, ["[AW-1:0]"]);
+        assert_eq!(iface.signals[3].signal_type.net_type.as_deref(), Some("wire"));
+
+        let modports = iface.modports.iter()
+            .map(|m| (m.name.as_str(), m.ports.iter()
+                .map(|p| format!("{} {}", p.direction, p.name)).collect::<Vec<_>>().join(", ")))
+            .collect::<Vec<_>>();
+        assert_eq!(modports, [
+            ("master", "output paddr, output psel, input prdata, import read".to_string()),
+            ("slave", "input paddr, input psel, output prdata, clocking cb".to_string()),
+        ]);
+        assert_eq!(iface.modports[0].comment.as_deref(), Some("Requester side."));
+
+        let cb = &iface.clocking_blocks[0];
+        assert_eq!(cb.name.as_deref(), Some("cb"));
+        assert!(cb.is_default);
+        assert_eq!(cb.event, "@(posedge pclk)");
+        assert_eq!(cb.default_skew.as_deref(), Some("input #1step output #2"));
+        let signals = cb.signals.iter()
+            .map(|s| (s.name.as_str(), s.direction.as_str(), s.skew.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(signals, [
+            ("prdata", "input", None),
+            ("paddr", "output", Some("#1")),
+            ("psel", "output", Some("#1")),
+        ]);
+    }
+}
@@ -0,0 +1,343 @@
+//! Read simulator-style filelists (`.f` files).
+//!
+//! Supported entries:
+//!
+//! - source file paths
+//! - `+incdir+DIR[+DIR...]` include directories
+//! - `+define+NAME[=VALUE][+NAME[=VALUE]...]` preprocessor macros
+//! - `+libext+EXT[+EXT...]` extensions of `-y` library files
+//! - `-v FILE` library file
+//! - `-y DIR` library directory, all files with library extension are used
+//! - `-f FILE` and `-F FILE` nested filelists
+//! - `//` and `#` line comments, `$VAR`, `${VAR}` and `$(VAR)` environment variables
+//!
+//! Relative paths are resolved against the directory of the filelist
+//! given on the command line and of `-F` filelists.
+//! Like in simulators, `-f FILE` and paths inside it are relative
+//! to the current directory instead.
+//! All files of a filelist are SV sources, whatever their extension.
+//!
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+use regex::{Captures, Regex};
+
+use crate::args;
+
+/// Content of a filelist and all its nested filelists.
+///
+#[derive(Default)]
+pub struct Filelist {
+    pub files: Vec<PathBuf>,
+    pub incdirs: Vec<PathBuf>,
+    pub defines: Vec<(String, Option<String>)>,
+}
+
+/// Read filelist and all filelists it refers to.
+///
+pub fn read_filelist(path: &Path) -> Result<Filelist,String> {
+    let mut filelist = Filelist::default();
+    let mut visited: Vec<PathBuf> = Vec::new();
+
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    read_filelist_into(&mut filelist, &mut visited, path, base_dir)?;
+
+    Ok(filelist)
+}
+
+/// Read filelist `path`, its relative paths are resolved against `base_dir`.
+///
+fn read_filelist_into(
+    filelist: &mut Filelist,
+    visited: &mut Vec<PathBuf>,
+    path: &Path,
+    base_dir: &Path
+) -> Result<(),String>
+{
+    let canonical = fs::canonicalize(path)
+        .map_err(|e| format!("can't read filelist {:?}: {}", path, e))?;
+    if visited.contains(&canonical) {
+        println!("Warning: filelist {:?} is included recursively", path);
+        return Ok(());
+    }
+    visited.push(canonical);
+
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("can't read filelist {:?}: {}", path, e))?;
+
+    let mut libexts: Vec<String> = vec![String::from("v"), String::from("sv")];
+    let mut lib_dirs: Vec<PathBuf> = Vec::new();
+
+    let mut tokens: Vec<String> = Vec::new();
+    for line in content.lines() {
+        let line = strip_comment(line);
+        tokens.extend(line.split_whitespace().map(expand_env_vars));
+    }
+
+    let mut tokens = tokens.into_iter();
+    while let Some(token) = tokens.next() {
+        if let Some(dirs) = token.strip_prefix("+incdir+") {
+            for dir in dirs.split('+').filter(|d| !d.is_empty()) {
+                filelist.incdirs.push(resolve_path(base_dir, dir));
+            }
+        }
+        else if let Some(defines) = token.strip_prefix("+define+") {
+            for define in defines.split('+').filter(|d| !d.is_empty()) {
+                filelist.defines.push(args::parse_define(define));
+            }
+        }
+        else if let Some(exts) = token.strip_prefix("+libext+") {
+            libexts = exts.split('+')
+                .filter(|e| !e.is_empty())
+                .map(|e| e.trim_start_matches('.').to_string())
+                .collect();
+        }
+        else if token == "-f" {
+            match tokens.next() {
+                Some(nested) => read_filelist_into(filelist, visited, &normalize_path(Path::new(&nested)), Path::new(""))?,
+                None => println!("Warning: missing file name after -f in {:?}", path),
+            }
+        }
+        else if token == "-F" {
+            match tokens.next() {
+                Some(nested) => {
+                    let nested = resolve_path(base_dir, &nested);
+                    let nested_dir = nested.parent().unwrap_or_else(|| Path::new("")).to_path_buf();
+                    read_filelist_into(filelist, visited, &nested, &nested_dir)?;
+                }
+                None => println!("Warning: missing file name after -F in {:?}", path),
+            }
+        }
+        else if token == "-v" {
+            match tokens.next() {
+                Some(file) => filelist.files.push(resolve_path(base_dir, &file)),
+                None => println!("Warning: missing file name after -v in {:?}", path),
+            }
+        }
+        else if token == "-y" {
+            match tokens.next() {
+                Some(dir) => lib_dirs.push(resolve_path(base_dir, &dir)),
+                None => println!("Warning: missing directory after -y in {:?}", path),
+            }
+        }
+        else if token.starts_with('+') || token.starts_with('-') {
+            println!("Warning: ignore unsupported filelist option '{}' in {:?}", token, path);
+        }
+        else {
+            filelist.files.push(resolve_path(base_dir, &token));
+        }
+    }
+
+    // Library extensions apply to all `-y` directories of the filelist.
+    for dir in &lib_dirs {
+        add_library_dir_files(filelist, dir, &libexts);
+    }
+
+    Ok(())
+}
+
+fn add_library_dir_files(filelist: &mut Filelist, dir: &Path, libexts: &[String]) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => { println!("Warning: can't read library directory {:?}: {}", dir, e);
+                    return; },
+    };
+
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| match path.extension() {
+            Some(ext) => libexts.iter().any(|libext| ext.eq(libext.as_str())),
+            None => false,
+        })
+        .map(|path| normalize_path(&path))
+        .collect();
+
+    files.sort();
+    filelist.files.append(&mut files);
+}
+
+fn strip_comment(line: &str) -> &str {
+    let end = [line.find("//"), line.find('#')]
+        .iter()
+        .flatten()
+        .min()
+        .copied()
+        .unwrap_or(line.len());
+
+    &line[..end]
+}
+
+fn expand_env_vars(token: &str) -> String {
+    static ENV_VAR: OnceLock<Regex> = OnceLock::new();
+    let re = ENV_VAR.get_or_init(|| Regex::new(r"\$\{(\w+)\}|\$\((\w+)\)|\$(\w+)").unwrap());
+
+    re.replace_all(token, |caps: &Captures| {
+        let name = caps.get(1).or_else(|| caps.get(2)).or_else(|| caps.get(3)).unwrap().as_str();
+        match std::env::var(name) {
+            Ok(value) => value,
+            Err(_) => { println!("Warning: environment variable '{}' is not set", name);
+                        String::new() },
+        }
+    }).to_string()
+}
+
+fn resolve_path(base_dir: &Path, path: &str) -> PathBuf {
+    normalize_path(&base_dir.join(path))
+}
+
+/// Lexically remove `.` and `..` from path,
+/// and make absolute path relative to current directory if possible.
+///
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let path = if path.is_absolute() {
+        match std::env::current_dir() {
+            Ok(cwd) => path.strip_prefix(&cwd).map(Path::to_path_buf).unwrap_or_else(|_| path.to_path_buf()),
+            Err(_) => path.to_path_buf(),
+        }
+    }
+    else {
+        path.to_path_buf()
+    };
+
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                match normalized.components().next_back() {
+                    Some(Component::Normal(_)) => { normalized.pop(); },
+                    _ => normalized.push(".."),
+                }
+            }
+            c => normalized.push(c.as_os_str()),
+        }
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty directory for test files, the filelist paths stay absolute.
+    ///
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("svdocgen-filelist-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(dir: &Path, name: &str, text: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn nested_filelists() {
+        let dir = test_dir("nested");
+        let inner = write(&dir, "sub/inner.f", "rtl/b.sv\n");
+        write(&dir, "sub/deep/last.f", "c.sv\n");
+        write(&dir, "sub/other.f", "-F deep/last.f\n");
+        let top = write(&dir, "top.f", &format!("a.sv\n-f {}\n-F sub/other.f\n-F top.f\n", inner.display()));
+
+        let filelist = read_filelist(&top).unwrap();
+
+        // `-f` filelist paths are relative to current directory, `-F` to the filelist.
+        assert_eq!(filelist.files, vec![
+            dir.join("a.sv"),
+            PathBuf::from("rtl/b.sv"),
+            dir.join("sub/deep/c.sv"),
+        ]);
+    }
+
+    #[test]
+    fn nested_filelist_relative_to_current_dir() {
+        let dir = test_dir("nested-cwd");
+        write(&dir, "sub/inner.f", "b.sv\n");
+        let top = write(&dir, "top.f", "-f sub/inner.f\n");
+
+        assert!(read_filelist(&top).is_err());
+    }
+
+    #[test]
+    fn incdirs() {
+        let dir = test_dir("incdir");
+        let top = write(&dir, "top.f", "+incdir+inc+../common+./rtl/include\n+incdir+more\n");
+
+        let filelist = read_filelist(&top).unwrap();
+
+        assert_eq!(filelist.incdirs, vec![
+            dir.join("inc"),
+            dir.parent().unwrap().join("common"),
+            dir.join("rtl/include"),
+            dir.join("more"),
+        ]);
+    }
+
+    #[test]
+    fn defines() {
+        let dir = test_dir("define");
+        let top = write(&dir, "top.f", "+define+WIDTH=8+SIM\n+define+NAME=a=b\n");
+
+        let filelist = read_filelist(&top).unwrap();
+
+        assert_eq!(filelist.defines, vec![
+            (String::from("WIDTH"), Some(String::from("8"))),
+            (String::from("SIM"), None),
+            (String::from("NAME"), Some(String::from("a=b"))),
+        ]);
+    }
+
+    #[test]
+    fn library_dirs() {
+        let dir = test_dir("libdir");
+        write(&dir, "lib/b.vh", "");
+        write(&dir, "lib/a.vlib", "");
+        write(&dir, "lib/c.sv", "");
+        let top = write(&dir, "top.f", "-y lib\n+libext+.vlib+vh\n-v lib/c.sv\n");
+
+        let filelist = read_filelist(&top).unwrap();
+
+        assert_eq!(filelist.files, vec![
+            dir.join("lib/c.sv"),
+            dir.join("lib/a.vlib"),
+            dir.join("lib/b.vh"),
+        ]);
+    }
+
+    #[test]
+    fn comments() {
+        let dir = test_dir("comment");
+        let top = write(&dir, "top.f", "// a.sv\nb.sv // c.sv\n# d.sv\ne.sv# f.sv\n  \n");
+
+        let filelist = read_filelist(&top).unwrap();
+
+        assert_eq!(filelist.files, vec![dir.join("b.sv"), dir.join("e.sv")]);
+    }
+
+    #[test]
+    fn env_vars() {
+        std::env::set_var("SVDOCGEN_TEST_RTL", "rtl");
+        std::env::set_var("SVDOCGEN_TEST_TOP", "top");
+        std::env::remove_var("SVDOCGEN_TEST_UNSET");
+
+        assert_eq!(expand_env_vars("$SVDOCGEN_TEST_RTL/${SVDOCGEN_TEST_TOP}.sv"), "rtl/top.sv");
+        assert_eq!(expand_env_vars("$(SVDOCGEN_TEST_RTL)/a.sv"), "rtl/a.sv");
+        assert_eq!(expand_env_vars("${SVDOCGEN_TEST_UNSET}/a.sv"), "/a.sv");
+
+        let dir = test_dir("env");
+        let top = write(&dir, "top.f", "$SVDOCGEN_TEST_RTL/${SVDOCGEN_TEST_TOP}.sv\n+incdir+${SVDOCGEN_TEST_RTL}\n");
+
+        let filelist = read_filelist(&top).unwrap();
+
+        assert_eq!(filelist.files, vec![dir.join("rtl/top.sv")]);
+        assert_eq!(filelist.incdirs, vec![dir.join("rtl")]);
+    }
+}
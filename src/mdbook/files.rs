@@ -3,25 +3,43 @@
 
 use std::fs;
 //use std::path;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::io;
 
 use crate::args;
 use crate::fsnode::FsNode;
+use crate::mdbook::filelist;
 
 
 /// Info about user's source files.
 ///
 pub struct SrcFiles {
     pub nodes: FsNode,
+    /// SV include paths.
+    pub incdirs: Vec<String>,
+    /// SV preprocessor macros as NAME and optional VALUE.
+    pub defines: Vec<(String, Option<String>)>,
+    /// Files from filelists, they are SV sources whatever their extension,
+    /// like `-y` library files with `+libext+.vlib`.
+    pub filelist_files: Vec<PathBuf>,
+}
+
+impl SrcFiles {
+    /// SV source file, with extension `sv` or `v` or from a filelist.
+    ///
+    pub fn is_sv_file(&self, path: &Path) -> bool {
+        let ext = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+
+        ext == "sv" || ext == "v" || self.filelist_files.iter().any(|file| file == path)
+    }
 }
 
 //pub struct DstFiles {
 //
 //}
 
-/// Data about all the files.
-///
+// Data about all the files.
+//
 //pub struct Files {
 //    pub src: SrcFiles,
 //    pub dst: DstFiles,
@@ -50,6 +68,31 @@ pub fn collect_sources(options: &args::ParsedOptions) -> Result<SrcFiles,String>
         }
     }
 
+    // Include paths to look up input files are also SV include paths.
+    let mut incdirs: Vec<String> = options.incdirs.clone();
+    incdirs.extend(options.includes.iter().cloned());
+
+    let mut defines: Vec<(String, Option<String>)> = options.defines.clone();
+    let mut filelist_files: Vec<PathBuf> = Vec::new();
+
+    // Add files, include paths and macros from filelists.
+    for filelist_path in &options.filelists {
+        let filelist = filelist::read_filelist(Path::new(filelist_path))?;
+
+        for file in filelist.files {
+            if !file.is_file() {
+                println!("Warning: can't find {:?} from filelist '{}'", file, filelist_path);
+            }
+            else {
+                filelist_files.push(file.clone());
+                inputs.push(file);
+            }
+        }
+
+        incdirs.extend(filelist.incdirs.iter().map(|dir| dir.to_string_lossy().to_string()));
+        defines.extend(filelist.defines);
+    }
+
     let mut nodes = FsNode {
         name: String::from(""),
         children: Vec::new()
@@ -74,9 +117,8 @@ pub fn collect_sources(options: &args::ParsedOptions) -> Result<SrcFiles,String>
         println!("traverse {}: {:?}", node.name, path);
         if path.is_dir() && node.children.is_empty() {
             println!("checking for files in {:?}", path);
-            match visit_dir_and_search_files(&mut nodes_with_files, path) {
-                Err(_) => println!("error"),
-                _ => (),
+            if visit_dir_and_search_files(&mut nodes_with_files, path).is_err() {
+                println!("error");
             }
         }
     };
@@ -85,11 +127,36 @@ pub fn collect_sources(options: &args::ParsedOptions) -> Result<SrcFiles,String>
 
     let src = SrcFiles {
         nodes: nodes_with_files,
+        incdirs,
+        defines,
+        filelist_files,
     };
 
     Ok(src)
 }
 
+/// Path of input file in the book, like `src/rtl/top.sv`.
+///
+/// Files outside of current directory are under `src/external`,
+/// with `..` as `__`, `/lib/cell.sv` is `src/external/lib/cell.sv`
+/// and `../lib/cell.sv` is `src/external/__/lib/cell.sv`.
+///
+pub fn book_path(path: &Path) -> PathBuf {
+    let mut book_path = PathBuf::from("src");
+    if path.is_absolute() || path.starts_with("..") {
+        book_path.push("external");
+    }
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => book_path.push(name),
+            Component::ParentDir => book_path.push("__"),
+            _ => (),
+        }
+    }
+
+    book_path
+}
+
 fn visit_dir_and_search_files(nodes: &mut FsNode, dir: &Path) -> io::Result<()> {
 
     if dir.is_dir() {
@@ -128,7 +195,7 @@ pub fn get_files_with_extensions(
         if path.is_file() {
             if let Some(ext) = path.extension() {
                 if extensions.contains(&ext.to_str().unwrap_or("")) {
-                    files_with_ext.push(&path);
+                    files_with_ext.push(path);
                 }
             }
         }
@@ -143,6 +210,22 @@ pub fn get_md_files(all_files: &FsNode) -> Result<FsNode,String> {
     get_files_with_extensions(all_files, &["md"])
 }
 
-pub fn get_sv_files(all_files: &FsNode) -> Result<FsNode,String> {
-    get_files_with_extensions(all_files, &["sv", "v"])
+/// SV source files, see `SrcFiles::is_sv_file`.
+///
+pub fn get_sv_files(src_files: &SrcFiles) -> Result<FsNode,String> {
+
+    let mut sv_files = FsNode {
+        name: String::from(""),
+        children: Vec::new()
+    };
+
+    let mut/*env*/ filter_files = |_node: &FsNode, path: &PathBuf, _level: usize| {
+        if path.is_file() && src_files.is_sv_file(path) {
+            sv_files.push(path);
+        }
+    };
+
+    src_files.nodes.traverse_top(&mut filter_files);
+
+    Ok(sv_files)
 }
@@ -0,0 +1,713 @@
+//! Find finite state machines in modules and draw their state diagrams.
+//!
+//! State machine is a `case (state)` statement whose items assign
+//! states to the state variable itself or to a next state variable,
+//! which is then assigned to the state variable.
+//! Conditions of transitions come from `if` statements and `?:` operators
+//! inside the `case` items, `default` item makes transitions from states
+//! not listed in other items. Reset state is a state assigned to the state
+//! variable under some condition outside of the `case` statement,
+//! such assignment of the next state variable is a transition from every state.
+//!
+
+use sv_parser::{unwrap_locate, Locate, RefNode, SyntaxTree};
+use sv_parser::{Statement, StatementOrNull, StatementItem, BlockingAssignment};
+use sv_parser::{CaseStatement, CaseItem, Expression, Primary, MintypmaxExpression};
+use sv_parser::{TypeDeclaration, BlockItemDeclaration, DataDeclaration, VariableDeclAssignment};
+
+use crate::mdbook::design::{Design, Location, StateMachine, StateTransition, TypedefKind};
+use crate::mdbook::extract;
+use crate::mdbook::svpar;
+
+/// Assignment in always block with conditions and `case` items it's under.
+///
+struct Assignment {
+    lhs: String,
+    value: String,
+    conditions: Vec<String>,
+    /// Index of `case` statement, labels of the item (`None` for `default`)
+    /// and number of conditions outside of the item.
+    cases: Vec<(usize, Option<Vec<String>>, usize)>,
+}
+
+/// `case` statement found in always block.
+///
+struct Case<'a> {
+    expr: String,
+    /// Labels of all non-default items.
+    labels: Vec<String>,
+    locate: Option<&'a Locate>,
+}
+
+#[derive(Default)]
+struct Walker<'a> {
+    assignments: Vec<Assignment>,
+    cases: Vec<Case<'a>>,
+    conditions: Vec<String>,
+    case_stack: Vec<(usize, Option<Vec<String>>, usize)>,
+}
+
+/// Find state machines in always blocks of the module.
+///
+pub fn extract_state_machines(
+    syntax_tree: &SyntaxTree,
+    source: &svpar::SourceFile,
+    module_node: &RefNode
+) -> Vec<StateMachine>
+{
+    let mut walker = Walker::default();
+
+    for node in module_node.clone().into_iter() {
+        if let RefNode::AlwaysConstruct(x) = node {
+            walker.walk_statement(syntax_tree, &x.nodes.1);
+        }
+    }
+
+    let mut machines: Vec<StateMachine> = Vec::new();
+
+    for (index, case) in walker.cases.iter().enumerate() {
+        if !is_identifier(&case.expr) || machines.iter().any(|m| m.state_var == case.expr) {
+            continue;
+        }
+
+        if let Some(mut machine) = find_state_machine(&walker.assignments, index, case) {
+            let (state_type, members) = declared_type(syntax_tree, module_node, &machine.state_var);
+            if let Some(members) = members {
+                merge_states(&mut machine.states, &members);
+            }
+            machine.state_type = state_type;
+            if let Some(locate) = case.locate {
+                machine.location = source.location(syntax_tree, locate);
+            }
+            machines.push(machine);
+        }
+    }
+
+    machines
+}
+
+/// States of machine, ordered as members of its enum type if it's declared in a package.
+///
+pub fn state_names(design: &Design, machine: &StateMachine) -> Vec<String> {
+    let mut states = machine.states.clone();
+
+    if let Some(state_type) = &machine.state_type {
+        let (pkg_name, type_name) = match state_type.rsplit_once("::") {
+            Some((pkg, name)) => (Some(pkg), name),
+            None => (None, state_type.as_str()),
+        };
+        let typedef = design.packages.iter()
+            .filter(|pkg| pkg_name.is_none_or(|name| pkg.name == name))
+            .flat_map(|pkg| pkg.typedefs.iter())
+            .find(|t| t.name == type_name && t.kind == TypedefKind::Enum);
+        if let Some(typedef) = typedef {
+            let members: Vec<String> = typedef.members.iter().map(|m| m.name.clone()).collect();
+            merge_states(&mut states, &members);
+        }
+    }
+
+    states
+}
+
+/// State diagram as Mermaid `stateDiagram-v2` inside `<div class="mermaid">`.
+///
+pub fn to_mermaid(machine: &StateMachine, states: &[String]) -> String {
+    let mut text = String::new();
+
+    text.push_str("<div class=\"mermaid\">\nstateDiagram-v2\n");
+
+    let id = |state: &str| states.iter().position(|s| s == state).map(|i| format!("s{}", i));
+
+    for (i, state) in states.iter().enumerate() {
+        text.push_str(format!("  state \"{}\" as s{}\n", mermaid_escape(state), i).as_str());
+    }
+
+    if let Some(reset) = machine.reset_state.as_deref().and_then(id) {
+        text.push_str(format!("  [*] --> {}\n", reset).as_str());
+    }
+
+    for tr in &machine.transitions {
+        if let (Some(from), Some(to)) = (id(&tr.from), id(&tr.to)) {
+            match &tr.condition {
+                Some(cond) => text.push_str(format!("  {} --> {} : {}\n",
+                    from, to, mermaid_escape(cond)).as_str()),
+                None => text.push_str(format!("  {} --> {}\n", from, to).as_str()),
+            }
+        }
+    }
+
+    text.push_str("</div>\n");
+
+    text
+}
+
+impl<'a> Walker<'a> {
+
+    fn walk_statement_or_null(&mut self, syntax_tree: &SyntaxTree, stmt: &'a StatementOrNull) {
+        if let StatementOrNull::Statement(x) = stmt {
+            self.walk_statement(syntax_tree, x);
+        }
+    }
+
+    fn walk_statement(&mut self, syntax_tree: &SyntaxTree, stmt: &'a Statement) {
+        match &stmt.nodes.2 {
+            StatementItem::BlockingAssignment(x) => match &x.0 {
+                BlockingAssignment::Variable(a) =>
+                    self.add_assignment(syntax_tree, RefNode::from(&a.nodes.0), &a.nodes.3),
+                BlockingAssignment::OperatorAssignment(a) if syntax_tree.get_str_trim(&a.nodes.1) == Some("=") =>
+                    self.add_assignment(syntax_tree, RefNode::from(&a.nodes.0), &a.nodes.2),
+                _ => (),
+            },
+            StatementItem::NonblockingAssignment(x) =>
+                self.add_assignment(syntax_tree, RefNode::from(&x.0.nodes.0), &x.0.nodes.3),
+            StatementItem::SeqBlock(x) => {
+                // Parser takes leading `a = b;` in a block for a declaration of `a`
+                // with implicit type, so it's an assignment if nothing is declared.
+                for decl in &x.nodes.2 {
+                    if let BlockItemDeclaration::Data(d) = decl {
+                        if let DataDeclaration::Variable(v) = &d.nodes.1 {
+                            let is_implicit = v.nodes.0.is_none() && v.nodes.1.is_none() && v.nodes.2.is_none()
+                                && syntax_tree.get_str_trim(&v.nodes.3).is_none();
+                            for var in (v.nodes.4).nodes.0.contents() {
+                                if let (true, VariableDeclAssignment::Variable(var)) = (is_implicit, var) {
+                                    if let Some((_, value)) = &var.nodes.2 {
+                                        self.add_assignment(syntax_tree, RefNode::from(&var.nodes.0), value);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                for item in &x.nodes.3 {
+                    self.walk_statement_or_null(syntax_tree, item);
+                }
+            }
+            StatementItem::ProceduralTimingControlStatement(x) =>
+                self.walk_statement_or_null(syntax_tree, &x.nodes.1),
+            StatementItem::ConditionalStatement(x) => {
+                let depth = self.conditions.len();
+                let mut negated: Vec<String> = Vec::new();
+
+                let cond = expr_str(syntax_tree, RefNode::from(&(x.nodes.2).nodes.1));
+                self.conditions.push(strip_parens(&cond).to_string());
+                self.walk_statement_or_null(syntax_tree, &x.nodes.3);
+                self.conditions.truncate(depth);
+                negated.push(negate(&cond));
+
+                for (_, _, pred, stmt) in &x.nodes.4 {
+                    let cond = expr_str(syntax_tree, RefNode::from(&pred.nodes.1));
+                    self.conditions.extend(negated.iter().cloned());
+                    self.conditions.push(strip_parens(&cond).to_string());
+                    self.walk_statement_or_null(syntax_tree, stmt);
+                    self.conditions.truncate(depth);
+                    negated.push(negate(&cond));
+                }
+
+                if let Some((_, stmt)) = &x.nodes.5 {
+                    self.conditions.extend(negated.iter().cloned());
+                    self.walk_statement_or_null(syntax_tree, stmt);
+                    self.conditions.truncate(depth);
+                }
+            }
+            StatementItem::CaseStatement(x) => {
+                if let CaseStatement::Normal(case) = x.as_ref() {
+                    let mut items: Vec<&CaseItem> = vec![&case.nodes.3];
+                    items.extend(case.nodes.4.iter());
+
+                    let item_labels = |item: &CaseItem| match item {
+                        CaseItem::NonDefault(i) => Some(i.nodes.0.contents().iter()
+                            .map(|label| expr_str(syntax_tree, RefNode::from(*label)))
+                            .collect::<Vec<String>>()),
+                        CaseItem::Default(_) => None,
+                    };
+
+                    let index = self.cases.len();
+                    self.cases.push(Case {
+                        expr: expr_str(syntax_tree, RefNode::from(&(case.nodes.2).nodes.1)),
+                        labels: items.iter().filter_map(|item| item_labels(item)).flatten().collect(),
+                        locate: unwrap_locate!(&case.nodes.1),
+                    });
+
+                    for item in items {
+                        let stmt = match item {
+                            CaseItem::NonDefault(i) => &i.nodes.2,
+                            CaseItem::Default(i) => &i.nodes.2,
+                        };
+                        self.case_stack.push((index, item_labels(item), self.conditions.len()));
+                        self.walk_statement_or_null(syntax_tree, stmt);
+                        self.case_stack.pop();
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Add assignment to simple variable, `?:` in value makes one assignment per branch.
+    ///
+    fn add_assignment(&mut self, syntax_tree: &SyntaxTree, lhs: RefNode, value: &Expression) {
+        let lhs = expr_str(syntax_tree, lhs);
+        if !is_identifier(&lhs) {
+            return;
+        }
+
+        let mut values: Vec<(String, Vec<String>)> = Vec::new();
+        split_conditional(syntax_tree, value, &mut Vec::new(), &mut values);
+
+        for (value, conditions) in values {
+            let mut all_conditions = self.conditions.clone();
+            all_conditions.extend(conditions);
+            self.assignments.push(Assignment {
+                lhs: lhs.clone(),
+                value,
+                conditions: all_conditions,
+                cases: self.case_stack.clone(),
+            });
+        }
+    }
+}
+
+/// Values of expression with conditions selecting them, like `A` if `c` and `B` if `!c` for `c ? A : B`.
+///
+fn split_conditional(
+    syntax_tree: &SyntaxTree,
+    expr: &Expression,
+    conditions: &mut Vec<String>,
+    values: &mut Vec<(String, Vec<String>)>
+)
+{
+    match expr {
+        Expression::ConditionalExpression(x) => {
+            let cond = expr_str(syntax_tree, RefNode::from(&x.nodes.0));
+            conditions.push(strip_parens(&cond).to_string());
+            split_conditional(syntax_tree, &x.nodes.3, conditions, values);
+            conditions.pop();
+            conditions.push(negate(&cond));
+            split_conditional(syntax_tree, &x.nodes.5, conditions, values);
+            conditions.pop();
+        }
+        Expression::Primary(p) => match p.as_ref() {
+            Primary::MintypmaxExpression(m) => match &(m.nodes.0).nodes.1 {
+                MintypmaxExpression::Expression(e) => split_conditional(syntax_tree, e, conditions, values),
+                _ => values.push((expr_str(syntax_tree, RefNode::from(expr)), conditions.clone())),
+            },
+            _ => values.push((expr_str(syntax_tree, RefNode::from(expr)), conditions.clone())),
+        },
+        _ => values.push((expr_str(syntax_tree, RefNode::from(expr)), conditions.clone())),
+    }
+}
+
+/// State machine of `case` statement, if its items assign states.
+///
+fn find_state_machine(assignments: &[Assignment], index: usize, case: &Case) -> Option<StateMachine> {
+    let state_var = &case.expr;
+    let in_case = |a: &&Assignment| a.cases.iter().any(|c| c.0 == index);
+
+    // Variable which gets most of its values from `case` labels is the next state.
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for a in assignments.iter().filter(in_case) {
+        if case.labels.contains(&a.value) || &a.value == state_var {
+            match counts.iter_mut().find(|c| c.0 == a.lhs) {
+                Some(c) => c.1 += 1,
+                None => counts.push((&a.lhs, 1)),
+            }
+        }
+    }
+    let next_var = counts.iter().max_by_key(|c| c.1)?.0;
+
+    // Next state must become the state.
+    if next_var != state_var && !assignments.iter().any(|a| &a.lhs == state_var && a.value == next_var) {
+        return None;
+    }
+
+    let mut states = case.labels.clone();
+    let mut transitions: Vec<StateTransition> = Vec::new();
+
+    for a in assignments.iter().filter(in_case).filter(|a| a.lhs == next_var) {
+        let (_, labels, depth) = a.cases.iter().find(|c| c.0 == index).unwrap();
+        let from: Vec<String> = match labels {
+            Some(labels) => labels.clone(),
+            None => Vec::new(),
+        };
+        // Value which is not a state, like `state + 1`, can't be drawn.
+        if !is_identifier(&a.value) && !is_number(&a.value) {
+            return None;
+        }
+        let mut conditions: Vec<&str> = Vec::new();
+        for cond in &a.conditions[*depth..] {
+            if !conditions.contains(&cond.as_str()) {
+                conditions.push(cond);
+            }
+        }
+        let condition = match conditions.is_empty() {
+            true => None,
+            false => Some(conditions.join(" && ")),
+        };
+        if labels.is_none() {
+            // `default` item is for states not listed in other items,
+            // added after all states are known.
+            transitions.push(StateTransition { from: String::new(), to: a.value.clone(), condition });
+            continue;
+        }
+        for from in from {
+            let to = match &a.value == state_var {
+                true => from.clone(),
+                false => a.value.clone(),
+            };
+            if !states.contains(&to) {
+                states.push(to.clone());
+            }
+            transitions.push(StateTransition { from, to, condition: condition.clone() });
+        }
+    }
+
+    let reset = assignments.iter()
+        .filter(|a| !in_case(a) && &a.lhs == state_var && !a.conditions.is_empty())
+        .find(|a| states.contains(&a.value));
+
+    let default_from: Vec<String> = states.iter()
+        .filter(|s| !case.labels.contains(s))
+        .cloned()
+        .collect();
+    let mut all_transitions: Vec<StateTransition> = Vec::new();
+    for tr in transitions {
+        let from = match tr.from.is_empty() {
+            true => default_from.clone(),
+            false => vec![tr.from.clone()],
+        };
+        for from in from {
+            let to = match &tr.to == state_var {
+                true => from.clone(),
+                false => tr.to.clone(),
+            };
+            let exists = all_transitions.iter()
+                .any(|t| t.from == from && t.to == to && t.condition == tr.condition);
+            if !exists {
+                all_transitions.push(StateTransition { from, to, condition: tr.condition.clone() });
+            }
+        }
+    }
+
+    // Next state assigned outside of `case`, like `if (abort) next = IDLE`.
+    if next_var != state_var {
+        for a in assignments.iter()
+            .filter(|a| !in_case(a) && a.lhs == next_var && !a.conditions.is_empty())
+            .filter(|a| states.contains(&a.value))
+        {
+            let condition = Some(a.conditions.join(" && "));
+            for from in &states {
+                let exists = all_transitions.iter()
+                    .any(|t| &t.from == from && t.to == a.value && t.condition == condition);
+                if !exists {
+                    all_transitions.push(StateTransition {
+                        from: from.clone(), to: a.value.clone(), condition: condition.clone() });
+                }
+            }
+        }
+    }
+
+    if states.len() < 2 {
+        return None;
+    }
+
+    Some(StateMachine {
+        state_var: state_var.clone(),
+        next_var: match next_var == state_var {
+            true => None,
+            false => Some(next_var.to_string()),
+        },
+        state_type: None,
+        states,
+        reset_state: reset.map(|a| a.value.clone()),
+        reset_condition: reset.map(|a| a.conditions.join(" && ")),
+        transitions: all_transitions,
+        location: Location { file: String::new(), line: 0 },
+    })
+}
+
+/// Declared type of variable and enum members, if the type is enum declared in the module.
+///
+fn declared_type(
+    syntax_tree: &SyntaxTree,
+    module_node: &RefNode,
+    var_name: &str
+) -> (Option<String>, Option<Vec<String>>)
+{
+    let enum_members = |node: RefNode| -> Option<Vec<String>> {
+        let members: Vec<String> = node.into_iter()
+            .filter_map(|n| match n {
+                RefNode::EnumIdentifier(_) => svpar::get_str_trim(syntax_tree, &n),
+                _ => None,
+            })
+            .collect();
+        match members.is_empty() {
+            true => None,
+            false => Some(members),
+        }
+    };
+
+    for node in svpar::scope_nodes(module_node) {
+        if !matches!(node, RefNode::NetDeclaration(_) | RefNode::DataDeclarationVariable(_)) {
+            continue;
+        }
+        let declares = extract::get_identifiers_and_dims(syntax_tree, node.clone()).iter()
+            .any(|(name, _, _)| name == var_name);
+        if !declares {
+            continue;
+        }
+
+        let port_type = extract::get_port_type(syntax_tree, node.clone());
+        if let Some(members) = enum_members(node.clone()) {
+            return (port_type.data_type, Some(members));
+        }
+
+        // Enum typedef in the module itself.
+        let type_name = match port_type.type_name {
+            Some(type_name) => type_name,
+            None => return (port_type.data_type, None),
+        };
+        for node in svpar::scope_nodes(module_node) {
+            if let RefNode::TypeDeclaration(TypeDeclaration::DataType(t)) = node {
+                let name = svpar::get_str_trim(syntax_tree, &RefNode::from(&t.nodes.2));
+                if name.as_ref() == Some(&type_name) {
+                    return (Some(type_name), enum_members(RefNode::from(&t.nodes.1)));
+                }
+            }
+        }
+
+        return (Some(type_name), None);
+    }
+
+    (None, None)
+}
+
+/// Add states which are not in the list, keeping the order of `members`.
+///
+fn merge_states(states: &mut Vec<String>, members: &[String]) {
+    let mut merged: Vec<String> = members.to_vec();
+    merged.extend(states.iter().filter(|s| !members.contains(s)).cloned());
+    *states = merged;
+}
+
+fn expr_str(syntax_tree: &SyntaxTree, node: RefNode) -> String {
+    svpar::get_str_trim(syntax_tree, &node)
+        .map(|s| s.split_whitespace().collect::<Vec<&str>>().join(" "))
+        .unwrap_or_default()
+}
+
+/// Condition without enclosing parentheses, like `tms` for `(tms)`.
+///
+fn strip_parens(cond: &str) -> &str {
+    let inner = match cond.strip_prefix('(').and_then(|c| c.strip_suffix(')')) {
+        Some(inner) => inner,
+        None => return cond,
+    };
+
+    // `(a) || (b)` is not enclosed as a whole.
+    let mut depth = 0;
+    for c in inner.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return cond,
+            ')' => depth -= 1,
+            _ => (),
+        }
+    }
+
+    strip_parens(inner.trim())
+}
+
+fn negate(cond: &str) -> String {
+    let cond = strip_parens(cond);
+    match cond.strip_prefix('!') {
+        Some(inner) if is_identifier(inner) => inner.to_string(),
+        _ if is_identifier(cond) => format!("!{}", cond),
+        _ => format!("!({})", cond),
+    }
+}
+
+fn is_identifier(text: &str) -> bool {
+    text.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$' || c == ':')
+}
+
+fn is_number(text: &str) -> bool {
+    text.chars().next().is_some_and(|c| c.is_ascii_digit() || c == '\'')
+}
+
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+        .replace(':', "#58;")
+        .replace(';', "#59;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sv_parser::parse_sv_str;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    /// State machines of the first module in `source`.
+    ///
+    fn machines(source: &str) -> Vec<StateMachine> {
+        let (syntax_tree, _) = parse_sv_str(source, PathBuf::from("test.sv"),
+            &HashMap::new(), &[] as &[PathBuf], false, false).unwrap();
+        let module = syntax_tree.into_iter()
+            .find(|node| matches!(node, RefNode::ModuleDeclarationAnsi(_)))
+            .unwrap();
+
+        extract_state_machines(&syntax_tree, &svpar::SourceFile::new("test.sv"), &module)
+    }
+
+    fn transitions(machine: &StateMachine) -> Vec<(&str, &str, Option<&str>)> {
+        machine.transitions.iter()
+            .map(|t| (t.from.as_str(), t.to.as_str(), t.condition.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn async_reset() {
+        let machines = machines("module M(input clk, rst_n, go);
+            typedef enum logic [1:0] { IDLE, RUN, DONE } state_t;
+            state_t state;
+            always_ff @(posedge clk or negedge rst_n) begin
+                if (!rst_n)
+                    state <= IDLE;
+                else
+                    case (state)
+                        IDLE: if (go) state <= RUN;
+                        RUN: state <= DONE;
+                        DONE: state <= IDLE;
+                    endcase
+            end
+            endmodule");
+
+        assert_eq!(machines.len(), 1);
+        let machine = &machines[0];
+        assert_eq!(machine.state_var, "state");
+        assert_eq!(machine.next_var, None);
+        assert_eq!(machine.state_type.as_deref(), Some("state_t"));
+        assert_eq!(machine.states, vec!["IDLE", "RUN", "DONE"]);
+        assert_eq!(machine.reset_state.as_deref(), Some("IDLE"));
+        assert_eq!(machine.reset_condition.as_deref(), Some("!rst_n"));
+        assert_eq!(transitions(machine), vec![
+            ("IDLE", "RUN", Some("go")),
+            ("RUN", "DONE", None),
+            ("DONE", "IDLE", None),
+        ]);
+        assert_eq!(machine.location.line, 8);
+    }
+
+    #[test]
+    fn ternary_transitions() {
+        let machines = machines("module M(input clk, go, stop);
+            logic [1:0] state;
+            always_ff @(posedge clk) begin
+                case (state)
+                    2'd0: state <= go ? 2'd1 : 2'd0;
+                    2'd1: state <= stop ? 2'd0 : 2'd1;
+                endcase
+            end
+            endmodule");
+
+        assert_eq!(machines.len(), 1);
+        assert_eq!(machines[0].states, vec!["2'd0", "2'd1"]);
+        assert_eq!(machines[0].reset_state, None);
+        assert_eq!(transitions(&machines[0]), vec![
+            ("2'd0", "2'd1", Some("go")),
+            ("2'd0", "2'd0", Some("!go")),
+            ("2'd1", "2'd0", Some("stop")),
+            ("2'd1", "2'd1", Some("!stop")),
+        ]);
+    }
+
+    #[test]
+    fn default_item() {
+        let machines = machines("module M(input clk, go);
+            typedef enum { A, B, C } state_t;
+            state_t state, next;
+            always_ff @(posedge clk) state <= next;
+            always_comb begin
+                next = state;
+                case (state)
+                    A: if (go) next = B;
+                    B: next = C;
+                    default: next = A;
+                endcase
+            end
+            endmodule");
+
+        assert_eq!(machines.len(), 1);
+        assert_eq!(machines[0].next_var.as_deref(), Some("next"));
+        assert_eq!(machines[0].states, vec!["A", "B", "C"]);
+        assert_eq!(transitions(&machines[0]), vec![
+            ("A", "B", Some("go")),
+            ("B", "C", None),
+            ("C", "A", None),
+        ]);
+    }
+
+    #[test]
+    fn unique_case() {
+        let machines = machines("module M(input clk, go);
+            typedef enum { A, B } state_t;
+            state_t state;
+            always_ff @(posedge clk) begin
+                unique case (state)
+                    A: if (go) state <= B;
+                    B: state <= A;
+                endcase
+            end
+            endmodule");
+
+        assert_eq!(machines.len(), 1);
+        assert_eq!(transitions(&machines[0]), vec![
+            ("A", "B", Some("go")),
+            ("B", "A", None),
+        ]);
+    }
+
+    #[test]
+    fn next_state_outside_case() {
+        let machines = machines("module M(input clk, go, abort);
+            typedef enum { A, B } state_t;
+            state_t state, next;
+            always_ff @(posedge clk) state <= next;
+            always_comb begin
+                case (state)
+                    A: if (go) next = B; else next = A;
+                    B: next = A;
+                endcase
+                if (abort) next = A;
+            end
+            endmodule");
+
+        assert_eq!(machines.len(), 1);
+        assert_eq!(machines[0].reset_state, None);
+        assert_eq!(transitions(&machines[0]), vec![
+            ("A", "B", Some("go")),
+            ("A", "A", Some("!go")),
+            ("B", "A", None),
+            ("A", "A", Some("abort")),
+            ("B", "A", Some("abort")),
+        ]);
+    }
+
+    #[test]
+    fn no_state_machine() {
+        let machines = machines("module M(input clk, input [1:0] sel, input [3:0] d, output logic q);
+            always_ff @(posedge clk) begin
+                case (sel)
+                    2'd0: q <= d[0];
+                    2'd1: q <= d[1];
+                    default: q <= d[3];
+                endcase
+            end
+            endmodule");
+
+        assert!(machines.is_empty());
+    }
+}
@@ -1,6 +1,7 @@
 //! Parse SV files and generate mdBook sources.
 //!
-//!
+//! Pages are generated into in-memory `Book` first,
+//! `write_mdbook` writes them as mdBook sources.
 //!
 
 use std::fs;
@@ -9,7 +10,9 @@ use std::io::{BufWriter, Write};
 
 use crate::args;
 use crate::mdbook;
+use crate::mdbook::book::{Book, Chapter, Page};
 use crate::mdbook::files::SrcFiles;
+use crate::mdbook::design::Design;
 use crate::fsnode::FsNode;
 
 const MDBOOK_SRC_DIR: &str = "src";
@@ -17,7 +20,7 @@ const MDBOOK_SUMMARY_MD: &str = "SUMMARY.md";
 const MDBOOK_BOOK_TOML: &str = "book.toml";
 
 #[svgbobdoc::transform]
-/// Generate mdBook sources.
+/// Generate all pages and the table of contents in memory.
 ///
 /// ```svgbob
 ///     .--.---.
@@ -26,79 +29,97 @@ const MDBOOK_BOOK_TOML: &str = "book.toml";
 ///     |   \ \|
 ///     '----'-'
 /// ```
-pub fn generate(options: &args::ParsedOptions) -> Result<(),String> {
+///
+/// Pages are the same for every output format,
+/// see `render.rs` for how they are written.
+///
+pub fn generate_book(
+    options: &args::ParsedOptions,
+    src_files: &SrcFiles,
+    design: &Design
+) -> Result<Book,String>
+{
+    let mut book = Book::new(&options.project_name);
+
+    add_src_files(&mut book, src_files)?;
+
+    book.user_chapters = list_users_md_docs(src_files)?;
 
+    book.chapters = create_sv_docs(&mut book, src_files, design, options)?;
+
+    for page in &mut book.pages {
+        page.text = mdbook::diagram::render_diagrams(&page.text);
+    }
+
+    Ok(book)
+}
+
+/// Write mdBook sources: pages, copies of input files, `SUMMARY.md` and `book.toml`.
+///
+pub fn write_mdbook(output_dir: &str, book: &Book) -> Result<(),String> {
 
-    match fs::create_dir_all(&options.output_dir) {
+    match fs::create_dir_all(output_dir) {
         Err(e) => { println!("Can't create output directory '{}' error: {}",
-                        &options.output_dir, e);
+                        output_dir, e);
                     return Err(e.to_string()); },
-        Ok(_) => println!("Created output directory '{}'", &options.output_dir),
+        Ok(_) => println!("Created output directory '{}'", output_dir),
     }
 
-    let mdbook_src_dir = Path::new(&options.output_dir).join(MDBOOK_SRC_DIR);
-    let mdbook_src_dir = mdbook_src_dir.to_str().unwrap();
+    let mdbook_src_dir = Path::new(output_dir).join(MDBOOK_SRC_DIR);
 
-    match fs::create_dir_all(mdbook_src_dir) {
+    match fs::create_dir_all(&mdbook_src_dir) {
         Err(e) => { println!("Can't create '{}' error: {}",
-                        mdbook_src_dir, e);
+                        mdbook_src_dir.display(), e);
                     return Err(e.to_string()); },
-        Ok(_) => println!("Created directory '{}'", mdbook_src_dir),
+        Ok(_) => println!("Created directory '{}'", mdbook_src_dir.display()),
     }
 
-    let src_files = mdbook::files::collect_sources(options)?;
+    copy_assets(&mdbook_src_dir, book)?;
+    write_pages(&mdbook_src_dir, &book.pages)?;
+    write_pages(&mdbook_src_dir, &book.files)?;
 
-    copy_src_files(mdbook_src_dir, &src_files)?;
-
-    create_summary_md(mdbook_src_dir, &src_files)?;
+    if let Err(e) = fs::write(mdbook_src_dir.join(MDBOOK_SUMMARY_MD), book.summary_md()) {
+        return Err(e.to_string());
+    }
 
-    create_book_toml(&options.output_dir, &options.project_name)?;
+    create_book_toml(output_dir, &book.title)?;
 
     Ok(())
 }
 
-/// Create mdBook SUMMARY.md file.
-///
-/// The summary file is used by mdBook to know what chapters to include,
-/// in what order they should appear, what their hierarchy is
-/// and where the source files are. Without this file, there is no book.
+/// Copy input files of the book into `dir/src`.
 ///
-fn create_summary_md(mdbook_src_dir: &str, src_files: &SrcFiles) -> Result<(),String> {
+pub fn copy_assets(dir: &Path, book: &Book) -> Result<(),String> {
 
-    let summary_fname = Path::new(&mdbook_src_dir).join(MDBOOK_SUMMARY_MD);
-    let summary_fname = summary_fname.to_str().unwrap();
-
-    let file = match fs::OpenOptions::new()
-        .read(false)
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(summary_fname) {
-            Err(e) => return Err(e.to_string()),
-            Ok(file) => file,
-    };
-
-    let mut text_buf: Vec<String> = Vec::new();
-    text_buf.push("# Summary\n".to_string());
-
-    text_buf.push("\n- [User's Documentation]()\n".to_string());
-    let users_md_docs = list_users_md_docs(mdbook_src_dir, src_files)?;
-    for users_doc in &users_md_docs {
-        text_buf.push(format!("{:indent$}- [{}]({})\n", " ",
-            users_doc.1, users_doc.2, indent=users_doc.0*2));
+    for asset in &book.assets {
+        let target = dir.join(mdbook::files::book_path(asset));
+        if let Some(parent) = target.parent() {
+            if let Err(e) = fs::create_dir_all(parent) {
+                return Err(e.to_string());
+            }
+        }
+        match fs::copy(asset, &target) {
+            Err(e) => println!("error {:?} copying {:?} {:?}", e, asset, &target),
+            Ok(nr_bytes) => println!("copied {} bytes from {:?} to {:?}", nr_bytes, asset, &target),
+        }
     }
 
-    text_buf.push("\n---\n\n".to_string());
-
-    let mut svtext = create_sv_docs(mdbook_src_dir, &src_files)?;
-    text_buf.append(&mut svtext);
+    Ok(())
+}
 
-    let mut writer = BufWriter::new(file);
+/// Write pages or generated files into `dir`.
+///
+pub fn write_pages(dir: &Path, pages: &[Page]) -> Result<(),String> {
 
-    for text in &text_buf {
-        match writer.write_all(text.as_bytes()) {
-            Err(e) => return Err(e.to_string()),
-            Ok(_) => (),
+    for page in pages {
+        let target = dir.join(&page.path);
+        if let Some(parent) = target.parent() {
+            if let Err(e) = fs::create_dir_all(parent) {
+                return Err(e.to_string());
+            }
+        }
+        if let Err(e) = fs::write(&target, &page.text) {
+            return Err(e.to_string());
         }
     }
 
@@ -139,370 +160,125 @@ additional-js = ["loadwavedrom.js"]
 
     let mut writer = BufWriter::new(file);
 
-    match writer.write_all(data.as_bytes()) {
-        Err(e) => return Err(e.to_string()),
-        Ok(_) => (),
+    if let Err(e) = writer.write_all(data.as_bytes()) {
+        return Err(e.to_string());
     }
 
     Ok(())
 }
 
-/// Copy all input files into mdBook `src` directory.
+/// Add all input files to the book.
 ///
+/// Markdown files become pages, other files are copied as is,
+/// every SV file also gets a page with its source.
 ///
-fn copy_src_files(path: &str, files: &SrcFiles) -> Result<(),String> {
-
-    let target_dir = Path::new(&path).join("src");
-    let target_dir = target_dir.to_str().unwrap();
+fn add_src_files(book: &mut Book, files: &SrcFiles) -> Result<(),String> {
 
-    match fs::create_dir_all(target_dir) {
-        Err(e) => return Err(e.to_string()),
-        Ok(_) => (),
-    }
+    let mut result: Result<(),String> = Ok(());
 
-    let mut/*env*/ create_dirs = |_node: &FsNode, path: &PathBuf, _level: usize| {
-        if path.is_dir() {
-            if let Some(path_str) = path.to_str() {
-                if let Some(target_str) = Path::new(&target_dir).join(&path_str).to_str() {
-                    match fs::create_dir_all(target_str) {
-                        Err(e) => println!("error {}", e.to_string()),
-                        Ok(_) => println!("create dir: {}", target_str),
-                    }
-                }
-            }
+    let mut/*env*/ add_files = |_node: &FsNode, path: &PathBuf, _level: usize| {
+        if !path.is_file() || result.is_err() {
+            return;
         }
-    };
 
-    files.nodes.traverse_top(&mut create_dirs);
+        let src_path = mdbook::files::book_path(path);
+        let src_path_str = src_path.to_str().unwrap();
+        let ext = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
 
-    let mut/*env*/ copy_files = |_node: &FsNode, path: &PathBuf, _level: usize| {
-        if path.is_file() { if  let Some(fname) = path.file_name() {
-            let target = if let Some(parent) = path.parent() {
-                Path::new(&target_dir).join(parent).join(fname)
-            }
-            else {
-                Path::new(&target_dir).join(fname)
-            };
-            match fs::copy(&path, &target) {
-                Err(e) => println!("error {:?} copying {:?} {:?}", e, &path, &target),
-                Ok(nr_bytes) => println!("copied {} bytes from {:?} to {:?}", nr_bytes, &path, &target),
+        if ext == "md" {
+            match fs::read_to_string(path) {
+                Err(e) => result = Err(format!("can't read '{}': {}", path.display(), e)),
+                Ok(text) => book.add_page(src_path_str, text),
             }
+            return;
+        }
 
-            let mut ext = target.extension().unwrap().to_os_string();
-            if ext.eq("v") || ext.eq("sv") {
-                ext.push(".md");
-                let mut sv_md = target.clone();
-                sv_md.set_extension(ext);
-                println!("generate {:?}", sv_md);
-                let fname_str = fname.to_str().unwrap();
-                let txt = format!(
-                    "## {}\n\n```verilog\n{{{{#include {}}}}}\n```\n",
-                    fname_str, fname_str);
-                fs::write(&sv_md, txt).expect("failed to create file");
-            }
-        }}
+        book.assets.push(path.clone());
+
+        if files.is_sv_file(path) {
+            let fname_str = path.file_name().unwrap().to_str().unwrap();
+            let source = fs::read_to_string(path).unwrap_or_default();
+            let txt = format!(
+                "## {}\n\n```verilog\n{}\n```\n",
+                fname_str, source.strip_suffix('\n').unwrap_or(&source));
+            book.add_page(&format!("{}.md", src_path_str), txt);
+        }
     };
 
-    files.nodes.traverse_top(&mut copy_files);
+    files.nodes.traverse_top(&mut add_files);
 
-    Ok(())
+    result
 }
 
-/// Create files.md file that lists all input files.
+/// Create files.md page that lists all input files.
 ///
 ///
-fn create_files_md(path: &str, files: &FsNode) -> Result<String,String> {
-
-    let fname = Path::new(&path).join("files.md");
-    let fname = fname.to_str().unwrap();
-
-    let file = match fs::OpenOptions::new()
-        .read(false)
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(fname) {
-            Err(e) => return Err(e.to_string()),
-            Ok(file) => file,
-    };
+fn create_files_md(book: &mut Book, files: &FsNode) -> Vec<Chapter> {
 
-    fn show_src(data: &mut Vec<String>, path: &str) {
-        let src_path = Path::new("src").join(path);
-        let path_str = src_path.to_str().unwrap();
-        let mut path_str_md = String::from(path_str);
-        path_str_md.push_str(".md");
-        data.push(format!("- [{}]({})\n", path, path_str_md));
+    let mut list: Vec<Chapter> = Vec::new();
 
-        //data.push(format!("\n```verilog\n"));
-        //data.push(format!("{{{{#include {}}}}}\n", src_path.to_str().unwrap()));
-        //data.push(format!("```\n\n"));
-    }
-
-    fn add_to_list(list: &mut String, path: &str) {
-        let src_path = Path::new("src").join(path);
-        let path_str = src_path.to_str().unwrap();
-        let mut path_str_md = String::from(path_str);
-        path_str_md.push_str(".md");
-        list.push_str(format!("  - [{}]({})\n", path, path_str_md).as_str());
-    }
-
-    let mut list = String::new();
-
-    let mut data: Vec<String> = Vec::new();
-    data.push("# Files\n\n".to_string());
+    let mut text = String::from("# Files\n\n");
 
     let mut/*env*/ print_files = |_node: &FsNode, path: &PathBuf, _level: usize| {
         if path.is_file() {
             if let Some(path_str) = path.to_str() {
-                //data.push(format!("- {}\n", path_str));
-                show_src(&mut data, path_str);
-                add_to_list(&mut list, path_str);
+                let src_path = mdbook::files::book_path(path);
+                let path_str_md = format!("{}.md", src_path.to_str().unwrap());
+                text.push_str(format!("- [{}]({})\n", path_str, path_str_md).as_str());
+                list.push(Chapter { level: 2, title: path_str.to_string(), path: Some(path_str_md) });
             }
         }
     };
 
     files.traverse_top(&mut print_files);
 
-    let mut writer = BufWriter::new(file);
+    book.add_page("files.md", text);
 
-    for d in &data {
-        match writer.write_all(d.as_bytes()) {
-            Err(e) => return Err(e.to_string()),
-            Ok(_) => (),
-        }
-    }
-
-    Ok(list)
+    list
 }
 
-/// Create modules.md file that lists all input files.
-///
+/// Create index page `path` with `title` heading and entity pages `text_chunk`,
+/// returns chapters of the entities.
 ///
-fn create_modules_md(
-    output_path: &str,
-    files: &FsNode
-) -> Result<Vec<(String,String,String)>,String>
-{
-
-    let fname = Path::new(&output_path).join("modules.md");
-    let fname = fname.to_str().unwrap();
-
-    let file = match fs::OpenOptions::new()
-        .read(false)
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(fname) {
-            Err(e) => return Err(e.to_string()),
-            Ok(file) => file,
-    };
-
-    fn print_module_info(output_path: &str, path: &str)
-    -> (Vec<String>, Vec<(String,String,String)>)
-    {
-        mdbook::svmodule::generate_sv_module_info(output_path, path)
-    }
-
-    let mut list_of_modules: Vec<(String,String,String)> = Vec::new();
-
-    let mut text: Vec<String> = Vec::new();
-    text.push("# Modules\n\n".to_string());
-
-    let mut/*env*/ print_modules = |_node: &FsNode, path: &PathBuf, _level: usize| {
-        if path.is_file() {
-            if let Some(path_str) = path.to_str() {
-                let (mut new_text_chunk, mut new_mod_chunk) = print_module_info(output_path, path_str);
-                text.append(&mut new_text_chunk);
-                list_of_modules.append(&mut new_mod_chunk);
-            }
-        }
-    };
-
-    files.traverse_top(&mut print_modules);
-
-    let mut writer = BufWriter::new(file);
-
-    for t in &text {
-        match writer.write_all(t.as_bytes()) {
-            Err(e) => return Err(e.to_string()),
-            Ok(_) => (),
-        }
-    }
-
-    Ok(list_of_modules)
-}
-
-fn create_ifaces_md(
-    output_path: &str,
-    files: &FsNode
-) -> Result<Vec<(String,String,String)>,String>
+fn create_index_md(
+    book: &mut Book,
+    path: &str,
+    title: &str,
+    text_chunk: Vec<String>,
+    mut list: Vec<(String,String,String)>
+) -> Vec<Chapter>
 {
-
-    let fname = Path::new(&output_path).join("ifaces.md");
-    let fname = fname.to_str().unwrap();
-
-    let file = match fs::OpenOptions::new()
-        .read(false).write(true).create(true).truncate(true)
-        .open(fname) {
-            Err(e) => return Err(e.to_string()),
-            Ok(file) => file,
-    };
-
-    fn print_iface_info(output_path: &str, path: &str)
-    -> (Vec<String>, Vec<(String,String,String)>)
-    {
-        mdbook::sviface::generate_sv_interface_info(output_path, path)
+    let mut text = format!("# {}\n\n", title);
+    for t in &text_chunk {
+        text.push_str(t);
     }
 
-    let mut list_of_ifaces: Vec<(String,String,String)> = Vec::new();
-
-    let mut text: Vec<String> = Vec::new();
-    text.push("# Interfaces\n\n".to_string());
-
-    let mut/*env*/ print_ifaces = |_node: &FsNode, path: &PathBuf, _level: usize| {
-        if path.is_file() {
-            if let Some(path_str) = path.to_str() {
-                let (mut new_text_chunk, mut new_iface_chunk) = print_iface_info(output_path, path_str);
-                text.append(&mut new_text_chunk);
-                list_of_ifaces.append(&mut new_iface_chunk);
-            }
-        }
-    };
-
-    files.traverse_top(&mut print_ifaces);
+    book.add_page(path, text);
 
-    let mut writer = BufWriter::new(file);
+    list.sort();
 
-    for t in &text {
-        match writer.write_all(t.as_bytes()) {
-            Err(e) => return Err(e.to_string()),
-            Ok(_) => (),
-        }
-    }
-
-    Ok(list_of_ifaces)
+    list.into_iter()
+        .map(|(name, file, page)| Chapter {
+            level: 2,
+            title: format!("`{}`  :{}", name, file),
+            path: Some(page),
+        })
+        .collect()
 }
 
-fn create_classes_md(
-    output_path: &str,
-    files: &FsNode
-) -> Result<Vec<(String,String,String)>,String>
-{
-
-    let fname = Path::new(&output_path).join("classes.md");
-    let fname = fname.to_str().unwrap();
+fn list_users_md_docs(src_files: &SrcFiles) -> Result<Vec<Chapter>,String> {
 
-    let file = match fs::OpenOptions::new()
-        .read(false).write(true).create(true).truncate(true)
-        .open(fname) {
-            Err(e) => return Err(e.to_string()),
-            Ok(file) => file,
-    };
-
-    fn print_class_info(output_path: &str, path: &str)
-    -> (Vec<String>, Vec<(String,String,String)>)
-    {
-        mdbook::svclass::generate_sv_class_info(output_path, path)
-    }
-
-    let mut list_of_classes: Vec<(String,String,String)> = Vec::new();
-
-    let mut text: Vec<String> = Vec::new();
-    text.push("# Classes\n\n".to_string());
-
-    let mut/*env*/ print_classes = |_node: &FsNode, path: &PathBuf, _level: usize| {
-        if path.is_file() {
-            if let Some(path_str) = path.to_str() {
-                let (mut new_text_chunk, mut new_class_chunk) = print_class_info(output_path, path_str);
-                text.append(&mut new_text_chunk);
-                list_of_classes.append(&mut new_class_chunk);
-            }
-        }
-    };
-
-    files.traverse_top(&mut print_classes);
-
-    let mut writer = BufWriter::new(file);
-
-    for t in &text {
-        match writer.write_all(t.as_bytes()) {
-            Err(e) => return Err(e.to_string()),
-            Ok(_) => (),
-        }
-    }
-
-    Ok(list_of_classes)
-}
-
-fn create_packages_md(
-    output_path: &str,
-    files: &FsNode
-) -> Result<Vec<(String,String,String)>,String>
-{
-
-    let fname = Path::new(&output_path).join("packages.md");
-    let fname = fname.to_str().unwrap();
-
-    let file = match fs::OpenOptions::new()
-        .read(false).write(true).create(true).truncate(true)
-        .open(fname) {
-            Err(e) => return Err(e.to_string()),
-            Ok(file) => file,
-    };
-
-    fn print_pkg_info(output_path: &str, path: &str)
-    -> (Vec<String>, Vec<(String,String,String)>)
-    {
-        mdbook::svpkg::generate_sv_package_info(output_path, path)
-    }
-
-    let mut list_of_pkgs: Vec<(String,String,String)> = Vec::new();
-
-    let mut text: Vec<String> = Vec::new();
-    text.push("# Packages\n\n".to_string());
-
-    let mut/*env*/ print_pkgs = |_node: &FsNode, path: &PathBuf, _level: usize| {
-        if path.is_file() {
-            if let Some(path_str) = path.to_str() {
-                let (mut new_text_chunk, mut new_pkg_chunk) = print_pkg_info(output_path, path_str);
-                text.append(&mut new_text_chunk);
-                list_of_pkgs.append(&mut new_pkg_chunk);
-            }
-        }
-    };
-
-    files.traverse_top(&mut print_pkgs);
-
-    let mut writer = BufWriter::new(file);
-
-    for t in &text {
-        match writer.write_all(t.as_bytes()) {
-            Err(e) => return Err(e.to_string()),
-            Ok(_) => (),
-        }
-    }
-
-    Ok(list_of_pkgs)
-}
-
-fn list_users_md_docs(
-    _mdbook_src_dir: &str,
-    src_files: &SrcFiles
-) -> Result<Vec<(usize,String,String)>,String>
-{
     let md_files = mdbook::files::get_md_files(&src_files.nodes)?;
 
-    let mut list: Vec<(usize, String, String)> = Vec::new();
+    let mut list: Vec<Chapter> = Vec::new();
 
     let mut/*env*/ list_md_files = |node: &FsNode, path: &PathBuf, level: usize| {
-        if path.is_file() {
-            let mdbook_path = Path::new("src").join(path);
-            let mdbook_path_str = mdbook_path.to_str().unwrap_or("");
-            list.push((level, node.name.clone(), mdbook_path_str.to_string()));
-        }
-        else {
-            list.push((level, node.name.clone(), "".to_string()));
-        }
+        let mdbook_path = match path.is_file() {
+            true => mdbook::files::book_path(path).to_str().map(|p| p.to_string()),
+            false => None,
+        };
+        // Nested under "User's Documentation" draft chapter.
+        list.push(Chapter { level: level + 1, title: node.name.clone(), path: mdbook_path });
     };
 
     md_files.traverse(&mut PathBuf::from(""), 1, &mut list_md_files);
@@ -511,48 +287,53 @@ fn list_users_md_docs(
 }
 
 fn create_sv_docs(
-    mdbook_src_dir: &str,
-    all_files: &SrcFiles
-) -> Result<Vec<String>,String>
+    book: &mut Book,
+    all_files: &SrcFiles,
+    design: &Design,
+    options: &args::ParsedOptions
+) -> Result<Vec<Chapter>,String>
 {
 
-    let sv_files = mdbook::files::get_sv_files(&all_files.nodes)?;
+    let sv_files = mdbook::files::get_sv_files(all_files)?;
 
-    let mut text_buf: Vec<String> = Vec::new();
+    let mut chapters: Vec<Chapter> = Vec::new();
 
-    let file_list = create_files_md(mdbook_src_dir, &sv_files)?;
-    text_buf.push("- [Files](files.md)\n".to_string());
-    text_buf.push(file_list);
-
-    let mut module_list = create_modules_md(mdbook_src_dir, &sv_files)?;
-    module_list.sort();
-    text_buf.push("- [Modules](modules.md)\n".to_string());
-    for module in &module_list {
-        text_buf.push(format!("  - [`{}`  :{}]({})\n", module.0, module.1, module.2));
+    fn chapter(title: &str, path: &str) -> Chapter {
+        Chapter { level: 1, title: title.to_string(), path: Some(path.to_string()) }
     }
 
-    let mut pkg_list = create_packages_md(mdbook_src_dir, &sv_files)?;
-    pkg_list.sort();
-    text_buf.push("- [Packages](packages.md)\n".to_string());
-    for pkg in &pkg_list {
-        text_buf.push(format!("  - [`{}`  :{}]({})\n", pkg.0, pkg.1, pkg.2));
-    }
+    chapters.push(chapter("Files", "files.md"));
+    chapters.append(&mut create_files_md(book, &sv_files));
 
-    let mut iface_list = create_ifaces_md(mdbook_src_dir, &sv_files)?;
-    iface_list.sort();
-    text_buf.push("- [Interfaces](ifaces.md)\n".to_string());
-    for iface in &iface_list {
-        text_buf.push(format!("  - [`{}`  :{}]({})\n", iface.0, iface.1, iface.2));
-    }
+    let mut text = String::from("# Design Hierarchy\n\n");
+    text.push_str(&mdbook::hierarchy::generate_hierarchy_info(design, &options.tops));
+    book.add_page("hierarchy.md", text);
+    chapters.push(chapter("Design Hierarchy", "hierarchy.md"));
 
-    let mut class_list = create_classes_md(mdbook_src_dir, &sv_files)?;
-    class_list.sort();
-    text_buf.push("- [Classes](classes.md)\n".to_string());
-    for class in &class_list {
-        text_buf.push(format!("  - [`{}`  :{}]({})\n", class.0, class.1, class.2));
-    }
+    let (text, list) = mdbook::svmodule::generate_sv_module_info(book, design, options);
+    chapters.push(chapter("Modules", "modules.md"));
+    chapters.append(&mut create_index_md(book, "modules.md", "Modules", text, list));
+
+    let (text, list) = mdbook::svpkg::generate_sv_package_info(book, design);
+    chapters.push(chapter("Packages", "packages.md"));
+    chapters.append(&mut create_index_md(book, "packages.md", "Packages", text, list));
+
+    let (text, list) = mdbook::sviface::generate_sv_interface_info(book, design);
+    chapters.push(chapter("Interfaces", "ifaces.md"));
+    chapters.append(&mut create_index_md(book, "ifaces.md", "Interfaces", text, list));
+
+    let (text, list) = mdbook::svclass::generate_sv_class_info(book, design);
+    chapters.push(chapter("Classes", "classes.md"));
+    chapters.append(&mut create_index_md(book, "classes.md", "Classes", text, list));
+
+    let mut text = String::from("# Class Hierarchy\n\n");
+    text.push_str(&mdbook::svclass::generate_class_hierarchy(design));
+    book.add_page("class_hierarchy.md", text);
+    chapters.push(chapter("Class Hierarchy", "class_hierarchy.md"));
 
-    text_buf.push("- [Functions]()\n".to_string());
+    let (text, list) = mdbook::svfunc::generate_sv_function_info(book, design);
+    chapters.push(chapter("Functions", "functions.md"));
+    chapters.append(&mut create_index_md(book, "functions.md", "Functions and tasks", text, list));
 
-    Ok(text_buf)
+    Ok(chapters)
 }
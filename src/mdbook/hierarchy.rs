@@ -0,0 +1,301 @@
+//! Elaborate design hierarchy from top-level modules.
+//!
+//! Instance tree is built from instantiations collected for every module,
+//! parameters and generate conditions are not evaluated.
+//!
+
+use crate::mdbook::design::{Design, Location};
+use crate::mdbook::markdown;
+
+/// Instance in elaborated design hierarchy.
+///
+pub struct InstanceNode {
+    pub instance_name: String,
+    pub module_name: String,
+    /// Hierarchical name, like `top.u_core.u_alu`.
+    pub path: String,
+    /// Where the instance is, `None` for top module.
+    pub location: Option<Location>,
+    /// Module or interface is found in the sources.
+    pub found: bool,
+    pub children: Vec<InstanceNode>,
+}
+
+/// Modules which are not instantiated by any other module.
+///
+pub fn find_tops(design: &Design) -> Vec<String> {
+    design.modules.iter()
+        .filter(|module| !design.modules.iter()
+            .any(|m| m.instances.iter().any(|inst| inst.module_name == module.name)))
+        .map(|module| module.name.clone())
+        .collect()
+}
+
+/// Build instance tree for every top module, given or detected.
+///
+pub fn elaborate(design: &Design, tops: &[String]) -> Vec<InstanceNode> {
+    let tops = match tops.is_empty() {
+        true => find_tops(design),
+        false => tops.to_vec(),
+    };
+
+    let mut trees: Vec<InstanceNode> = Vec::new();
+
+    for top in tops {
+        if !design.modules.iter().any(|m| m.name == top) {
+            println!("Warning: top module '{}' is not found", top);
+            continue;
+        }
+        trees.push(elaborate_instance(design, &top, &top, &top, None, &mut Vec::new()));
+    }
+
+    trees
+}
+
+fn elaborate_instance(
+    design: &Design,
+    instance_name: &str,
+    module_name: &str,
+    path: &str,
+    location: Option<Location>,
+    parents: &mut Vec<String>
+) -> InstanceNode
+{
+    let module = design.modules.iter().find(|m| m.name == module_name);
+    let found = module.is_some() || design.interfaces.iter().any(|i| i.name == module_name);
+
+    let mut node = InstanceNode {
+        instance_name: instance_name.to_string(),
+        module_name: module_name.to_string(),
+        path: path.to_string(),
+        location,
+        found,
+        children: Vec::new(),
+    };
+
+    // Recursive instantiation can't be elaborated without parameters.
+    if parents.iter().any(|p| p == module_name) {
+        println!("Warning: module '{}' instantiates itself in '{}'", module_name, path);
+        return node;
+    }
+
+    if let Some(module) = module {
+        parents.push(module_name.to_string());
+        for inst in &module.instances {
+            node.children.push(elaborate_instance(design,
+                &inst.instance_name,
+                &inst.module_name,
+                &format!("{}.{}", path, inst.instance_name),
+                Some(inst.location.clone()),
+                parents));
+        }
+        parents.pop();
+    }
+
+    node
+}
+
+/// Number of instances of every module type in the trees, in order of first appearance.
+///
+pub fn count_instances(trees: &[InstanceNode]) -> Vec<(String, usize, bool)> {
+    fn count(node: &InstanceNode, counts: &mut Vec<(String, usize, bool)>) {
+        match counts.iter_mut().find(|c| c.0 == node.module_name) {
+            Some(c) => c.1 += 1,
+            None => counts.push((node.module_name.clone(), 1, node.found)),
+        }
+        for child in &node.children {
+            count(child, counts);
+        }
+    }
+
+    let mut counts: Vec<(String, usize, bool)> = Vec::new();
+    for tree in trees {
+        count(tree, &mut counts);
+    }
+
+    counts
+}
+
+/// Generate text of design hierarchy chapter,
+/// tree is rendered as nested HTML `<details>` to make it collapsible.
+///
+pub fn generate_hierarchy_info(design: &Design, tops: &[String]) -> String {
+    let trees = elaborate(design, tops);
+
+    let mut text = String::new();
+
+    if trees.is_empty() {
+        text.push_str("No top-level modules found.\n");
+        return text;
+    }
+
+    text.push_str("<ul class=\"svdoc-hierarchy\">\n");
+    for tree in &trees {
+        print_instance_node(&mut text, design, tree);
+    }
+    text.push_str("</ul>\n\n");
+
+    let counts = count_instances(&trees);
+
+    text.push_str("## Instance counts\n\n");
+    text.push_str("| Module | Instances |\n");
+    text.push_str("|--------|-----------|\n");
+    for (name, count, found) in &counts {
+        let page = design.modules.iter().find(|m| &m.name == name)
+            .map(|m| markdown::page_path(&m.location.file, "module", &m.name))
+            .or_else(|| markdown::iface_page(design, name));
+        match (page, found) {
+            (Some(page), _) => text.push_str(format!("| [`{}`]({}) | {} |\n", name, page, count).as_str()),
+            (None, false) => text.push_str(format!("| `{}` ⚠ not found | {} |\n", name, count).as_str()),
+            (None, true) => text.push_str(format!("| `{}` | {} |\n", name, count).as_str()),
+        }
+    }
+    text.push('\n');
+
+    let missing: Vec<&str> = counts.iter()
+        .filter(|c| !c.2)
+        .map(|c| c.0.as_str())
+        .collect();
+    if !missing.is_empty() {
+        text.push_str("## Modules not found in sources\n\n");
+        for name in missing {
+            text.push_str(format!("- `{}`\n", name).as_str());
+        }
+        text.push('\n');
+    }
+
+    text
+}
+
+fn print_instance_node(
+    text: &mut String,
+    design: &Design,
+    node: &InstanceNode
+)
+{
+    // Raw HTML is not processed by mdBook, so link to generated `.html` page.
+    let page = design.modules.iter().find(|m| m.name == node.module_name)
+        .map(|m| markdown::page_path(&m.location.file, "module", &m.name))
+        .or_else(|| markdown::iface_page(design, &node.module_name))
+        .map(|page| page.trim_end_matches(".md").to_string() + ".html");
+
+    let module_str = match (&page, node.found) {
+        (Some(page), _) => format!("<a href=\"{}\"><code>{}</code></a>", page, node.module_name),
+        (None, false) => format!("<code>{}</code> <strong>⚠ not found</strong>", node.module_name),
+        (None, true) => format!("<code>{}</code>", node.module_name),
+    };
+
+    let label = format!("<code title=\"{}\">{}</code> : {}", node.path, node.instance_name, module_str);
+
+    match node.children.is_empty() {
+        true => text.push_str(format!("<li>{}</li>\n", label).as_str()),
+        false => {
+            text.push_str(format!("<li><details open><summary>{}</summary>\n<ul>\n", label).as_str());
+            for child in &node.children {
+                print_instance_node(text, design, child);
+            }
+            text.push_str("</ul>\n</details></li>\n");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mdbook::design::parse_str;
+
+    const SOURCE: &str = "
+        module leaf; endmodule
+        module mid; leaf u_a(); leaf u_b(); endmodule
+        module top; mid u_mid0(); mid u_mid1(); ip_macro u_ip(); endmodule
+        module tb; top u_top(); endmodule
+        module spare; leaf u_leaf(); endmodule
+        module rec; rec u_self(); endmodule
+        module ping; pong u_pong(); endmodule
+        module pong; ping u_ping(); endmodule";
+
+    /// Hierarchical names of the tree in depth-first order.
+    ///
+    fn paths(node: &InstanceNode, all: &mut Vec<String>) {
+        all.push(node.path.clone());
+        for child in &node.children {
+            paths(child, all);
+        }
+    }
+
+    #[test]
+    fn top_detection() {
+        let design = parse_str(SOURCE);
+
+        // Recursive modules instantiate each other, so they are never tops.
+        assert_eq!(find_tops(&design), ["tb", "spare"]);
+
+        let trees = elaborate(&design, &[]);
+        let tops = trees.iter().map(|t| t.module_name.as_str()).collect::<Vec<_>>();
+        assert_eq!(tops, ["tb", "spare"]);
+        assert!(trees.iter().all(|t| t.location.is_none()));
+    }
+
+    #[test]
+    fn top_override() {
+        let design = parse_str(SOURCE);
+
+        let trees = elaborate(&design, &["mid".to_string(), "missing".to_string()]);
+        assert_eq!(trees.len(), 1);
+
+        let mut all = Vec::new();
+        paths(&trees[0], &mut all);
+        assert_eq!(all, ["mid", "mid.u_a", "mid.u_b"]);
+        assert_eq!(trees[0].children[1].location.as_ref().unwrap().line, 3);
+    }
+
+    #[test]
+    fn repeated_and_unknown_instances() {
+        let design = parse_str(SOURCE);
+        let trees = elaborate(&design, &["tb".to_string()]);
+
+        let mut all = Vec::new();
+        paths(&trees[0], &mut all);
+        assert_eq!(all, ["tb", "tb.u_top",
+            "tb.u_top.u_mid0", "tb.u_top.u_mid0.u_a", "tb.u_top.u_mid0.u_b",
+            "tb.u_top.u_mid1", "tb.u_top.u_mid1.u_a", "tb.u_top.u_mid1.u_b",
+            "tb.u_top.u_ip"]);
+
+        let ip = &trees[0].children[0].children[2];
+        assert!(!ip.found);
+        assert!(ip.children.is_empty());
+
+        assert_eq!(count_instances(&trees), [
+            ("tb".to_string(), 1, true),
+            ("top".to_string(), 1, true),
+            ("mid".to_string(), 2, true),
+            ("leaf".to_string(), 4, true),
+            ("ip_macro".to_string(), 1, false),
+        ]);
+
+        let text = generate_hierarchy_info(&design, &["tb".to_string()]);
+        assert!(text.contains("## Modules not found in sources\n\n- `ip_macro`\n"));
+        assert!(text.contains("<code>ip_macro</code> <strong>⚠ not found</strong>"));
+    }
+
+    #[test]
+    fn recursive_instances() {
+        let design = parse_str(SOURCE);
+
+        let trees = elaborate(&design, &["rec".to_string(), "ping".to_string()]);
+        let mut all = Vec::new();
+        for tree in &trees {
+            paths(tree, &mut all);
+        }
+        assert_eq!(all, ["rec", "rec.u_self", "ping", "ping.u_pong", "ping.u_pong.u_ping"]);
+        assert!(trees[0].children[0].found);
+    }
+
+    #[test]
+    fn no_tops() {
+        let design = parse_str("module rec; rec u_self(); endmodule");
+
+        assert!(elaborate(&design, &[]).is_empty());
+        assert_eq!(generate_hierarchy_info(&design, &[]), "No top-level modules found.\n");
+    }
+}
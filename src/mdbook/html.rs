@@ -0,0 +1,354 @@
+//! Render the book as static HTML site without mdBook.
+//!
+//! Every page becomes self-contained HTML file next to where mdBook
+//! would put it, with embedded CSS and no scripts,
+//! `index.html` lists all pages in table of contents order.
+//! Module symbols are inline SVG already; Mermaid diagrams need JavaScript,
+//! so they are shown as preformatted source text with a note unless
+//! `--js-libs bundled` adds the bundled WaveDrom and Mermaid to the site.
+//!
+
+use std::fs;
+use std::path::Path;
+
+use crate::mdbook;
+use crate::mdbook::book::{Book, Chapter, Page};
+use crate::mdbook::markdown;
+use crate::mdbook::render::{Context, RenderError, Renderer};
+
+const INDEX_HTML: &str = "index.html";
+
+const STYLE_CSS: &str = r#"
+body { font-family: -apple-system, "Segoe UI", Helvetica, Arial, sans-serif;
+       line-height: 1.5; color: #222; margin: 0; }
+nav { background: #f4f4f4; border-bottom: 1px solid #ddd; padding: 0.5em 1em; }
+nav a { margin-right: 1em; }
+main { max-width: 60em; padding: 1em 2em; }
+a { color: #2a6496; text-decoration: none; }
+a:hover { text-decoration: underline; }
+code { font-family: "Source Code Pro", Consolas, monospace; font-size: 0.9em;
+       background: #f6f6f6; padding: 0 0.2em; }
+pre { background: #f6f6f6; padding: 0.5em; overflow-x: auto; }
+pre code { padding: 0; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: left; }
+th { background: #eee; }
+pre.svdoc-mermaid { font-size: 0.85em; background: #fafafa; border: 1px dashed #ccc; }
+p.svdoc-note { font-size: 0.85em; font-style: italic; color: #666; margin-bottom: 0.2em; }
+ul.toc { list-style: none; padding-left: 1.2em; }
+"#;
+
+/// Static HTML pages, an alternative to `mdbook::build::build`.
+///
+pub struct HtmlRenderer;
+
+impl Renderer for HtmlRenderer {
+    fn name(&self) -> &str { "html" }
+
+    fn render(&self, ctx: &Context) -> Result<(),RenderError> {
+        ctx.create_output_dir()?;
+
+        let book = ctx.book()?;
+        let output_dir = Path::new(&ctx.options.output_dir);
+
+        mdbook::generate::copy_assets(output_dir, book)?;
+        mdbook::generate::write_pages(output_dir, &book.files)?;
+
+        // CDN would be a network fetch, so scripts are only added when bundled.
+        let scripts = ctx.options.js_libs == "bundled";
+        if scripts {
+            mdbook::build::write_js_loader(output_dir, &ctx.options.js_libs)?;
+            mdbook::build::write_bundled_js(output_dir)?;
+        }
+
+        let pages: Vec<Page> = book.pages.iter()
+            .map(|page| Page { path: html_path(&page.path), text: page_html(book, page, scripts) })
+            .collect();
+        mdbook::generate::write_pages(output_dir, &pages)?;
+
+        let index_path = output_dir.join(INDEX_HTML);
+        match fs::write(&index_path, index_html(book, scripts)) {
+            Err(e) => return Err(RenderError::from(e.to_string())),
+            Ok(_) => println!("Created '{}'", index_path.display()),
+        }
+
+        Ok(())
+    }
+}
+
+/// Path of HTML file of markdown page, `src/a.md` is `src/a.html`.
+///
+pub fn html_path(page_path: &str) -> String {
+    match page_path.strip_suffix(".md") {
+        Some(stem) => format!("{}.html", stem),
+        None => format!("{}.html", page_path),
+    }
+}
+
+/// HTML document of page with navigation to contents, previous and next page,
+/// `scripts` adds the script loading WaveDrom and Mermaid.
+///
+pub fn page_html(book: &Book, page: &Page, scripts: bool) -> String {
+    let chapters = book.chapter_pages();
+    let pos = chapters.iter().position(|(_, p)| p.path == page.path);
+
+    let title = match pos {
+        Some(pos) => chapters[pos].0.title.clone(),
+        None => page.path.clone(),
+    };
+
+    let link = |to: &str| markdown::relative_link(&page.path, to);
+
+    let mut nav = format!("<a href=\"{}\">Contents</a>", link(INDEX_HTML));
+    if let Some(pos) = pos {
+        if pos > 0 {
+            nav.push_str(format!("<a href=\"{}\">&larr; Previous</a>",
+                link(&html_path(&chapters[pos - 1].1.path))).as_str());
+        }
+        if pos + 1 < chapters.len() {
+            nav.push_str(format!("<a href=\"{}\">Next &rarr;</a>",
+                link(&html_path(&chapters[pos + 1].1.path))).as_str());
+        }
+    }
+
+    let content = ::mdbook::utils::render_markdown(&page.text, false);
+    let content = match scripts {
+        true => content,
+        false => mermaid_as_source(&content),
+    };
+
+    let script = match scripts {
+        true => Some(link(mdbook::build::LOADER_JS)),
+        false => None,
+    };
+
+    document(&book.title, &title, &nav, &content, script.as_deref())
+}
+
+/// HTML document with table of contents.
+///
+pub fn index_html(book: &Book, scripts: bool) -> String {
+    let mut content = String::from("<h1>Contents</h1>\n");
+
+    if !book.user_chapters.is_empty() {
+        content.push_str("<h2>User's Documentation</h2>\n");
+        print_toc(&mut content, &book.user_chapters, 2);
+    }
+
+    content.push_str("<h2>Design</h2>\n");
+    print_toc(&mut content, &book.chapters, 1);
+
+    document(&book.title, "Contents", "", &content,
+        match scripts { true => Some(mdbook::build::LOADER_JS), false => None })
+}
+
+/// Nested lists of chapters, `top` is the level of outer list.
+///
+fn print_toc(text: &mut String, chapters: &[Chapter], top: usize) {
+    let mut level = top - 1;
+
+    for chapter in chapters {
+        while level < chapter.level {
+            text.push_str("<ul class=\"toc\">\n");
+            level += 1;
+        }
+        while level > chapter.level {
+            text.push_str("</ul>\n");
+            level -= 1;
+        }
+        let title = ::mdbook::utils::render_markdown(&chapter.title, false);
+        let title = title.trim().trim_start_matches("<p>").trim_end_matches("</p>");
+        match &chapter.path {
+            Some(path) => text.push_str(format!("<li><a href=\"{}\">{}</a></li>\n",
+                html_path(path), title).as_str()),
+            None => text.push_str(format!("<li>{}</li>\n", title).as_str()),
+        }
+    }
+
+    while level >= top {
+        text.push_str("</ul>\n");
+        level -= 1;
+    }
+}
+
+/// Replace `<div class="mermaid">` blocks, which are drawn by script,
+/// with their source text in `<pre>` and a note why it is not a diagram.
+///
+fn mermaid_as_source(content: &str) -> String {
+    const MERMAID_DIV: &str = "<div class=\"mermaid\">";
+
+    let mut text = String::new();
+    let mut rest = content;
+
+    while let Some(start) = rest.find(MERMAID_DIV) {
+        let source = &rest[start + MERMAID_DIV.len()..];
+        let end = match source.find("</div>") {
+            Some(end) => end,
+            None => break,
+        };
+        text.push_str(&rest[..start]);
+        text.push_str("<p class=\"svdoc-note\">Mermaid diagram source, \
+            diagrams are drawn only with <code>--js-libs bundled</code>.</p>\n");
+        text.push_str(format!("<pre class=\"svdoc-mermaid\"><code>{}</code></pre>",
+            html_escape(source[..end].trim_matches('\n'))).as_str());
+        rest = &source[end + "</div>".len()..];
+    }
+    text.push_str(rest);
+
+    text
+}
+
+fn document(
+    book_title: &str,
+    title: &str,
+    nav: &str,
+    content: &str,
+    script: Option<&str>
+) -> String
+{
+    let title = match book_title.is_empty() {
+        true => html_escape(title),
+        false => format!("{} - {}", html_escape(title), html_escape(book_title)),
+    };
+
+    let script = match script {
+        Some(src) => format!("<script src=\"{}\"></script>\n", src),
+        None => String::new(),
+    };
+
+    format!("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"UTF-8\">\n\
+        <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<nav>{}</nav>\n<main>\n{}\n</main>\n{}</body>\n</html>\n",
+        title, STYLE_CSS, nav, content, script)
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::ParsedOptions;
+    use std::path::PathBuf;
+
+    const TOP_SV: &str = "\
+// Counter states.
+package CntPkg;
+    typedef enum logic [1:0] { IDLE, RUN } state_t;
+endpackage
+
+// Leaf counter.
+module Counter import CntPkg::*; (input logic clk, rst_n, go, output logic [7:0] q);
+    state_t state;
+    always_ff @(posedge clk or negedge rst_n)
+        if (!rst_n) state <= IDLE;
+        else case (state)
+            IDLE: if (go) state <= RUN;
+            RUN: state <= IDLE;
+        endcase
+endmodule
+
+// Top level.
+module Top (input logic clk, rst_n, output logic [7:0] q);
+    Counter u_cnt (.clk, .rst_n, .go(1'b1), .q);
+endmodule
+";
+
+    /// Files under `dir` relative to it, with `/` separators.
+    ///
+    fn list_files(dir: &Path, base: &Path, files: &mut Vec<String>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            match path.is_dir() {
+                true => list_files(&path, base, files),
+                false => files.push(path.strip_prefix(base).unwrap().to_str().unwrap().replace('\\', "/")),
+            }
+        }
+    }
+
+    #[test]
+    fn small_book() {
+        let dir = std::env::temp_dir().join(format!("svdocgen-html-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("rtl")).unwrap();
+        fs::write(dir.join("rtl/top.sv"), TOP_SV).unwrap();
+        fs::write(dir.join("README.md"), "# Intro\n\nSee [top](rtl/top.sv).\n").unwrap();
+        let output_dir = dir.join("out");
+
+        let options = ParsedOptions {
+            output_dir: output_dir.to_str().unwrap().to_string(),
+            inputs: vec![dir.join("rtl").to_str().unwrap().to_string(),
+                dir.join("README.md").to_str().unwrap().to_string()],
+            filelists: Vec::new(),
+            includes: Vec::new(),
+            incdirs: Vec::new(),
+            defines: Vec::new(),
+            tops: Vec::new(),
+            collapse_buses: false,
+            format: String::from("html"),
+            js_libs: String::from("cdn"),
+            project_name: String::from("Small"),
+        };
+        let ctx = Context::new(&options).unwrap();
+        HtmlRenderer.render(&ctx).unwrap();
+
+        let mut files = Vec::new();
+        list_files(&output_dir, &output_dir, &mut files);
+        let mut html_files: Vec<&String> = files.iter().filter(|f| f.ends_with(".html")).collect();
+        html_files.sort();
+
+        // Every page and the contents, nothing else.
+        let book = ctx.book().unwrap();
+        let mut expected: Vec<String> = book.pages.iter().map(|p| html_path(&p.path)).collect();
+        expected.push(String::from(INDEX_HTML));
+        expected.sort();
+        assert_eq!(html_files, expected.iter().collect::<Vec<_>>());
+        for name in ["/README.html", "/rtl/top.sv.html", "/rtl/top.module.Top.html",
+            "/rtl/top.module.Counter.html", "/rtl/top.pkg.CntPkg.html", "/rtl/top.module.Top.dot"]
+        {
+            assert!(files.iter().any(|f| f.ends_with(name)), "no {}", name);
+        }
+
+        // Relative links point to written files.
+        let href = regex::Regex::new("href=\"([^\"#]*)[^\"]*\"").unwrap();
+        for file in &html_files {
+            let html = fs::read_to_string(output_dir.join(file)).unwrap();
+            assert!(!html.contains("<script"), "{}", file);
+            for cap in href.captures_iter(&html) {
+                let target = &cap[1];
+                if target.is_empty() || target.contains("://") {
+                    continue;
+                }
+                let path: PathBuf = output_dir.join(file).parent().unwrap().join(target);
+                assert!(path.is_file(), "{} links to missing '{}'", file, target);
+            }
+        }
+
+        // Mermaid needs script, so its source is shown.
+        let top_page = html_files.iter().find(|f| f.ends_with("/top.module.Top.html")).unwrap();
+        let html = fs::read_to_string(output_dir.join(top_page)).unwrap();
+        assert!(!html.contains("<div class=\"mermaid\">"));
+        assert!(html.contains("<p class=\"svdoc-note\">Mermaid diagram source"));
+        assert!(html.contains("<pre class=\"svdoc-mermaid\"><code>flowchart LR\n  p0([&quot;clk&quot;])\n"));
+        assert!(html.contains("i0[&quot;u_cnt&lt;br/&gt;Counter&quot;]"));
+
+        let index = fs::read_to_string(output_dir.join(INDEX_HTML)).unwrap();
+        assert!(index.contains("<title>Contents - Small</title>"));
+        assert!(index.contains("<h2>User's Documentation</h2>"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn mermaid_source() {
+        let content = "<p>a</p>\n<div class=\"mermaid\">\ngraph BT\n  a[\"x<br/>y\"]\n</div>\n<p>b</p>\n";
+
+        assert_eq!(mermaid_as_source(content), "<p>a</p>\n\
+            <p class=\"svdoc-note\">Mermaid diagram source, \
+            diagrams are drawn only with <code>--js-libs bundled</code>.</p>\n\
+            <pre class=\"svdoc-mermaid\"><code>graph BT\n  a[&quot;x&lt;br/&gt;y&quot;]</code></pre>\n<p>b</p>\n");
+    }
+}
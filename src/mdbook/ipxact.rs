@@ -0,0 +1,334 @@
+//! Export modules as IP-XACT (IEEE 1685-2014) components.
+//!
+//! Every module becomes `ipxact:component` with one `rtl` view,
+//! its parameters, wire ports and source file.
+//! Interface ports can't be described without bus definitions,
+//! so they become bus interface placeholders naming the interface.
+//! Elements are in the order of the 2014 schema sequences.
+//!
+
+use std::fs;
+use std::path::Path;
+
+use crate::args;
+use crate::mdbook::design::{Design, Direction, Module, ParamKind, Port};
+
+const IPXACT_DIR: &str = "ipxact";
+const IPXACT_NS: &str = "http://www.accellera.org/XMLSchema/IPXACT/1685-2014";
+
+/// Component VLNV without name, which is the module name.
+///
+pub struct Vlnv {
+    pub vendor: String,
+    pub library: String,
+    pub version: String,
+}
+
+/// Write `ipxact/<module>.xml` for every module into output directory.
+///
+pub fn emit(options: &args::ParsedOptions, design: &Design) -> Result<(),String> {
+
+    let ipxact_dir = Path::new(&options.output_dir).join(IPXACT_DIR);
+
+    match fs::create_dir_all(&ipxact_dir) {
+        Err(e) => { println!("Can't create output directory '{}' error: {}",
+                        ipxact_dir.display(), e);
+                    return Err(e.to_string()); },
+        Ok(_) => println!("Created output directory '{}'", ipxact_dir.display()),
+    }
+
+    let vlnv = Vlnv {
+        vendor: match options.project_name.is_empty() {
+            true => String::from("unknown"),
+            false => options.project_name.clone(),
+        },
+        library: String::from("rtl"),
+        version: String::from("1.0"),
+    };
+
+    for module in &design.modules {
+        let xml_path = ipxact_dir.join(format!("{}.xml", module.name));
+        match fs::write(&xml_path, component_xml(module, &vlnv)) {
+            Err(e) => return Err(e.to_string()),
+            Ok(_) => println!("Created '{}'", xml_path.display()),
+        }
+    }
+
+    Ok(())
+}
+
+/// IP-XACT component document of module.
+///
+pub fn component_xml(module: &Module, vlnv: &Vlnv) -> String {
+    let mut xml = String::new();
+
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(format!("<ipxact:component xmlns:ipxact=\"{ns}\" \
+        xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+        xsi:schemaLocation=\"{ns} {ns}/index.xsd\">\n", ns = IPXACT_NS).as_str());
+    element(&mut xml, 1, "vendor", &vlnv.vendor);
+    element(&mut xml, 1, "library", &vlnv.library);
+    element(&mut xml, 1, "name", &module.name);
+    element(&mut xml, 1, "version", &vlnv.version);
+
+    let bus_ports: Vec<&Port> = module.ports.iter().filter(|p| p.port_type.interface.is_some()).collect();
+    let wire_ports: Vec<&Port> = module.ports.iter().filter(|p| p.port_type.interface.is_none()).collect();
+
+    if !bus_ports.is_empty() {
+        xml.push_str("  <ipxact:busInterfaces>\n");
+        for port in bus_ports {
+            print_bus_interface(&mut xml, port, vlnv);
+        }
+        xml.push_str("  </ipxact:busInterfaces>\n");
+    }
+
+    // Local parameters can't be overridden, so they are not part of the component.
+    let params: Vec<_> = module.parameters.iter()
+        .filter(|p| matches!(p.kind, ParamKind::Parameter | ParamKind::TypeParameter))
+        .collect();
+
+    xml.push_str("  <ipxact:model>\n");
+    xml.push_str("    <ipxact:views>\n");
+    xml.push_str("      <ipxact:view>\n");
+    element(&mut xml, 4, "name", "rtl");
+    element(&mut xml, 4, "componentInstantiationRef", "rtl_implementation");
+    xml.push_str("      </ipxact:view>\n");
+    xml.push_str("    </ipxact:views>\n");
+    xml.push_str("    <ipxact:instantiations>\n");
+    xml.push_str("      <ipxact:componentInstantiation>\n");
+    element(&mut xml, 4, "name", "rtl_implementation");
+    element(&mut xml, 4, "language", "systemverilog");
+    element(&mut xml, 4, "moduleName", &module.name);
+    if !params.is_empty() {
+        xml.push_str("        <ipxact:moduleParameters>\n");
+        for param in &params {
+            xml.push_str(format!("          <ipxact:moduleParameter parameterId=\"{}\" resolve=\"user\"{}>\n",
+                xml_escape(&param.name),
+                match param.kind {
+                    ParamKind::TypeParameter => " dataType=\"type\"",
+                    _ => "",
+                }).as_str());
+            element(&mut xml, 6, "name", &param.name);
+            element(&mut xml, 6, "value", param.default.as_deref().unwrap_or(""));
+            xml.push_str("          </ipxact:moduleParameter>\n");
+        }
+        xml.push_str("        </ipxact:moduleParameters>\n");
+    }
+    xml.push_str("        <ipxact:fileSetRef>\n");
+    element(&mut xml, 5, "localName", "rtl_files");
+    xml.push_str("        </ipxact:fileSetRef>\n");
+    xml.push_str("      </ipxact:componentInstantiation>\n");
+    xml.push_str("    </ipxact:instantiations>\n");
+    if !wire_ports.is_empty() {
+        xml.push_str("    <ipxact:ports>\n");
+        for port in wire_ports {
+            print_wire_port(&mut xml, port);
+        }
+        xml.push_str("    </ipxact:ports>\n");
+    }
+    xml.push_str("  </ipxact:model>\n");
+
+    xml.push_str("  <ipxact:fileSets>\n");
+    xml.push_str("    <ipxact:fileSet>\n");
+    element(&mut xml, 3, "name", "rtl_files");
+    xml.push_str("      <ipxact:file>\n");
+    element(&mut xml, 4, "name", &module.location.file);
+    element(&mut xml, 4, "fileType", match module.location.file.ends_with(".v") {
+        true => "verilogSource",
+        false => "systemVerilogSource",
+    });
+    xml.push_str("      </ipxact:file>\n");
+    xml.push_str("    </ipxact:fileSet>\n");
+    xml.push_str("  </ipxact:fileSets>\n");
+
+    if let Some(comment) = &module.comment {
+        element(&mut xml, 1, "description", comment.trim());
+    }
+
+    if !params.is_empty() {
+        xml.push_str("  <ipxact:parameters>\n");
+        for param in &params {
+            xml.push_str(format!("    <ipxact:parameter parameterId=\"{}\" resolve=\"user\">\n",
+                xml_escape(&param.name)).as_str());
+            element(&mut xml, 3, "name", &param.name);
+            if let Some(comment) = &param.comment {
+                element(&mut xml, 3, "description", comment);
+            }
+            element(&mut xml, 3, "value", param.default.as_deref().unwrap_or(""));
+            xml.push_str("    </ipxact:parameter>\n");
+        }
+        xml.push_str("  </ipxact:parameters>\n");
+    }
+
+    xml.push_str("</ipxact:component>\n");
+
+    xml
+}
+
+/// Bus interface for interface port, bus type is the interface name
+/// and the mode is guessed from modport name: `master` or `slave`
+/// if the name says so, otherwise `monitor`, which doesn't drive the bus.
+///
+fn print_bus_interface(xml: &mut String, port: &Port, vlnv: &Vlnv) {
+    let iface = port.port_type.interface.as_deref().unwrap_or("");
+    let modport = port.port_type.modport.as_deref().unwrap_or("");
+
+    xml.push_str("    <ipxact:busInterface>\n");
+    element(xml, 3, "name", &port.name);
+    if let Some(comment) = &port.comment {
+        element(xml, 3, "description", comment);
+    }
+    xml.push_str(format!("      <ipxact:busType vendor=\"{}\" library=\"{}\" name=\"{}\" version=\"{}\"/>\n",
+        xml_escape(&vlnv.vendor), xml_escape(&vlnv.library), xml_escape(iface), xml_escape(&vlnv.version)).as_str());
+
+    xml.push_str(match interface_mode(modport) {
+        Some(mode) => format!("      <ipxact:{}/>\n", mode),
+        None => String::from("      <ipxact:monitor interfaceMode=\"master\"/>\n"),
+    }.as_str());
+
+    if !modport.is_empty() {
+        xml.push_str("      <ipxact:vendorExtensions>\n");
+        xml.push_str(format!("        <svdocgen:modport xmlns:svdocgen=\"https://github.com/igorlesik/svdocgen\">{}</svdocgen:modport>\n",
+            xml_escape(modport)).as_str());
+        xml.push_str("      </ipxact:vendorExtensions>\n");
+    }
+    xml.push_str("    </ipxact:busInterface>\n");
+}
+
+/// `master` or `slave` if modport name says so.
+///
+pub fn interface_mode(modport: &str) -> Option<&'static str> {
+    let modport = modport.to_lowercase();
+    let has = |names: &[&str]| names.iter().any(|n| modport.contains(n));
+
+    if has(&["master", "mst", "initiator", "manager", "host", "driver"]) {
+        Some("master")
+    }
+    else if has(&["slave", "slv", "target", "subordinate", "device", "responder"]) {
+        Some("slave")
+    }
+    else {
+        None
+    }
+}
+
+/// Wire port with direction, vector bounds from packed dimensions and type,
+/// and array bounds from unpacked dimensions.
+///
+fn print_wire_port(xml: &mut String, port: &Port) {
+    let port_type = &port.port_type;
+
+    xml.push_str("      <ipxact:port>\n");
+    element(xml, 4, "name", &port.name);
+    if let Some(comment) = &port.comment {
+        element(xml, 4, "description", comment);
+    }
+    xml.push_str("        <ipxact:wire>\n");
+    element(xml, 5, "direction", match port.direction {
+        Some(Direction::Output) => "out",
+        Some(Direction::Inout) | Some(Direction::Ref) => "inout",
+        _ => "in",
+    });
+
+    let bounds: Vec<(String, String)> = port_type.packed_dims.iter()
+        .filter_map(|dim| dim_bounds(dim))
+        .collect();
+    if !bounds.is_empty() {
+        xml.push_str("          <ipxact:vectors>\n");
+        for (left, right) in bounds {
+            xml.push_str("            <ipxact:vector>\n");
+            element(xml, 7, "left", &left);
+            element(xml, 7, "right", &right);
+            xml.push_str("            </ipxact:vector>\n");
+        }
+        xml.push_str("          </ipxact:vectors>\n");
+    }
+
+    if let Some(type_name) = wire_type_name(port) {
+        xml.push_str("          <ipxact:wireTypeDefs>\n");
+        xml.push_str("            <ipxact:wireTypeDef>\n");
+        element(xml, 7, "typeName", &type_name);
+        element(xml, 7, "viewRef", "rtl");
+        xml.push_str("            </ipxact:wireTypeDef>\n");
+        xml.push_str("          </ipxact:wireTypeDefs>\n");
+    }
+
+    xml.push_str("        </ipxact:wire>\n");
+
+    let arrays: Vec<(String, String)> = port.unpacked_dims.iter()
+        .filter_map(|dim| array_bounds(dim))
+        .collect();
+    if !arrays.is_empty() {
+        xml.push_str("        <ipxact:arrays>\n");
+        for (left, right) in arrays {
+            xml.push_str("          <ipxact:array>\n");
+            element(xml, 6, "left", &left);
+            element(xml, 6, "right", &right);
+            xml.push_str("          </ipxact:array>\n");
+        }
+        xml.push_str("        </ipxact:arrays>\n");
+    }
+
+    xml.push_str("      </ipxact:port>\n");
+}
+
+/// Left and right bounds of packed dimension, `[7:0]` is `7` and `0`, `[8]` is `7` and `0`.
+/// Dynamic arrays and queues have no bounds.
+///
+pub fn dim_bounds(dim: &str) -> Option<(String, String)> {
+    let range = dim.trim().strip_prefix('[')?.strip_suffix(']')?.trim();
+    if range.is_empty() || range == "*" || range.starts_with('$') {
+        return None;
+    }
+
+    match range.split_once(':') {
+        Some((left, right)) => Some((left.trim().to_string(), right.trim().to_string())),
+        None => match range.parse::<u64>() {
+            Ok(size) if size > 0 => Some(((size - 1).to_string(), String::from("0"))),
+            _ => Some((format!("{}-1", range), String::from("0"))),
+        },
+    }
+}
+
+/// Left and right bounds of unpacked dimension, `[0:3]` is `0` and `3`, `[4]` is `0` and `3`.
+///
+pub fn array_bounds(dim: &str) -> Option<(String, String)> {
+    let (left, right) = dim_bounds(dim)?;
+
+    match dim.contains(':') {
+        true => Some((left, right)),
+        false => Some((right, left)),
+    }
+}
+
+/// Type of port without packed dimensions, like `logic` or `state_t`.
+///
+fn wire_type_name(port: &Port) -> Option<String> {
+    let port_type = &port.port_type;
+
+    if let Some(type_name) = &port_type.type_name {
+        return Some(type_name.clone());
+    }
+
+    let data_type = port_type.data_type.as_deref()
+        .map(|t| t.split('[').next().unwrap_or("").trim())
+        .filter(|t| !t.is_empty() && *t != "signed" && *t != "unsigned");
+
+    match (data_type, &port_type.net_type) {
+        (Some(data_type), _) => Some(data_type.to_string()),
+        (None, Some(net_type)) => Some(net_type.clone()),
+        (None, None) => None,
+    }
+}
+
+fn element(xml: &mut String, depth: usize, name: &str, value: &str) {
+    xml.push_str(format!("{}<ipxact:{}>{}</ipxact:{}>\n",
+        "  ".repeat(depth), name, xml_escape(value), name).as_str());
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,171 @@
+//! Export the design model as JSON for other tools.
+//!
+//! The file has `schema_version`, `generator` and `design` members,
+//! `design` is `struct Design` with all its fields, see README for the layout.
+//! Members are never removed or renamed without incrementing `SCHEMA_VERSION`,
+//! new members may be added in the same version.
+//!
+
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+use crate::mdbook::design::Design;
+
+/// Version of JSON layout, incremented on incompatible changes.
+pub const SCHEMA_VERSION: u32 = 1;
+
+const JSON_FILE: &str = "design.json";
+
+#[derive(Serialize)]
+struct Export<'a> {
+    schema_version: u32,
+    /// Name and version of the tool, like `svdocgen 0.1.0`.
+    generator: String,
+    design: &'a Design,
+}
+
+/// Write `design.json` into output directory.
+///
+pub fn emit(output_dir: &str, design: &Design) -> Result<(),String> {
+
+    let json_path = Path::new(output_dir).join(JSON_FILE);
+
+    match fs::write(&json_path, to_json(design)?) {
+        Err(e) => return Err(e.to_string()),
+        Ok(_) => println!("Created '{}'", json_path.display()),
+    }
+
+    Ok(())
+}
+
+/// Design as pretty printed JSON document.
+///
+pub fn to_json(design: &Design) -> Result<String,String> {
+    let export = Export {
+        schema_version: SCHEMA_VERSION,
+        generator: format!("svdocgen {}", env!("CARGO_PKG_VERSION")),
+        design,
+    };
+
+    serde_json::to_string_pretty(&export).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mdbook::design::parse_str;
+    use serde_json::Value;
+
+    /// Check that `value` is an object with `members`, as documented in README.
+    ///
+    fn check_members(value: &Value, entity: &str, members: &[&str]) {
+        let object = value.as_object()
+            .unwrap_or_else(|| panic!("{} is not an object: {}", entity, value));
+        for member in members {
+            assert!(object.contains_key(*member), "{} has no '{}': {}", entity, member, value);
+        }
+    }
+
+    #[test]
+    fn schema() {
+        let design = parse_str("
+            // Types.
+            package P;
+                import Q::*;
+                export *::*;
+                parameter int N = 2;
+                typedef enum logic [1:0] { IDLE, RUN } state_t;
+                function automatic int twice(int x); return 2 * x; endfunction
+            endpackage
+            interface Bus #(W = 8) (input logic clk);
+                logic [W-1:0] data;
+                modport src(output data);
+                clocking cb @(posedge clk); input data; endclocking
+            endinterface
+            class Base #(type T = int);
+                rand T value;
+                constraint c_value { value > 0; }
+            endclass
+            module Core #(W = 8) (input logic clk, rst_n, go, Bus.src bus, output logic [W-1:0] q);
+                import P::*;
+                state_t state;
+                always_ff @(posedge clk or negedge rst_n)
+                    if (!rst_n) state <= IDLE;
+                    else case (state)
+                        IDLE: if (go) state <= RUN;
+                        RUN: state <= IDLE;
+                    endcase
+            endmodule
+            module Top (input logic clk, rst_n);
+                Bus b (clk);
+                Core #(.W(4)) u_core (.clk, .rst_n, .go(1'b1), .bus(b), .q());
+            endmodule");
+
+        let json: Value = serde_json::from_str(&to_json(&design).unwrap()).unwrap();
+
+        assert_eq!(json["schema_version"], SCHEMA_VERSION);
+        assert_eq!(json["generator"], format!("svdocgen {}", env!("CARGO_PKG_VERSION")));
+        check_members(&json["design"], "design",
+            &["modules", "packages", "interfaces", "classes", "subroutines"]);
+
+        let design = &json["design"];
+        let entity = |kind: &str, members: &[&str]| {
+            let list = design[kind].as_array().unwrap();
+            assert!(!list.is_empty(), "no {}", kind);
+            for value in list {
+                check_members(value, kind, &["name", "location", "comment"]);
+                check_members(value, kind, members);
+                check_members(&value["location"], "location", &["file", "line"]);
+                assert_eq!(value["location"]["file"], "test.sv");
+            }
+        };
+        entity("modules", &["is_ansi", "parameters", "ports", "instances", "state_machines", "imports"]);
+        entity("packages", &["parameters", "typedefs", "imports", "exports"]);
+        entity("interfaces", &["parameters", "ports", "signals", "modports", "clocking_blocks", "imports"]);
+        entity("classes", &["parameters", "is_virtual", "extends", "base_name", "implements",
+            "properties", "constraints", "covergroups", "imports"]);
+        entity("subroutines", &["kind", "scope", "package", "return_type", "arguments",
+            "lifetime", "qualifiers"]);
+
+        let package = &design["packages"][0];
+        assert_eq!(package["comment"], "Types.");
+        check_members(&package["parameters"][0], "parameter", &["kind", "data_type", "default"]);
+        assert_eq!(package["parameters"][0]["kind"], "parameter");
+        check_members(&package["typedefs"][0], "typedef",
+            &["kind", "data_type", "packed", "width", "members"]);
+        assert_eq!(package["typedefs"][0]["kind"], "enum");
+
+        let subroutine = &design["subroutines"][0];
+        assert_eq!(subroutine["kind"], "function");
+        assert_eq!(subroutine["scope"]["kind"], "package");
+        assert_eq!(subroutine["scope"]["name"], "P");
+
+        let core = &design["modules"][0];
+        let port = &core["ports"][0];
+        check_members(port, "port", &["name", "direction", "port_type", "unpacked_dims", "comment"]);
+        assert_eq!(port["direction"], "input");
+        check_members(&port["port_type"], "port_type",
+            &["net_type", "data_type", "type_name", "packed_dims", "interface", "modport"]);
+        assert_eq!(core["ports"][3]["port_type"]["interface"], "Bus");
+        assert_eq!(core["ports"][3]["port_type"]["modport"], "src");
+
+        check_members(&core["state_machines"][0], "state_machine", &["state_var", "next_var",
+            "state_type", "states", "reset_state", "reset_condition", "transitions"]);
+        check_members(&core["state_machines"][0]["transitions"][0], "transition",
+            &["from", "to", "condition"]);
+
+        let instance = &design["modules"][1]["instances"][1];
+        check_members(instance, "instance",
+            &["module_name", "instance_name", "location", "parameters", "connections"]);
+        check_members(&instance["parameters"][0], "parameter override", &["name", "value"]);
+        assert_eq!(instance["parameters"][0]["value"], "4");
+        let connection = &instance["connections"][0];
+        check_members(connection, "connection", &["kind", "port", "expr", "signals"]);
+        assert_eq!(connection["kind"], "implicit");
+
+        // Missing optional values are null, lists are always present.
+        assert!(instance["connections"][4]["expr"].is_null());
+        assert_eq!(design["modules"][1]["parameters"], Value::Array(Vec::new()));
+    }
+}
@@ -0,0 +1,333 @@
+//! Markdown formatting helpers shared by SV page generators.
+//!
+//!
+//!
+
+use std::path::{Component, Path, PathBuf};
+use crate::mdbook::design::{Design, Direction, Parameter, Port, PortType, Scope, Subroutine};
+use crate::mdbook::files;
+use crate::mdbook::xref::Resolver;
+
+/// Make text safe to put into markdown table cell.
+///
+pub fn table_cell(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .replace('|', "\\|")
+}
+
+/// Same as `table_cell` but as inline code, empty if no text.
+///
+pub fn code_cell(text: &Option<String>) -> String {
+    match text {
+        Some(text) => format!("`{}`", table_cell(text)),
+        None => String::new(),
+    }
+}
+
+/// Print table of parameters with kind, type, default value and comment.
+///
+pub fn print_parameters(
+    text: &mut String,
+    params: &[Parameter]
+)
+{
+    if params.is_empty() {
+        return;
+    }
+
+    text.push_str("### Parameters: \n\n");
+    text.push_str("| Name | Kind | Type | Default | Description |\n");
+    text.push_str("|------|------|------|---------|-------------|\n");
+
+    for param in params {
+        text.push_str(format!("| `{}` | {} | {} | {} | {} |\n",
+            param.name,
+            param.kind.as_str(),
+            code_cell(&param.data_type),
+            code_cell(&param.default),
+            table_cell(param.comment.as_deref().unwrap_or(""))).as_str());
+    }
+
+    text.push_str("\n\n");
+}
+
+/// Print table of ports with direction, type and description.
+///
+pub fn print_ports(
+    text: &mut String,
+    ports: &[Port],
+    resolver: &Resolver,
+    page: &str
+)
+{
+    if ports.is_empty() {
+        return;
+    }
+
+    text.push_str("| Name | Direction | Type | Description |\n");
+    text.push_str("|------|-----------|------|-------------|\n");
+
+    for port in ports {
+        let port_type = &port.port_type;
+
+        let name_str = match port.unpacked_dims.is_empty() {
+            true => format!("`{}`", port.name),
+            false => format!("`{} {}`", port.name, port.unpacked_dims.concat()),
+        };
+
+        let (dir_str, type_str) = match &port_type.interface {
+            Some(iface) => {
+                let iface_str = match &port_type.modport {
+                    Some(modport) => format!("{}.{}", iface, modport),
+                    None => iface.clone(),
+                };
+                // Generic interface port `interface.mp` has no interface to link.
+                let iface_page = match iface.as_str() {
+                    "interface" => None,
+                    _ => resolver.iface_page(iface),
+                };
+                ("interface", type_link(&iface_str, iface_page, page))
+            }
+            None => {
+                let dir_str = match port.direction {
+                    Some(Direction::Input) => "➔ input",
+                    Some(Direction::Output) => "output ➔",
+                    Some(Direction::Inout) => "inout",
+                    Some(Direction::Ref) => "ref",
+                    _ => "?",
+                };
+
+                (dir_str, data_type_cell(resolver, port_type, page))
+            }
+        };
+
+        text.push_str(format!("| {} | {} | {} | {} |\n",
+            name_str,
+            dir_str,
+            type_str,
+            table_cell(port.comment.as_deref().unwrap_or(""))).as_str());
+    }
+}
+
+/// Net type and data type of port or signal, linked to user type page.
+///
+pub fn data_type_cell(resolver: &Resolver, port_type: &PortType, page: &str) -> String {
+    let type_str = [&port_type.net_type, &port_type.data_type]
+        .iter()
+        .filter_map(|t| t.as_deref())
+        .collect::<Vec<&str>>()
+        .join(" ");
+
+    let type_page = port_type.type_name.as_ref()
+        .and_then(|type_name| resolver.type_page(type_name));
+
+    match type_str.is_empty() {
+        true => String::new(),
+        false => type_link(&type_str, type_page, page),
+    }
+}
+
+/// Type as inline code, linked to the page where the type is defined if known.
+///
+pub fn type_link(type_str: &str, type_page: Option<String>, page: &str) -> String {
+    match type_page {
+        Some(type_page) => format!("[`{}`]({})",
+            table_cell(type_str), relative_link(page, &type_page)),
+        None => format!("`{}`", table_cell(type_str)),
+    }
+}
+
+/// Path of generated page for SV entity, relative to mdBook source directory,
+/// `kind` is one of `module`, `pkg`, `iface`, `class`.
+///
+pub fn page_path(file_path: &str, kind: &str, name: &str) -> String {
+    let mut path = files::book_path(Path::new(file_path));
+    path.set_extension(format!("{}.{}.md", kind, name));
+    path.to_str().unwrap().to_string()
+}
+
+/// Link to page `to` from page `from`, both relative to mdBook source directory.
+///
+pub fn relative_link(from: &str, to: &str) -> String {
+    let from_dir: Vec<Component> = Path::new(from).parent()
+        .map(|dir| dir.components().collect())
+        .unwrap_or_default();
+    let to: Vec<Component> = Path::new(to).components().collect();
+
+    let common = from_dir.iter().zip(to.iter()).take_while(|(a, b)| a == b).count();
+
+    let mut link = PathBuf::new();
+    for _ in common..from_dir.len() {
+        link.push("..");
+    }
+    for c in &to[common..] {
+        link.push(c.as_os_str());
+    }
+
+    link.to_str().unwrap().replace('\\', "/")
+}
+
+/// Page of the interface with given name.
+///
+pub fn iface_page(design: &Design, iface_name: &str) -> Option<String> {
+    design.interfaces.iter()
+        .find(|iface| iface.name == iface_name)
+        .map(|iface| page_path(&iface.location.file, "iface", &iface.name))
+}
+
+/// Page of the entity owning the scope, `None` for compilation unit
+/// or if the entity is not in the design.
+///
+pub fn scope_page(design: &Design, scope: &Scope) -> Option<String> {
+    match scope {
+        Scope::CompilationUnit => None,
+        Scope::Package(name) => design.packages.iter()
+            .find(|pkg| &pkg.name == name)
+            .map(|pkg| page_path(&pkg.location.file, "pkg", name)),
+        Scope::Module(name) => design.modules.iter()
+            .find(|module| &module.name == name)
+            .map(|module| page_path(&module.location.file, "module", name)),
+        Scope::Interface(name) => iface_page(design, name),
+        Scope::Class(name) => class_page(design, name),
+    }
+}
+
+/// Page of the class with given name.
+///
+pub fn class_page(design: &Design, class_name: &str) -> Option<String> {
+    design.classes.iter()
+        .find(|class| class.name == class_name)
+        .map(|class| page_path(&class.location.file, "class", &class.name))
+}
+
+/// Page of function or task.
+///
+pub fn subroutine_page(sub: &Subroutine) -> String {
+    let scope_name = sub.scope.name().trim_start_matches('$');
+    page_path(&sub.location.file, "func", &format!("{}.{}", scope_name, sub.name))
+}
+
+/// SV-like declaration of function or task, like
+/// `function automatic int f(input int a = 0)`.
+///
+pub fn subroutine_signature(sub: &Subroutine) -> String {
+    let mut words: Vec<&str> = sub.qualifiers.iter().map(|q| q.as_str()).collect();
+    words.push(sub.kind.as_str());
+    if let Some(lifetime) = &sub.lifetime {
+        words.push(lifetime);
+    }
+    if let Some(return_type) = &sub.return_type {
+        words.push(return_type);
+    }
+
+    let args: Vec<String> = sub.arguments.iter()
+        .map(|arg| {
+            let mut arg_str = arg.direction.clone();
+            if let Some(data_type) = &arg.data_type {
+                arg_str.push(' ');
+                arg_str.push_str(data_type);
+            }
+            arg_str.push(' ');
+            arg_str.push_str(&arg.name);
+            for dim in &arg.unpacked_dims {
+                arg_str.push_str(dim);
+            }
+            if let Some(default) = &arg.default {
+                arg_str.push_str(" = ");
+                arg_str.push_str(default);
+            }
+            arg_str
+        })
+        .collect();
+
+    format!("{} {}({})", words.join(" "), sub.name, args.join(", "))
+}
+
+/// Print list of functions and tasks declared in the scope,
+/// linked to their pages.
+///
+pub fn print_subroutine_list(
+    text: &mut String,
+    design: &Design,
+    scope: &Scope,
+    page: &str
+)
+{
+    let subs: Vec<&Subroutine> = design.subroutines.iter()
+        .filter(|sub| &sub.scope == scope)
+        .collect();
+
+    if subs.is_empty() {
+        return;
+    }
+
+    text.push_str("### Functions and tasks: \n\n");
+
+    for sub in subs {
+        text.push_str(format!("- {} [`{}`]({})",
+            sub.kind.as_str(), sub.name, relative_link(page, &subroutine_page(sub))).as_str());
+        if let Some(comment) = &sub.comment {
+            let summary = comment.lines().next().unwrap_or("");
+            text.push_str(format!(" — {}", summary).as_str());
+        }
+        text.push('\n');
+    }
+
+    text.push_str("\n\n");
+}
+
+/// Print table of modules instantiating the module or interface,
+/// and classes having properties of the class type.
+///
+pub fn print_used_by(
+    text: &mut String,
+    design: &Design,
+    name: &str,
+    page: &str
+)
+{
+    // Parent link, instance name and location.
+    let mut rows: Vec<(String, &str, String)> = Vec::new();
+
+    for module in &design.modules {
+        let module_page = page_path(&module.location.file, "module", &module.name);
+        for inst in module.instances.iter().filter(|inst| inst.module_name == name) {
+            rows.push((
+                format!("module [`{}`]({})", module.name, relative_link(page, &module_page)),
+                &inst.instance_name,
+                format!("{}:{}", inst.location.file, inst.location.line)));
+        }
+    }
+
+    for class in &design.classes {
+        let class_page = page_path(&class.location.file, "class", &class.name);
+        for prop in &class.properties {
+            // Class in package, like `Pkg::C`, is found by its name as class pages are.
+            let type_name = prop.data_type.as_deref()
+                .and_then(|t| t.split(|c: char| c.is_whitespace() || c == '[' || c == '#').next())
+                .map(|t| t.rsplit_once("::").map_or(t, |(_, n)| n));
+            if type_name == Some(name) {
+                rows.push((
+                    format!("class [`{}`]({})", class.name, relative_link(page, &class_page)),
+                    &prop.name,
+                    format!("{}:{}", prop.location.file, prop.location.line)));
+            }
+        }
+    }
+
+    if rows.is_empty() {
+        return;
+    }
+
+    text.push_str("### Used by: \n\n");
+    text.push_str("| Parent | Instance | Location |\n");
+    text.push_str("|--------|----------|----------|\n");
+
+    for (parent, instance, location) in rows {
+        text.push_str(format!("| {} | `{}` | `{}` |\n", parent, instance, location).as_str());
+    }
+
+    text.push_str("\n\n");
+}
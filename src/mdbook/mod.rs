@@ -3,10 +3,29 @@
 //! Most of the work is done by <https://rust-lang.github.io/mdBook/index.html>.
 
 pub mod files;    // collect SV files
+pub mod filelist; // read simulator-style filelists
 pub mod generate; // generate mdBook source files
+pub mod book;     // in-memory pages and table of contents
 pub mod svpar;    // parsing helpers
+pub mod design;   // parse SV files once into design model
+pub mod extract;  // extract design model from syntax tree
+pub mod typedef;  // extract typedefs and compute type widths
+pub mod consteval; // evaluate constant expressions
+pub mod hierarchy; // elaborate design hierarchy from top modules
+pub mod xref;     // resolve references to entity pages
+pub mod markdown; // markdown formatting helpers
 pub mod svmodule; // generate md file with SV module info
+pub mod symbol;   // draw module block symbols
+pub mod schematic; // build module connectivity schematics
+pub mod fsm;      // find state machines in modules
+pub mod diagram;  // render svgbob and WaveDrom blocks as SVG
+pub mod wavedrom; // draw WaveDrom diagrams
 pub mod sviface;  // generate md file with SV interface info
 pub mod svclass;
 pub mod svpkg;    // generate md file with SV package info
+pub mod svfunc;   // generate md files with SV function and task info
 pub mod build;    // build mdBook
+pub mod json;     // export design model as JSON
+pub mod ipxact;   // export modules as IP-XACT components
+pub mod render;   // render design to selected output format
+pub mod html;     // render book as static HTML pages
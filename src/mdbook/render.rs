@@ -0,0 +1,308 @@
+//! Render the extracted design to output formats.
+//!
+//! Every output format is a `Renderer` selected by name with `--format`.
+//! Renderers get the parsed design and the generated pages from `Context`,
+//! library users can add formats by implementing `Renderer`
+//! and passing it to `render` together with `renderers()`.
+//! Errors carry the exit code of `svdocgen`, see `RenderError`.
+//!
+
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::args;
+use crate::mdbook;
+use crate::mdbook::book::{self, Book};
+use crate::mdbook::design::Design;
+use crate::mdbook::files::SrcFiles;
+
+/// Exit code of invalid options, the same as of command line errors.
+pub const EXIT_USAGE: i32 = 1;
+/// Exit code of failed parsing or page generation.
+pub const EXIT_GENERATION: i32 = 2;
+/// Exit code of failed mdBook build.
+pub const EXIT_MDBOOK_BUILD: i32 = 3;
+/// Exit code of failed JSON export.
+pub const EXIT_JSON: i32 = 4;
+/// Exit code of failed IP-XACT export.
+pub const EXIT_IPXACT: i32 = 5;
+
+/// Error of renderer with exit code, `String` errors are generation errors.
+///
+#[derive(Debug)]
+pub struct RenderError {
+    pub exit_code: i32,
+    pub message: String,
+}
+
+impl RenderError {
+    pub fn new(exit_code: i32, message: String) -> RenderError {
+        RenderError { exit_code, message }
+    }
+}
+
+impl From<String> for RenderError {
+    fn from(message: String) -> Self {
+        RenderError::new(EXIT_GENERATION, message)
+    }
+}
+
+impl std::fmt::Display for RenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Output format.
+///
+pub trait Renderer {
+    /// Name used to select the renderer with `--format`.
+    fn name(&self) -> &str;
+
+    /// Write output for the design into `ctx.options.output_dir`.
+    fn render(&self, ctx: &Context) -> Result<(),RenderError>;
+}
+
+/// Everything renderers need, the design is parsed once for all renderers.
+///
+pub struct Context<'a> {
+    pub options: &'a args::ParsedOptions,
+    pub src_files: SrcFiles,
+    pub design: Design,
+    book: OnceCell<Book>,
+}
+
+impl<'a> Context<'a> {
+    /// Collect input files and parse them.
+    ///
+    pub fn new(options: &'a args::ParsedOptions) -> Result<Context<'a>,String> {
+        let src_files = mdbook::files::collect_sources(options)?;
+
+        let sv_files = mdbook::files::get_sv_files(&src_files)?;
+
+        let design = mdbook::design::parse_sources(
+            &sv_files, &src_files.incdirs, &src_files.defines)?;
+
+        Ok(Context { options, src_files, design, book: OnceCell::new() })
+    }
+
+    /// Generated pages, generated on first use.
+    ///
+    pub fn book(&self) -> Result<&Book,String> {
+        if let Some(book) = self.book.get() {
+            return Ok(book);
+        }
+
+        let book = mdbook::generate::generate_book(self.options, &self.src_files, &self.design)?;
+
+        Ok(self.book.get_or_init(|| book))
+    }
+
+    /// Create output directory.
+    ///
+    pub fn create_output_dir(&self) -> Result<(),String> {
+        match fs::create_dir_all(&self.options.output_dir) {
+            Err(e) => { println!("Can't create output directory '{}' error: {}",
+                            &self.options.output_dir, e);
+                        Err(e.to_string()) },
+            Ok(_) => { println!("Created output directory '{}'", &self.options.output_dir);
+                       Ok(()) },
+        }
+    }
+}
+
+/// Built-in renderers.
+///
+pub fn renderers() -> Vec<Box<dyn Renderer>> {
+    vec![
+        Box::new(MdBookRenderer),
+        Box::new(mdbook::html::HtmlRenderer),
+        Box::new(MarkdownRenderer),
+        Box::new(JsonRenderer),
+        Box::new(IpXactRenderer),
+    ]
+}
+
+/// Parse inputs and render them with renderer named `options.format`.
+///
+pub fn render(
+    options: &args::ParsedOptions,
+    renderers: &[Box<dyn Renderer>]
+) -> Result<(),RenderError>
+{
+    let renderer = match renderers.iter().find(|r| r.name() == options.format) {
+        Some(renderer) => renderer,
+        None => {
+            let names: Vec<&str> = renderers.iter().map(|r| r.name()).collect();
+            return Err(RenderError::from(format!("unknown format '{}', expected one of: {}",
+                options.format, names.join(", "))));
+        }
+    };
+
+    // Checked before anything is parsed or written.
+    if options.js_libs == "bundled" && !mdbook::build::has_bundled_js() {
+        return Err(RenderError::new(EXIT_USAGE, String::from(
+            "svdocgen was built without bundled JavaScript libraries, \
+            run `make -C assets/js` and rebuild it, or use `--js-libs cdn`")));
+    }
+
+    let ctx = Context::new(options)?;
+
+    renderer.render(&ctx)
+}
+
+/// mdBook sources in `src`, `SUMMARY.md` and `book.toml`, then the book in `book`.
+///
+pub struct MdBookRenderer;
+
+impl Renderer for MdBookRenderer {
+    fn name(&self) -> &str { "mdbook" }
+
+    fn render(&self, ctx: &Context) -> Result<(),RenderError> {
+        mdbook::generate::write_mdbook(&ctx.options.output_dir, ctx.book()?)?;
+
+        mdbook::build::build(ctx.options)
+            .map_err(|e| RenderError::new(EXIT_MDBOOK_BUILD, e))
+    }
+}
+
+/// All pages in one markdown file with links between pages turned into anchors.
+///
+pub struct MarkdownRenderer;
+
+impl Renderer for MarkdownRenderer {
+    fn name(&self) -> &str { "markdown" }
+
+    fn render(&self, ctx: &Context) -> Result<(),RenderError> {
+        ctx.create_output_dir()?;
+
+        let book = ctx.book()?;
+
+        // Linked files are written at the same paths relative to the document.
+        let output_dir = Path::new(&ctx.options.output_dir);
+        mdbook::generate::copy_assets(output_dir, book)?;
+        mdbook::generate::write_pages(output_dir, &book.files)?;
+
+        let md_path = output_dir.join(match book.title.is_empty() {
+            true => String::from("design.md"),
+            false => format!("{}.md", book.title),
+        });
+
+        match fs::write(&md_path, single_markdown(book)) {
+            Err(e) => return Err(RenderError::from(e.to_string())),
+            Ok(_) => println!("Created '{}'", md_path.display()),
+        }
+
+        Ok(())
+    }
+}
+
+/// `design.json` with the design model, see `json.rs`.
+///
+pub struct JsonRenderer;
+
+impl Renderer for JsonRenderer {
+    fn name(&self) -> &str { "json" }
+
+    fn render(&self, ctx: &Context) -> Result<(),RenderError> {
+        ctx.create_output_dir()
+            .and_then(|_| mdbook::json::emit(&ctx.options.output_dir, &ctx.design))
+            .map_err(|e| RenderError::new(EXIT_JSON, e))
+    }
+}
+
+/// IP-XACT component of every module, see `ipxact.rs`.
+///
+pub struct IpXactRenderer;
+
+impl Renderer for IpXactRenderer {
+    fn name(&self) -> &str { "ipxact" }
+
+    fn render(&self, ctx: &Context) -> Result<(),RenderError> {
+        mdbook::ipxact::emit(ctx.options, &ctx.design)
+            .map_err(|e| RenderError::new(EXIT_IPXACT, e))
+    }
+}
+
+/// Anchor of page in single document, like `src-rtl-top-module-top-md`.
+///
+pub fn page_anchor(path: &str) -> String {
+    path.chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_lowercase(),
+            false => '-',
+        })
+        .collect()
+}
+
+/// All chapter pages in table of contents order as one markdown document.
+///
+/// Links to pages become links to page anchors,
+/// links to other files become relative to the book `src` directory.
+///
+pub fn single_markdown(book: &Book) -> String {
+    let pages: HashMap<&str, String> = book.pages.iter()
+        .map(|page| (page.path.as_str(), page_anchor(&page.path)))
+        .collect();
+
+    let mut text = String::new();
+
+    if !book.title.is_empty() {
+        text.push_str(format!("# {}\n\n", book.title).as_str());
+    }
+
+    for (_chapter, page) in book.chapter_pages() {
+        text.push_str(format!("<a id=\"{}\"></a>\n\n", page_anchor(&page.path)).as_str());
+        text.push_str(&book::rewrite_links(page, |target, _fragment| {
+            match pages.get(target) {
+                Some(anchor) => Some(format!("#{}", anchor)),
+                None => Some(target.to_string()),
+            }
+        }));
+        text.push_str("\n\n");
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_names() {
+        let names: Vec<String> = renderers().iter().map(|r| r.name().to_string()).collect();
+
+        assert_eq!(names, args::FORMATS);
+    }
+
+    #[cfg(not(bundled_js))]
+    #[test]
+    fn bundled_js_missing() {
+        let output_dir = std::env::temp_dir().join(format!("svdocgen-render-{}", std::process::id()));
+        let options = args::ParsedOptions {
+            output_dir: output_dir.to_str().unwrap().to_string(),
+            inputs: vec![String::from("samples/sample1")],
+            filelists: Vec::new(),
+            includes: Vec::new(),
+            incdirs: Vec::new(),
+            defines: Vec::new(),
+            tops: Vec::new(),
+            collapse_buses: false,
+            format: String::from("mdbook"),
+            js_libs: String::from("bundled"),
+            project_name: String::new(),
+        };
+
+        let e = render(&options, &renderers()).unwrap_err();
+        assert_eq!(e.exit_code, EXIT_USAGE);
+        assert!(!output_dir.exists());
+    }
+
+    #[test]
+    fn anchors() {
+        assert_eq!(page_anchor("src/rtl/Top.module.Top.md"), "src-rtl-top-module-top-md");
+    }
+}
@@ -0,0 +1,443 @@
+//! Build connectivity schematic of module from port connections of its instances.
+//!
+//! Nets are named by connected expressions, like `data[7:0]`,
+//! or by signal names only when buses are collapsed.
+//! Schematic is written as Graphviz DOT and as Mermaid flowchart.
+//!
+
+use crate::mdbook::design::{ConnectionKind, Design, Direction, Instance, Module, Port};
+
+/// Port of instance with what is connected to it.
+///
+#[derive(Clone, Debug)]
+pub struct PinConnection {
+    /// Port name, `#N` for positional connection to unknown module.
+    pub port: String,
+    pub direction: Option<Direction>,
+    /// How the port is connected, `None` if the port is not listed at all.
+    pub kind: Option<ConnectionKind>,
+    /// Connected expression, `None` if the port is left unconnected.
+    pub expr: Option<String>,
+    pub signals: Vec<String>,
+}
+
+/// Net connecting pins of instances and ports of the module.
+///
+pub struct Net {
+    pub name: String,
+    /// Instance index, port name and direction.
+    pub pins: Vec<(usize, String, Option<Direction>)>,
+    /// Ports of the module connected to the net.
+    pub ports: Vec<(String, Option<Direction>)>,
+}
+
+/// Ports of instantiated module or interface, if it is in the design.
+///
+pub fn instance_ports<'a>(design: &'a Design, module_name: &str) -> Option<&'a [Port]> {
+    design.modules.iter()
+        .find(|m| m.name == module_name)
+        .map(|m| m.ports.as_slice())
+        .or_else(|| design.interfaces.iter()
+            .find(|i| i.name == module_name)
+            .map(|i| i.ports.as_slice()))
+}
+
+/// Resolve positional and wildcard connections to port names
+/// and add ports of instantiated module which are not connected.
+///
+pub fn resolve_connections(design: &Design, inst: &Instance) -> Vec<PinConnection> {
+    let ports = instance_ports(design, &inst.module_name);
+    let direction = |name: &str| ports
+        .and_then(|ports| ports.iter().find(|p| p.name == name))
+        .and_then(|p| p.direction);
+
+    let mut pins: Vec<PinConnection> = Vec::new();
+    let mut position = 0;
+    let mut wildcard = false;
+
+    for conn in &inst.connections {
+        let port = match (conn.kind, &conn.port) {
+            (ConnectionKind::Wildcard, _) => { wildcard = true; continue; }
+            (ConnectionKind::Positional, _) => {
+                position += 1;
+                match ports.and_then(|ports| ports.get(position - 1)) {
+                    Some(port) => port.name.clone(),
+                    None => format!("#{}", position),
+                }
+            }
+            (_, Some(port)) => port.clone(),
+            (_, None) => continue,
+        };
+        pins.push(PinConnection {
+            direction: direction(&port),
+            port,
+            kind: Some(conn.kind),
+            expr: conn.expr.clone(),
+            signals: conn.signals.clone(),
+        });
+    }
+
+    // Ports not listed are connected by `.*` or left unconnected.
+    for port in ports.unwrap_or(&[]) {
+        if pins.iter().any(|pin| pin.port == port.name) {
+            continue;
+        }
+        pins.push(match wildcard {
+            true => PinConnection {
+                port: port.name.clone(),
+                direction: port.direction,
+                kind: Some(ConnectionKind::Wildcard),
+                expr: Some(port.name.clone()),
+                signals: vec![port.name.clone()],
+            },
+            false => PinConnection {
+                port: port.name.clone(),
+                direction: port.direction,
+                kind: None,
+                expr: None,
+                signals: Vec::new(),
+            },
+        });
+    }
+
+    pins
+}
+
+/// Nets of the module, in order of first connection.
+///
+pub fn module_nets(design: &Design, module: &Module, collapse_buses: bool) -> Vec<Net> {
+    let mut nets: Vec<Net> = Vec::new();
+    // Signals used by every net, to find connected module ports.
+    let mut net_signals: Vec<Vec<String>> = Vec::new();
+
+    for (index, inst) in module.instances.iter().enumerate() {
+        for pin in resolve_connections(design, inst) {
+            let expr = match &pin.expr {
+                Some(expr) if !pin.signals.is_empty() => expr,
+                // Unconnected or tied to a constant.
+                _ => continue,
+            };
+
+            let names: Vec<String> = match collapse_buses {
+                true => pin.signals.clone(),
+                false => vec![expr.clone()],
+            };
+
+            for name in names {
+                let i = match nets.iter().position(|n| n.name == name) {
+                    Some(i) => i,
+                    None => {
+                        nets.push(Net { name: name.clone(), pins: Vec::new(), ports: Vec::new() });
+                        net_signals.push(Vec::new());
+                        nets.len() - 1
+                    }
+                };
+                nets[i].pins.push((index, pin.port.clone(), pin.direction));
+                for signal in &pin.signals {
+                    if !net_signals[i].contains(signal) && (!collapse_buses || signal == &name) {
+                        net_signals[i].push(signal.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    for (net, signals) in nets.iter_mut().zip(net_signals) {
+        for port in &module.ports {
+            if signals.contains(&port.name) {
+                net.ports.push((port.name.clone(), port.direction));
+            }
+        }
+    }
+
+    nets
+}
+
+/// Endpoint of net: node id, pin name, and whether it drives the net.
+///
+type Endpoint = (String, Option<String>, Option<bool>);
+
+fn net_endpoints(module: &Module, net: &Net) -> Vec<Endpoint> {
+    let mut endpoints: Vec<Endpoint> = Vec::new();
+
+    for (name, direction) in &net.ports {
+        let drives = match direction {
+            Some(Direction::Input) => Some(true),
+            Some(Direction::Output) => Some(false),
+            _ => None,
+        };
+        endpoints.push((port_id(module, name), None, drives));
+    }
+    for (index, port, direction) in &net.pins {
+        let drives = match direction {
+            Some(Direction::Output) => Some(true),
+            Some(Direction::Input) => Some(false),
+            _ => None,
+        };
+        endpoints.push((format!("i{}", index), Some(port.clone()), drives));
+    }
+
+    endpoints
+}
+
+/// Edges of net: from drivers to all other endpoints,
+/// or a chain of all endpoints if driver is not known.
+///
+fn net_edges(module: &Module, net: &Net) -> Vec<(Endpoint, Endpoint, bool)> {
+    let endpoints = net_endpoints(module, net);
+    let mut edges = Vec::new();
+
+    let drivers: Vec<&Endpoint> = endpoints.iter().filter(|e| e.2 == Some(true)).collect();
+    match drivers.is_empty() {
+        true => {
+            for pair in endpoints.windows(2) {
+                edges.push((pair[0].clone(), pair[1].clone(), false));
+            }
+        }
+        false => {
+            for driver in drivers {
+                for other in endpoints.iter().filter(|e| e.2 != Some(true)) {
+                    edges.push((driver.clone(), other.clone(), true));
+                }
+            }
+        }
+    }
+
+    edges
+}
+
+/// Node id of module port by its index, names may be escaped identifiers.
+///
+fn port_id(module: &Module, name: &str) -> String {
+    let index = module.ports.iter().position(|p| p.name == name).unwrap_or(module.ports.len());
+    format!("p{}", index)
+}
+
+/// Schematic as Graphviz DOT graph.
+///
+pub fn to_dot(module: &Module, nets: &[Net]) -> String {
+    let mut dot = String::new();
+
+    dot.push_str(format!("digraph \"{}\" {{\n", dot_escape(&module.name)).as_str());
+    dot.push_str("  rankdir=LR;\n");
+    dot.push_str("  node [fontname=\"monospace\"];\n");
+    dot.push_str("  edge [fontname=\"monospace\", fontsize=10];\n");
+
+    for (index, port) in module.ports.iter().enumerate() {
+        let shape = match port.direction {
+            Some(Direction::Output) => "rarrow",
+            Some(Direction::Inout) => "hexagon",
+            _ => "cds",
+        };
+        dot.push_str(format!("  \"p{}\" [shape={}, label=\"{}\"];\n",
+            index, shape, dot_escape(&port.name)).as_str());
+    }
+
+    for (index, inst) in module.instances.iter().enumerate() {
+        dot.push_str(format!("  \"i{}\" [shape=box, label=\"{}\\n{}\"];\n",
+            index, dot_escape(&inst.instance_name), dot_escape(&inst.module_name)).as_str());
+    }
+
+    for net in nets {
+        for (from, to, directed) in net_edges(module, net) {
+            let mut attrs = vec![format!("label=\"{}\"", dot_escape(&net.name))];
+            if let Some(pin) = &from.1 {
+                attrs.push(format!("taillabel=\"{}\"", dot_escape(pin)));
+            }
+            if let Some(pin) = &to.1 {
+                attrs.push(format!("headlabel=\"{}\"", dot_escape(pin)));
+            }
+            if !directed {
+                attrs.push(String::from("dir=none"));
+            }
+            dot.push_str(format!("  \"{}\" -> \"{}\" [{}];\n", from.0, to.0, attrs.join(", ")).as_str());
+        }
+    }
+
+    dot.push_str("}\n");
+
+    dot
+}
+
+/// Schematic as Mermaid flowchart inside `<div class="mermaid">`.
+///
+pub fn to_mermaid(module: &Module, nets: &[Net]) -> String {
+    let mut text = String::new();
+
+    text.push_str("<div class=\"mermaid\">\nflowchart LR\n");
+
+    // Only ports connected to instances, other ports would float around.
+    for (index, port) in module.ports.iter().enumerate() {
+        if nets.iter().any(|n| n.ports.iter().any(|p| p.0 == port.name)) {
+            text.push_str(format!("  p{}([\"{}\"])\n",
+                index, mermaid_escape(&port.name)).as_str());
+        }
+    }
+
+    for (index, inst) in module.instances.iter().enumerate() {
+        text.push_str(format!("  i{}[\"{}<br/>{}\"]\n",
+            index, mermaid_escape(&inst.instance_name), mermaid_escape(&inst.module_name)).as_str());
+    }
+
+    for net in nets {
+        for (from, to, directed) in net_edges(module, net) {
+            let arrow = match directed {
+                true => "-->",
+                false => "---",
+            };
+            text.push_str(format!("  {} {}|\"{}\"| {}\n",
+                from.0, arrow, mermaid_escape(&net.name), to.0).as_str());
+        }
+    }
+
+    text.push_str("</div>\n");
+
+    text
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn mermaid_escape(text: &str) -> String {
+    text.replace('&', "#amp;")
+        .replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mdbook::design::parse_str;
+
+    fn summary(nets: &[Net]) -> Vec<String> {
+        nets.iter()
+            .map(|n| format!("{}: {} | {}", n.name,
+                n.pins.iter().map(|p| format!("i{}.{}", p.0, p.1)).collect::<Vec<_>>().join(" "),
+                n.ports.iter().map(|p| p.0.as_str()).collect::<Vec<_>>().join(" ")))
+            .collect()
+    }
+
+    #[test]
+    fn nets() {
+        let design = parse_str("
+            module Sub (input logic [7:0] a, input logic en, output logic [7:0] y);
+            endmodule
+            module Top (input logic [3:0] hi, lo, input logic en, output logic [7:0] y);
+                Sub u_a (.a({hi, lo[3:0]}), .en(1'b1), .y(y));
+                Sub u_b (.a({hi, 4'h0}), .en(en), .y());
+            endmodule");
+        let module = &design.modules[1];
+
+        // Constants and unconnected ports make no nets.
+        assert_eq!(summary(&module_nets(&design, module, false)), [
+            "{hi, lo[3:0]}: i0.a | hi lo",
+            "y: i0.y | y",
+            "{hi, 4'h0}: i1.a | hi",
+            "en: i1.en | en",
+        ]);
+        assert_eq!(summary(&module_nets(&design, module, true)), [
+            "hi: i0.a i1.a | hi",
+            "lo: i0.a | lo",
+            "y: i0.y | y",
+            "en: i1.en | en",
+        ]);
+    }
+
+    #[test]
+    fn escaped_names() {
+        let design = parse_str(r#"
+            module Sub (input logic [7:0] a, input logic en, output logic [7:0] y);
+            endmodule
+            module \Top<1> (input logic [15:0] \data"in , input logic en, output logic [7:0] \q[0] );
+                logic [7:0] mid;
+                Sub \u&a (.a(\data"in [7:0]), .en, .y(mid));
+                Sub u_b (mid, en, \q[0] );
+            endmodule"#);
+        let module = &design.modules[1];
+        let nets = module_nets(&design, module, false);
+
+        assert_eq!(summary(&nets), [
+            r#"\data"in [7:0]: i0.a | \data"in"#,
+            "en: i0.en i1.en | en",
+            "mid: i0.y i1.a | ",
+            r"\q[0]: i1.y | \q[0]",
+        ]);
+
+        let dot = to_dot(module, &nets);
+        assert!(dot.starts_with("digraph \"\\\\Top<1>\" {\n"));
+        for line in [
+            r#"  "p0" [shape=cds, label="\\data\"in"];"#,
+            r#"  "p2" [shape=rarrow, label="\\q[0]"];"#,
+            r#"  "i0" [shape=box, label="\\u&a\nSub"];"#,
+            r#"  "p0" -> "i0" [label="\\data\"in [7:0]", headlabel="a"];"#,
+            r#"  "i0" -> "i1" [label="mid", taillabel="y", headlabel="a"];"#,
+            r#"  "i1" -> "p2" [label="\\q[0]", taillabel="y"];"#,
+        ] {
+            assert!(dot.lines().any(|l| l == line), "{}", line);
+        }
+
+        let mermaid = to_mermaid(module, &nets);
+        for line in [
+            r#"  p0(["\data#quot;in"])"#,
+            r#"  p2(["\q[0]"])"#,
+            r#"  i0["\u#amp;a<br/>Sub"]"#,
+            r#"  p0 -->|"\data#quot;in [7:0]"| i0"#,
+            r#"  i1 -->|"\q[0]"| p2"#,
+        ] {
+            assert!(mermaid.lines().any(|l| l == line), "{}", line);
+        }
+    }
+
+    #[test]
+    fn connections() {
+        use ConnectionKind::*;
+        use Direction::*;
+
+        let design = parse_str("
+            module Sub (input logic a, input logic b, output logic y, inout wire z);
+            endmodule
+            module Top (input logic a, b, output logic y);
+                Sub u_pos (a, , y);
+                Sub u_named (.a(a), .b(), .y, .nope(b));
+                Sub u_wild (.b(1'b0), .*);
+                Ext u_ext (a, b);
+                Ext u_ext_named (.p(a));
+            endmodule");
+        let pins = |index: usize| resolve_connections(&design, &design.modules[1].instances[index])
+            .into_iter()
+            .map(|p| (p.port, p.direction, p.kind, p.expr))
+            .collect::<Vec<_>>();
+        let pin = |port: &str, direction, kind, expr: Option<&str>|
+            (port.to_string(), direction, kind, expr.map(String::from));
+
+        // Empty positional connection and port not listed at all are both unconnected.
+        assert_eq!(pins(0), [
+            pin("a", Some(Input), Some(Positional), Some("a")),
+            pin("b", Some(Input), Some(Positional), None),
+            pin("y", Some(Output), Some(Positional), Some("y")),
+            pin("z", Some(Inout), None, None),
+        ]);
+        assert_eq!(pins(1), [
+            pin("a", Some(Input), Some(Named), Some("a")),
+            pin("b", Some(Input), Some(Named), None),
+            pin("y", Some(Output), Some(Implicit), Some("y")),
+            pin("nope", None, Some(Named), Some("b")),
+            pin("z", Some(Inout), None, None),
+        ]);
+        assert_eq!(pins(2), [
+            pin("b", Some(Input), Some(Named), Some("1'b0")),
+            pin("a", Some(Input), Some(Wildcard), Some("a")),
+            pin("y", Some(Output), Some(Wildcard), Some("y")),
+            pin("z", Some(Inout), Some(Wildcard), Some("z")),
+        ]);
+
+        // Ports of unknown module are known only by position or name.
+        assert_eq!(pins(3), [
+            pin("#1", None, Some(Positional), Some("a")),
+            pin("#2", None, Some(Positional), Some("b")),
+        ]);
+        assert_eq!(pins(4), [pin("p", None, Some(Named), Some("a"))]);
+    }
+}
@@ -1,56 +1,24 @@
-//! Parse SV file and generate info about Verilog class(s).
+//! Generate info about Verilog class(s).
 //!
 //!
 //!
 
-use sv_parser::{parse_sv, unwrap_node, /*unwrap_locate, Locate,*/ RefNode, SyntaxTree};
-//use sv_parser::{PortDirection, NetType, IntegerVectorType};
-use std::collections::HashMap;
-use std::path::{Path, PathBuf};
-//use regex::Regex;
-use crate::mdbook::svpar;
+use crate::mdbook::design::{Design, Class, Scope, Subroutine};
+use crate::mdbook::book::Book;
+use crate::mdbook::markdown;
+use crate::mdbook::xref::Resolver;
 
 pub fn generate_sv_class_info(
-    output_path: &str,
-    file_path: &str
+    book: &mut Book,
+    design: &Design
 ) -> (Vec<String>, Vec<(String, String, String)>)
 {
     let mut text: Vec<String> = Vec::new();
     let mut list: Vec<(String, String, String)> = Vec::new();
 
-    // The list of defined macros
-    let defines = HashMap::new();
-    // The list of include paths
-    let includes: Vec<PathBuf> = Vec::new();
-
-    // Parse
-    let result = parse_sv(file_path, &defines, &includes, false, true);
-
-    if let Ok((syntax_tree, _)) = result {
-        let mut prev_node: Option<RefNode> = None;
-        // &SyntaxTree is iterable
-        for node in &syntax_tree {
-            // The type of each node is RefNode
-            match node {
-                RefNode::ClassDeclaration(x) => {
-                    let id = unwrap_node!(x, ClassIdentifier).unwrap();
-                    let id = svpar::get_identifier(id).unwrap();
-                    let id = syntax_tree.get_str(&id).unwrap();
-                    let item = print_class(&mut text, output_path, file_path, id,
-                        &syntax_tree, &node, &prev_node);
-                    list.push(item)
-                }
-                RefNode::Description(_) => {
-                }
-                RefNode::WhiteSpace(_) => {
-                }
-                RefNode::Locate(_) => {
-                }
-                x => { prev_node = Some(x); () },
-            }
-        }
-    } else {
-        println!("parsing of '{}' failed\n", file_path);
+    for class in &design.classes {
+        let item = print_class(&mut text, book, class, design);
+        list.push(item)
     }
 
     (text, list)
@@ -58,38 +26,381 @@ pub fn generate_sv_class_info(
 
 fn print_class(
     top_text: &mut Vec<String>,
-    output_path: &str,
-    file_path: &str,
-    class_name: &str,
-    _syntax_tree: &SyntaxTree,
-    _class_node: &RefNode,
-    _prev_node: &Option<RefNode>
+    book: &mut Book,
+    class: &Class,
+    design: &Design
 ) -> (String, String, String)
 {
+    let class_name = class.name.as_str();
+    let file_path = class.location.file.as_str();
+
     let mut text = String::new();
 
     text.push_str(format!("## Class `{}`\n\n", class_name).as_str());
     text.push_str(format!("File: `{}`\n\n", file_path).as_str());
 
-    /*text.push_str(format!("### Ports: \n\n").as_str());
-    if is_ansi {
-        print_ansi_ports(&mut text, syntax_tree, module_node);
-    }
+    let page = markdown::page_path(file_path, "class", class_name);
 
-    text.push_str(format!("\n\n### Instantiates modules: \n\n").as_str());
-    print_instantiated_modules(&mut text, syntax_tree, module_node);*/
+    let scope = Scope::Class(class_name.to_string());
+    let resolver = Resolver::new(design, &scope);
 
-    //print_module_comments(&mut text, syntax_tree, module_node, prev_node);
+    print_class_header(&mut text, class, &resolver, &page);
+    print_class_diagram(&mut text, class, design);
 
-    let mut class_path = Path::new(output_path).join("src").join(file_path);
-    class_path.set_extension(format!("class.{}.md", class_name));
-    let mut src_class_path = Path::new("src").join(file_path);
-    src_class_path.set_extension(format!("class.{}.md", class_name));
-    let src_class_path = src_class_path.to_str().unwrap();
+    markdown::print_parameters(&mut text, &class.parameters);
 
-    std::fs::write(class_path, text).expect("failed to write file");
+    print_properties(&mut text, class, &resolver, &page);
+    print_methods(&mut text, class, design, &page);
+    print_constraints(&mut text, class);
+    print_covergroups(&mut text, class);
+    print_inherited_members(&mut text, class, design, &page);
+    markdown::print_used_by(&mut text, design, class_name, &page);
+
+    if let Some(comment) = &class.comment {
+        text.push_str("\n\n### Description:\n\n");
+        text.push_str(comment.as_str());
+        text.push('\n');
+    }
+
+    let src_class_path = page.as_str();
+
+    book.add_page(src_class_path, text);
 
     top_text.push(format!("- [`{}  :{}`]({})\n", class_name, file_path, src_class_path));
 
     (class_name.to_string(), file_path.to_string(), src_class_path.to_string())
-}
\ No newline at end of file
+}
+
+/// Print `extends` and `implements` of class, linked to base class pages.
+///
+fn print_class_header(
+    text: &mut String,
+    class: &Class,
+    resolver: &Resolver,
+    page: &str
+)
+{
+    if class.is_virtual {
+        text.push_str("Virtual class\n\n");
+    }
+
+    if let Some(extends) = &class.extends {
+        let base_page = class.base_name.as_ref()
+            .and_then(|base| resolver.class_page(base));
+        text.push_str(format!("Extends: {}\n\n", markdown::type_link(extends, base_page, page)).as_str());
+    }
+
+    if !class.implements.is_empty() {
+        let implements: Vec<String> = class.implements.iter()
+            .map(|i| format!("`{}`", i))
+            .collect();
+        text.push_str(format!("Implements: {}\n\n", implements.join(", ")).as_str());
+    }
+}
+
+fn print_properties(
+    text: &mut String,
+    class: &Class,
+    resolver: &Resolver,
+    page: &str
+)
+{
+    if class.properties.is_empty() {
+        return;
+    }
+
+    text.push_str("### Properties: \n\n");
+    text.push_str("| Name | Type | Qualifiers | Description |\n");
+    text.push_str("|------|------|------------|-------------|\n");
+
+    for prop in &class.properties {
+        let name_str = match prop.unpacked_dims.is_empty() {
+            true => format!("`{}`", prop.name),
+            false => format!("`{} {}`", prop.name, prop.unpacked_dims.concat()),
+        };
+
+        let type_str = match &prop.data_type {
+            Some(data_type) => markdown::type_link(data_type, resolver.type_str_page(data_type), page),
+            None => String::new(),
+        };
+
+        let mut description = prop.comment.as_deref().unwrap_or("").to_string();
+        if let Some(value) = &prop.value {
+            description = format!("= `{}` {}", markdown::table_cell(value), description);
+        }
+
+        text.push_str(format!("| {} | {} | {} | {} |\n",
+            name_str,
+            type_str,
+            prop.qualifiers.join(" "),
+            markdown::table_cell(&description)).as_str());
+    }
+
+    text.push_str("\n\n");
+}
+
+/// Print signatures of methods, linked to their pages.
+///
+fn print_methods(
+    text: &mut String,
+    class: &Class,
+    design: &Design,
+    page: &str
+)
+{
+    let scope = Scope::Class(class.name.clone());
+    let methods: Vec<&Subroutine> = design.subroutines.iter()
+        .filter(|sub| sub.scope == scope)
+        .collect();
+
+    if methods.is_empty() {
+        return;
+    }
+
+    text.push_str("### Methods: \n\n");
+
+    for sub in methods {
+        text.push_str(format!("#### [`{}`]({})\n\n",
+            sub.name, markdown::relative_link(page, &markdown::subroutine_page(sub))).as_str());
+        text.push_str(format!("```verilog\n{};\n```\n\n", markdown::subroutine_signature(sub)).as_str());
+        if let Some(comment) = &sub.comment {
+            text.push_str(format!("{}\n\n", comment.lines().next().unwrap_or("")).as_str());
+        }
+    }
+
+    text.push('\n');
+}
+
+fn print_constraints(
+    text: &mut String,
+    class: &Class
+)
+{
+    if class.constraints.is_empty() {
+        return;
+    }
+
+    text.push_str("### Constraints: \n\n");
+
+    for constraint in &class.constraints {
+        text.push_str(format!("#### `{}`\n\n", constraint.name).as_str());
+        if let Some(comment) = &constraint.comment {
+            text.push_str(format!("{}\n\n", comment).as_str());
+        }
+
+        let mut words: Vec<&str> = constraint.qualifiers.iter().map(|q| q.as_str()).collect();
+        words.push("constraint");
+        words.push(&constraint.name);
+        match &constraint.body {
+            Some(body) => text.push_str(format!("```verilog\n{} {}\n```\n\n", words.join(" "), body).as_str()),
+            None => text.push_str(format!("```verilog\n{};\n```\n\n", words.join(" ")).as_str()),
+        }
+    }
+
+    text.push('\n');
+}
+
+fn print_covergroups(
+    text: &mut String,
+    class: &Class
+)
+{
+    if class.covergroups.is_empty() {
+        return;
+    }
+
+    text.push_str("### Covergroups: \n\n");
+    text.push_str("| Name | Event | Coverpoints and crosses | Description |\n");
+    text.push_str("|------|-------|-------------------------|-------------|\n");
+
+    for cg in &class.covergroups {
+        let items: Vec<String> = cg.items.iter().map(|i| format!("`{}`", i)).collect();
+        text.push_str(format!("| `{}` | {} | {} | {} |\n",
+            cg.name,
+            markdown::code_cell(&cg.event),
+            items.join(", "),
+            markdown::table_cell(cg.comment.as_deref().unwrap_or(""))).as_str());
+    }
+
+    text.push_str("\n\n");
+}
+
+/// Print properties and methods of base classes
+/// which are not overridden in derived classes.
+///
+fn print_inherited_members(
+    text: &mut String,
+    class: &Class,
+    design: &Design,
+    page: &str
+)
+{
+    let mut names: Vec<&str> = class.properties.iter().map(|p| p.name.as_str())
+        .chain(design.subroutines.iter()
+            .filter(|sub| sub.scope == Scope::Class(class.name.clone()))
+            .map(|sub| sub.name.as_str()))
+        .collect();
+    let mut visited: Vec<&str> = vec![&class.name];
+    let mut section = String::new();
+
+    let mut base_name = class.base_name.as_deref();
+    while let Some(base) = design.classes.iter().find(|c| Some(c.name.as_str()) == base_name) {
+        // Guard against inheritance loops in broken sources.
+        if visited.contains(&base.name.as_str()) {
+            break;
+        }
+        visited.push(&base.name);
+
+        let base_page = markdown::page_path(&base.location.file, "class", &base.name);
+        let mut members = String::new();
+
+        for prop in &base.properties {
+            if prop.qualifiers.iter().any(|q| q == "local") || names.contains(&prop.name.as_str()) {
+                continue;
+            }
+            names.push(&prop.name);
+            members.push_str(format!("- property `{}`", prop.name).as_str());
+            if let Some(data_type) = &prop.data_type {
+                members.push_str(format!(": `{}`", data_type).as_str());
+            }
+            members.push('\n');
+        }
+
+        let scope = Scope::Class(base.name.clone());
+        for sub in design.subroutines.iter().filter(|sub| sub.scope == scope) {
+            if sub.qualifiers.iter().any(|q| q == "local") || sub.name == "new" || names.contains(&sub.name.as_str()) {
+                continue;
+            }
+            names.push(&sub.name);
+            members.push_str(format!("- {} [`{}`]({})\n",
+                sub.kind.as_str(), sub.name,
+                markdown::relative_link(page, &markdown::subroutine_page(sub))).as_str());
+        }
+
+        if !members.is_empty() {
+            section.push_str(format!("#### From [`{}`]({})\n\n{}\n",
+                base.name, markdown::relative_link(page, &base_page), members).as_str());
+        }
+
+        base_name = base.base_name.as_deref();
+    }
+
+    if !section.is_empty() {
+        text.push_str("### Inherited members: \n\n");
+        text.push_str(&section);
+        text.push('\n');
+    }
+}
+
+/// Ancestors of class from its direct base up to the root,
+/// the last one may be a base class which is not in the design.
+///
+fn ancestors<'a>(class: &'a Class, design: &'a Design) -> Vec<(&'a str, Option<&'a Class>)> {
+    let mut list: Vec<(&str, Option<&Class>)> = Vec::new();
+
+    let mut base_name = class.base_name.as_deref();
+    while let Some(name) = base_name {
+        // Guard against inheritance loops in broken sources.
+        if name == class.name || list.iter().any(|(n, _)| *n == name) {
+            break;
+        }
+        let base = design.classes.iter().find(|c| c.name == name);
+        list.push((name, base));
+        base_name = base.and_then(|b| b.base_name.as_deref());
+    }
+
+    list
+}
+
+/// Print Mermaid diagram with ancestors and direct subclasses of the class,
+/// base classes not found in the design are shown as dashed stubs.
+///
+fn print_class_diagram(
+    text: &mut String,
+    class: &Class,
+    design: &Design
+)
+{
+    let ancestors = ancestors(class, design);
+    let subclasses: Vec<&Class> = design.classes.iter()
+        .filter(|c| c.base_name.as_deref() == Some(class.name.as_str()))
+        .collect();
+
+    if ancestors.is_empty() && subclasses.is_empty() {
+        return;
+    }
+
+    text.push_str("### Class hierarchy: \n\n");
+    text.push_str("<div class=\"mermaid\">\ngraph BT\n");
+    text.push_str("  classDef current font-weight:bold,stroke-width:3px\n");
+    text.push_str("  classDef external stroke-dasharray:5 5\n");
+    text.push_str(format!("  c0[\"{}\"]:::current\n", class.name).as_str());
+
+    let mut prev = String::from("c0");
+    for (i, (name, base)) in ancestors.iter().enumerate() {
+        let id = format!("a{}", i);
+        match base {
+            Some(_) => text.push_str(format!("  {}[\"{}\"]\n", id, name).as_str()),
+            None => text.push_str(format!("  {}[\"{}\"]:::external\n", id, name).as_str()),
+        }
+        text.push_str(format!("  {} --> {}\n", prev, id).as_str());
+        prev = id;
+    }
+
+    for (i, sub) in subclasses.iter().enumerate() {
+        text.push_str(format!("  s{}[\"{}\"]\n", i, sub.name).as_str());
+        text.push_str(format!("  s{} --> c0\n", i).as_str());
+    }
+
+    text.push_str("</div>\n\n");
+}
+
+/// Tree of all classes by `extends` relationship, linked to class pages.
+/// Classes with base class not found in the design are grouped under its name.
+///
+pub fn generate_class_hierarchy(design: &Design) -> String {
+    let mut text = String::new();
+
+    // Roots are classes without base class and external base classes.
+    let mut external: Vec<&str> = Vec::new();
+    for class in &design.classes {
+        match class.base_name.as_deref() {
+            None => print_class_tree(&mut text, class, design, 0, &mut Vec::new()),
+            Some(base) => {
+                if !design.classes.iter().any(|c| c.name == base) && !external.contains(&base) {
+                    external.push(base);
+                }
+            }
+        }
+    }
+
+    for base in external {
+        text.push_str(format!("- `{}` (not in design)\n", base).as_str());
+        for class in design.classes.iter().filter(|c| c.base_name.as_deref() == Some(base)) {
+            print_class_tree(&mut text, class, design, 1, &mut Vec::new());
+        }
+    }
+
+    text
+}
+
+fn print_class_tree<'a>(
+    text: &mut String,
+    class: &'a Class,
+    design: &'a Design,
+    level: usize,
+    visited: &mut Vec<&'a str>
+)
+{
+    if visited.contains(&class.name.as_str()) {
+        return;
+    }
+    visited.push(&class.name);
+
+    let page = markdown::page_path(&class.location.file, "class", &class.name);
+    text.push_str(format!("{:indent$}- [`{}`]({})\n", "", class.name, page, indent=level*2).as_str());
+
+    for sub in design.classes.iter().filter(|c| c.base_name.as_deref() == Some(class.name.as_str())) {
+        print_class_tree(text, sub, design, level + 1, visited);
+    }
+}
@@ -0,0 +1,138 @@
+//! Generate info about SV functions and tasks.
+//!
+//!
+//!
+
+use crate::mdbook::design::{Design, Scope, Subroutine, SubroutineKind};
+use crate::mdbook::book::Book;
+use crate::mdbook::markdown;
+use crate::mdbook::xref::Resolver;
+
+pub fn generate_sv_function_info(
+    book: &mut Book,
+    design: &Design
+) -> (Vec<String>, Vec<(String, String, String)>)
+{
+    let mut text: Vec<String> = Vec::new();
+    let mut list: Vec<(String, String, String)> = Vec::new();
+
+    for sub in &design.subroutines {
+        let item = print_subroutine(&mut text, book, sub, design);
+        list.push(item)
+    }
+
+    (text, list)
+}
+
+fn print_subroutine(
+    top_text: &mut Vec<String>,
+    book: &mut Book,
+    sub: &Subroutine,
+    design: &Design
+) -> (String, String, String)
+{
+    let full_name = format!("{}::{}", sub.scope.name(), sub.name);
+    let file_path = sub.location.file.as_str();
+
+    let src_sub_path = markdown::subroutine_page(sub);
+    let src_sub_path = src_sub_path.as_str();
+
+    let mut text = String::new();
+
+    let kind_str = match sub.kind {
+        SubroutineKind::Function => "Function",
+        SubroutineKind::Task => "Task",
+    };
+    text.push_str(format!("## {} `{}`\n\n", kind_str, full_name).as_str());
+    text.push_str(format!("File: `{}`\n\n", file_path).as_str());
+
+    print_scope(&mut text, design, &sub.scope, src_sub_path);
+
+    text.push_str(format!("```verilog\n{};\n```\n\n", markdown::subroutine_signature(sub)).as_str());
+
+    let resolver = Resolver::new(design, &sub.scope);
+
+    if let Some(return_type) = &sub.return_type {
+        text.push_str(format!("Returns: {}\n\n",
+            markdown::type_link(return_type, resolver.type_str_page(return_type), src_sub_path)).as_str());
+    }
+
+    if let Some(lifetime) = &sub.lifetime {
+        text.push_str(format!("Lifetime: `{}`\n\n", lifetime).as_str());
+    }
+
+    if !sub.qualifiers.is_empty() {
+        let qualifiers: Vec<String> = sub.qualifiers.iter().map(|q| format!("`{}`", q)).collect();
+        text.push_str(format!("Qualifiers: {}\n\n", qualifiers.join(", ")).as_str());
+    }
+
+    print_arguments(&mut text, sub, &resolver, src_sub_path);
+
+    if let Some(comment) = &sub.comment {
+        text.push_str("### Description:\n\n");
+        text.push_str(comment.as_str());
+        text.push('\n');
+    }
+
+    book.add_page(src_sub_path, text);
+
+    top_text.push(format!("- [`{}  :{}`]({})\n", full_name, file_path, src_sub_path));
+
+    (full_name, file_path.to_string(), src_sub_path.to_string())
+}
+
+fn print_scope(
+    text: &mut String,
+    design: &Design,
+    scope: &Scope,
+    page: &str
+)
+{
+    let kind = match scope {
+        Scope::CompilationUnit => "compilation unit",
+        Scope::Package(_) => "package",
+        Scope::Module(_) => "module",
+        Scope::Interface(_) => "interface",
+        Scope::Class(_) => "class",
+    };
+
+    match markdown::scope_page(design, scope) {
+        Some(scope_page) => text.push_str(format!("Declared in: {} [`{}`]({})\n\n",
+            kind, scope.name(), markdown::relative_link(page, &scope_page)).as_str()),
+        None => text.push_str(format!("Declared in: {} `{}`\n\n", kind, scope.name()).as_str()),
+    }
+}
+
+fn print_arguments(
+    text: &mut String,
+    sub: &Subroutine,
+    resolver: &Resolver,
+    page: &str
+)
+{
+    if sub.arguments.is_empty() {
+        return;
+    }
+
+    text.push_str("### Arguments: \n\n");
+    text.push_str("| Name | Direction | Type | Default |\n");
+    text.push_str("|------|-----------|------|---------|\n");
+
+    for arg in &sub.arguments {
+        let name_str = match arg.unpacked_dims.is_empty() {
+            true => format!("`{}`", arg.name),
+            false => format!("`{} {}`", arg.name, arg.unpacked_dims.concat()),
+        };
+        let type_str = match &arg.data_type {
+            Some(data_type) => markdown::type_link(data_type, resolver.type_str_page(data_type), page),
+            None => String::new(),
+        };
+        text.push_str(format!("| {} | {} | {} | {} |\n",
+            name_str,
+            arg.direction,
+            type_str,
+            markdown::code_cell(&arg.default)).as_str());
+    }
+
+    text.push_str("\n\n");
+}
@@ -1,56 +1,25 @@
-//! Parse SV file and generate info about Verilog interface(s).
+//! Generate info about Verilog interface(s).
 //!
 //!
 //!
 
-use sv_parser::{parse_sv, unwrap_node, /*unwrap_locate, Locate,*/ RefNode, SyntaxTree};
-//use sv_parser::{PortDirection, NetType, IntegerVectorType};
-use std::collections::HashMap;
-use std::path::{Path, PathBuf};
-//use regex::Regex;
-use crate::mdbook::svpar;
+use crate::mdbook::design::{Design, Interface, Scope};
+use crate::mdbook::design::{ClockingBlock, Modport};
+use crate::mdbook::book::Book;
+use crate::mdbook::markdown;
+use crate::mdbook::xref::Resolver;
 
 pub fn generate_sv_interface_info(
-    output_path: &str,
-    file_path: &str
+    book: &mut Book,
+    design: &Design
 ) -> (Vec<String>, Vec<(String, String, String)>)
 {
     let mut text: Vec<String> = Vec::new();
     let mut list: Vec<(String, String, String)> = Vec::new();
 
-    // The list of defined macros
-    let defines = HashMap::new();
-    // The list of include paths
-    let includes: Vec<PathBuf> = Vec::new();
-
-    // Parse
-    let result = parse_sv(file_path, &defines, &includes, false, true);
-
-    if let Ok((syntax_tree, _)) = result {
-        let mut prev_node: Option<RefNode> = None;
-        // &SyntaxTree is iterable
-        for node in &syntax_tree {
-            // The type of each node is RefNode
-            match node {
-                RefNode::InterfaceDeclaration(x) => {
-                    let id = unwrap_node!(x, InterfaceIdentifier).unwrap();
-                    let id = svpar::get_identifier(id).unwrap();
-                    let id = syntax_tree.get_str(&id).unwrap();
-                    let item = print_iface(&mut text, output_path, file_path, id,
-                        &syntax_tree, &node, &prev_node);
-                    list.push(item)
-                }
-                RefNode::Description(_) => {
-                }
-                RefNode::WhiteSpace(_) => {
-                }
-                RefNode::Locate(_) => {
-                }
-                x => { prev_node = Some(x); () },
-            }
-        }
-    } else {
-        println!("parsing of '{}' failed\n", file_path);
+    for iface in &design.interfaces {
+        let item = print_iface(&mut text, book, iface, design);
+        list.push(item)
     }
 
     (text, list)
@@ -58,38 +27,201 @@ pub fn generate_sv_interface_info(
 
 fn print_iface(
     top_text: &mut Vec<String>,
-    output_path: &str,
-    file_path: &str,
-    iface_name: &str,
-    _syntax_tree: &SyntaxTree,
-    _iface_node: &RefNode,
-    _prev_node: &Option<RefNode>
+    book: &mut Book,
+    iface: &Interface,
+    design: &Design
 ) -> (String, String, String)
 {
+    let iface_name = iface.name.as_str();
+    let file_path = iface.location.file.as_str();
+
     let mut text = String::new();
 
     text.push_str(format!("## Interface `{}`\n\n", iface_name).as_str());
     text.push_str(format!("File: `{}`\n\n", file_path).as_str());
 
-    /*text.push_str(format!("### Ports: \n\n").as_str());
-    if is_ansi {
-        print_ansi_ports(&mut text, syntax_tree, module_node);
+    markdown::print_parameters(&mut text, &iface.parameters);
+
+    let page = markdown::page_path(file_path, "iface", iface_name);
+    let scope = Scope::Interface(iface_name.to_string());
+    let resolver = Resolver::new(design, &scope);
+
+    if !iface.ports.is_empty() {
+        text.push_str("### Ports: \n\n");
+        markdown::print_ports(&mut text, &iface.ports, &resolver, &page);
+        text.push_str("\n\n");
     }
 
-    text.push_str(format!("\n\n### Instantiates modules: \n\n").as_str());
-    print_instantiated_modules(&mut text, syntax_tree, module_node);*/
+    print_signals(&mut text, iface, &resolver, &page);
+    print_modports(&mut text, &iface.modports);
+    print_direction_matrix(&mut text, iface);
+    print_clocking_blocks(&mut text, &iface.clocking_blocks);
+
+    markdown::print_used_by(&mut text, design, iface_name, &page);
 
-    //print_module_comments(&mut text, syntax_tree, module_node, prev_node);
+    markdown::print_subroutine_list(&mut text, design, &scope, &page);
+
+    if let Some(comment) = &iface.comment {
+        text.push_str("\n\n### Description:\n\n");
+        text.push_str(comment.as_str());
+        text.push('\n');
+    }
 
-    let mut iface_path = Path::new(output_path).join("src").join(file_path);
-    iface_path.set_extension(format!("iface.{}.md", iface_name));
-    let mut src_iface_path = Path::new("src").join(file_path);
-    src_iface_path.set_extension(format!("iface.{}.md", iface_name));
-    let src_iface_path = src_iface_path.to_str().unwrap();
+    let src_iface_path = page.as_str();
 
-    std::fs::write(iface_path, text).expect("failed to write file");
+    book.add_page(src_iface_path, text);
 
     top_text.push(format!("- [`{}  :{}`]({})\n", iface_name, file_path, src_iface_path));
 
     (iface_name.to_string(), file_path.to_string(), src_iface_path.to_string())
-}
\ No newline at end of file
+}
+
+fn print_signals(
+    text: &mut String,
+    iface: &Interface,
+    resolver: &Resolver,
+    page: &str
+)
+{
+    if iface.signals.is_empty() {
+        return;
+    }
+
+    text.push_str("### Signals: \n\n");
+    text.push_str("| Name | Type | Width | Description |\n");
+    text.push_str("|------|------|-------|-------------|\n");
+
+    for signal in &iface.signals {
+        let name_str = match signal.unpacked_dims.is_empty() {
+            true => format!("`{}`", signal.name),
+            false => format!("`{} {}`", signal.name, signal.unpacked_dims.concat()),
+        };
+
+        text.push_str(format!("| {} | {} | {} | {} |\n",
+            name_str,
+            markdown::data_type_cell(resolver, &signal.signal_type, page),
+            signal.width.map(|w| w.to_string()).unwrap_or_default(),
+            markdown::table_cell(signal.comment.as_deref().unwrap_or(""))).as_str());
+    }
+
+    text.push_str("\n\n");
+}
+
+fn print_modports(
+    text: &mut String,
+    modports: &[Modport]
+)
+{
+    if modports.is_empty() {
+        return;
+    }
+
+    text.push_str("### Modports: \n\n");
+
+    for modport in modports {
+        text.push_str(format!("#### `{}`\n\n", modport.name).as_str());
+        if let Some(comment) = &modport.comment {
+            text.push_str(format!("{}\n\n", comment).as_str());
+        }
+
+        text.push_str("| Signal | Direction |\n");
+        text.push_str("|--------|-----------|\n");
+        for port in &modport.ports {
+            text.push_str(format!("| `{}` | {} |\n", port.name, port.direction).as_str());
+        }
+        text.push_str("\n\n");
+    }
+}
+
+/// Table with direction of every interface item in every modport.
+///
+fn print_direction_matrix(
+    text: &mut String,
+    iface: &Interface
+)
+{
+    if iface.modports.is_empty() {
+        return;
+    }
+
+    // Ports and signals in order of declaration,
+    // then modport items which are not signals, like tasks or clocking blocks.
+    let mut names: Vec<&str> = iface.ports.iter().map(|p| p.name.as_str())
+        .chain(iface.signals.iter().map(|s| s.name.as_str()))
+        .collect();
+    for modport in &iface.modports {
+        for port in &modport.ports {
+            if !names.contains(&port.name.as_str()) {
+                names.push(&port.name);
+            }
+        }
+    }
+
+    text.push_str("### Direction matrix: \n\n");
+
+    text.push_str("| Signal |");
+    for modport in &iface.modports {
+        text.push_str(format!(" `{}` |", modport.name).as_str());
+    }
+    text.push_str("\n|--------|");
+    for _ in &iface.modports {
+        text.push_str("------|");
+    }
+    text.push('\n');
+
+    for name in names {
+        text.push_str(format!("| `{}` |", name).as_str());
+        for modport in &iface.modports {
+            let direction = modport.ports.iter()
+                .find(|p| p.name == name)
+                .map(|p| p.direction.as_str())
+                .unwrap_or("");
+            text.push_str(format!(" {} |", direction).as_str());
+        }
+        text.push('\n');
+    }
+
+    text.push_str("\n\n");
+}
+
+fn print_clocking_blocks(
+    text: &mut String,
+    blocks: &[ClockingBlock]
+)
+{
+    if blocks.is_empty() {
+        return;
+    }
+
+    text.push_str("### Clocking blocks: \n\n");
+
+    for block in blocks {
+        let name = block.name.as_deref().unwrap_or("(unnamed)");
+        match block.is_default {
+            true => text.push_str(format!("#### `{}` (default)\n\n", name).as_str()),
+            false => text.push_str(format!("#### `{}`\n\n", name).as_str()),
+        }
+        if let Some(comment) = &block.comment {
+            text.push_str(format!("{}\n\n", comment).as_str());
+        }
+
+        text.push_str(format!("Clocking event: `{}`\n\n", block.event).as_str());
+        if let Some(skew) = &block.default_skew {
+            text.push_str(format!("Default skew: `{}`\n\n", skew).as_str());
+        }
+
+        if block.signals.is_empty() {
+            continue;
+        }
+
+        text.push_str("| Signal | Direction | Skew |\n");
+        text.push_str("|--------|-----------|------|\n");
+        for signal in &block.signals {
+            text.push_str(format!("| `{}` | {} | {} |\n",
+                signal.name,
+                signal.direction,
+                markdown::code_cell(&signal.skew)).as_str());
+        }
+        text.push_str("\n\n");
+    }
+}
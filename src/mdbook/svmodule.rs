@@ -1,252 +1,341 @@
-//! Parse SV file and generate info about Verilog module(s).
+//! Generate info about Verilog module(s).
 //!
 //!
 //!
 
-use sv_parser::{parse_sv, unwrap_node, unwrap_locate, /*Locate,*/ RefNode, SyntaxTree};
-use sv_parser::{PortDirection, NetType, IntegerVectorType};
-use std::collections::HashMap;
-use std::path::{Path, PathBuf};
-use regex::Regex;
-use crate::mdbook::svpar;
+use std::path::Path;
+use crate::mdbook::design::{ConnectionKind, Design, Direction, Instance, Module, ParamKind, Scope};
+use crate::args;
+use crate::mdbook::book::Book;
+use crate::mdbook::files;
+use crate::mdbook::fsm;
+use crate::mdbook::markdown;
+use crate::mdbook::schematic;
+use crate::mdbook::symbol;
+use crate::mdbook::xref::Resolver;
 
 pub fn generate_sv_module_info(
-    output_path: &str,
-    file_path: &str
+    book: &mut Book,
+    design: &Design,
+    options: &args::ParsedOptions
 ) -> (Vec<String>, Vec<(String, String, String)>)
 {
     let mut text: Vec<String> = Vec::new();
     let mut list: Vec<(String, String, String)> = Vec::new();
 
-    // The list of defined macros
-    let defines = HashMap::new();
-    // The list of include paths
-    let includes: Vec<PathBuf> = Vec::new();
-
-    // Parse
-    let result = parse_sv(file_path, &defines, &includes, false, true);
-
-    if let Ok((syntax_tree, _)) = result {
-        let mut prev_node: Option<RefNode> = None;
-        // &SyntaxTree is iterable
-        for node in &syntax_tree {
-            // The type of each node is RefNode
-            match node {
-                RefNode::ModuleDeclarationNonansi(x) => {
-                    // unwrap_node! gets the nearest ModuleIdentifier from x
-                    let id = unwrap_node!(x, ModuleIdentifier).unwrap();
-
-                    let id = svpar::get_identifier(id).unwrap();
-
-                    // Original string can be got by SyntaxTree::get_str(self, locate: &Locate)
-                    let id = syntax_tree.get_str(&id).unwrap();
-                    let item = print_module(&mut text, output_path, file_path, id, false,
-                        &syntax_tree, &node, &prev_node);
-                    list.push(item)
-                }
-                RefNode::ModuleDeclarationAnsi(x) => {
-                    let id = unwrap_node!(x, ModuleIdentifier).unwrap();
-                    let id = svpar::get_identifier(id).unwrap();
-                    let id = syntax_tree.get_str(&id).unwrap();
-                    let item = print_module(&mut text, output_path, file_path, id, true,
-                        &syntax_tree, &node, &prev_node);
-                    list.push(item)
-                }
-                RefNode::ModuleDeclaration(_) => {
-                }
-                RefNode::Description(_) => {
-                }
-                RefNode::WhiteSpace(_) => {
-                }
-                RefNode::Locate(_) => {
-                }
-                x => { prev_node = Some(x); () },
-            }
-        }
-    } else {
-        println!("parsing of '{}' failed\n", file_path);
+    for module in &design.modules {
+        let item = print_module(&mut text, book, module, design, options);
+        list.push(item)
     }
 
     (text, list)
 }
 
-
-
 fn print_module(
     top_text: &mut Vec<String>,
-    output_path: &str,
-    file_path: &str,
-    module_name: &str,
-    is_ansi: bool,
-    syntax_tree: &SyntaxTree,
-    module_node: &RefNode,
-    prev_node: &Option<RefNode>
+    book: &mut Book,
+    module: &Module,
+    design: &Design,
+    options: &args::ParsedOptions
 ) -> (String, String, String)
 {
+    let module_name = module.name.as_str();
+    let file_path = module.location.file.as_str();
+
     let mut text = String::new();
 
     text.push_str(format!("## Module `{}`\n\n", module_name).as_str());
     text.push_str(format!("File: `{}`\n\n", file_path).as_str());
 
-    text.push_str(format!("### Ports: \n\n").as_str());
-    if is_ansi {
-        print_ansi_ports(&mut text, syntax_tree, module_node);
-    }
+    text.push_str(&symbol::module_symbol_svg(module));
+    text.push('\n');
+
+    markdown::print_parameters(&mut text, &module.parameters);
+
+    let src_module_path = markdown::page_path(file_path, "module", module_name);
+    let src_module_path = src_module_path.as_str();
+
+    let scope = Scope::Module(module_name.to_string());
+    let resolver = Resolver::new(design, &scope);
 
-    text.push_str(format!("\n\n### Instantiates modules: \n\n").as_str());
-    print_instantiated_modules(&mut text, syntax_tree, module_node);
+    text.push_str("### Ports: \n\n");
+    markdown::print_ports(&mut text, &module.ports, &resolver, src_module_path);
 
-    print_module_comments(&mut text, syntax_tree, module_node, prev_node);
+    text.push_str("\n\n### Instantiates modules: \n\n");
+    print_instantiated_modules(&mut text, module, &resolver, src_module_path);
 
-    let mut module_path = Path::new(output_path).join("src").join(file_path);
-    module_path.set_extension(format!("module.{}.md", module_name));
-    let mut src_module_path = Path::new("src").join(file_path);
-    src_module_path.set_extension(format!("module.{}.md", module_name));
-    let src_module_path = src_module_path.to_str().unwrap();
+    text.push_str("\n\n");
+    print_instance_connections(&mut text, module, design, &resolver, src_module_path);
 
-    std::fs::write(module_path, text).expect("failed to write file");
+    print_schematic(&mut text, book, module, design, options.collapse_buses);
+
+    print_state_machines(&mut text, module, design);
+
+    markdown::print_used_by(&mut text, design, module_name, src_module_path);
+
+    markdown::print_subroutine_list(&mut text, design, &scope, src_module_path);
+
+    print_module_comments(&mut text, module);
+
+    book.add_page(src_module_path, text);
 
     top_text.push(format!("- [`{}  :{}`]({})\n", module_name, file_path, src_module_path));
 
     (module_name.to_string(), file_path.to_string(), src_module_path.to_string())
 }
 
-fn print_ansi_ports(
+fn print_instantiated_modules(
     text: &mut String,
-    syntax_tree: &SyntaxTree,
-    module_node: &RefNode
+    module: &Module,
+    resolver: &Resolver,
+    page: &str
 )
 {
-    // FIXME TODO check if it can be done  without cloning
-    for node in module_node.clone().into_iter() {
-        // The type of each node is RefNode
-        match node {
-            RefNode::AnsiPortDeclaration(x) => {
-                //text.push(format!("{:?}\n", x));
-                let id = unwrap_node!(x, PortIdentifier).unwrap();
-                let id = unwrap_locate!(id).unwrap();
-                let id = syntax_tree.get_str(id).unwrap();
-                text.push_str(format!("- {}\n", id).as_str());
-
-                let dir = unwrap_node!(x, PortDirection);
-                let dir_str = match dir {
-                    Some(RefNode::PortDirection(PortDirection::Input(_))) => "➔ input",
-                    Some(RefNode::PortDirection(PortDirection::Output(_))) => "output ➔",
-                    Some(RefNode::PortDirection(PortDirection::Inout(_))) => "inout",
-                    _ => "?",
-                };
-                text.push_str(format!("  * direction: {}\n", dir_str).as_str());
-
-                let net_type = unwrap_node!(x, NetType);
-                match net_type {
-                    Some(RefNode::NetType(NetType::Wire(_))) =>
-                        text.push_str("  * type: wire\n"),
-                    _ => (),
-                }
-
-                let vnet_type = unwrap_node!(x, IntegerVectorType);
-                match vnet_type {
-                    Some(RefNode::IntegerVectorType(IntegerVectorType::Reg(_))) =>
-                        text.push_str("  * type: reg\n"),
-                    Some(RefNode::IntegerVectorType(IntegerVectorType::Logic(_))) =>
-                        text.push_str("  * type: logic\n"),
-                    Some(RefNode::IntegerVectorType(IntegerVectorType::Bit(_))) =>
-                        text.push_str("  * type: bit\n"),
-                    _ => (),
-                }
-
-                let width = unwrap_node!(x, PackedDimensionRange);
-                if let Some(width) = width {
-                    //text.push(format!("{:?}\n", &width));
-                    text.push_str(format!("  * width: {}\n", svpar::get_whole_str(syntax_tree, &width)).as_str());
-                }
-            }
-            _ => (),
+    // Module type and its instance names, in order of appearance.
+    let mut mod_instances: Vec<(&str, Vec<&str>)> = Vec::new();
+
+    for inst in &module.instances {
+        match mod_instances.iter_mut().find(|m| m.0 == inst.module_name) {
+            Some(m) => m.1.push(&inst.instance_name),
+            None => mod_instances.push((&inst.module_name, vec![&inst.instance_name])),
         }
     }
 
+    for (mname, inames) in mod_instances.iter() {
+        match resolver.module_page(mname) {
+            Some(module_page) => text.push_str(format!("- [{}]({})\n",
+                mname, markdown::relative_link(page, &module_page)).as_str()),
+            None => text.push_str(format!("- {}\n", mname).as_str()),
+        }
+        for iname in inames.iter() {
+            text.push_str(format!("  - {}\n", iname).as_str());
+        }
+    }
 }
 
-fn print_instantiated_modules(
+/// Print parameter overrides and port connections of every instance.
+///
+fn print_instance_connections(
     text: &mut String,
-    syntax_tree: &SyntaxTree,
-    module_node: &RefNode
+    module: &Module,
+    design: &Design,
+    resolver: &Resolver,
+    page: &str
 )
 {
-    let mut mod_instances: HashMap<String, Vec<String>> = HashMap::new();
-
-    for node in module_node.clone().into_iter() {
-        match node {
-            RefNode::ModuleInstantiation(x) => {
-                let mod_name = unwrap_node!(x, ModuleIdentifier).unwrap();
-                let mod_name = svpar::get_identifier(mod_name).unwrap();
-                let mod_name = syntax_tree.get_str(&mod_name).unwrap();
-
-                let inst_name = unwrap_node!(x, InstanceIdentifier).unwrap();
-                let inst_name = svpar::get_identifier(inst_name).unwrap();
-                let inst_name = syntax_tree.get_str(&inst_name).unwrap();
-
-                let /*mut*/ m = match mod_instances.get_mut(mod_name) {
-                    Some(m) => m,
-                    None => {mod_instances.insert(mod_name.to_string(), Vec::<String>::new());
-                        mod_instances.get_mut(mod_name).unwrap()},
-                };
-
-                m.push(inst_name.to_string());
+    if module.instances.is_empty() {
+        return;
+    }
+
+    text.push_str("### Instances: \n\n");
+
+    for inst in &module.instances {
+        match resolver.module_page(&inst.module_name) {
+            Some(module_page) => text.push_str(format!("#### `{}` : [{}]({})\n\n",
+                inst.instance_name, inst.module_name, markdown::relative_link(page, &module_page)).as_str()),
+            None => text.push_str(format!("#### `{}` : {}\n\n",
+                inst.instance_name, inst.module_name).as_str()),
+        }
+        text.push_str(format!("Line: {}\n\n", inst.location.line).as_str());
+
+        let parameters = resolve_parameters(design, inst);
+        if !parameters.is_empty() {
+            text.push_str("| Parameter | Value |\n");
+            text.push_str("|-----------|-------|\n");
+            for (name, value) in parameters {
+                text.push_str(format!("| `{}` | {} |\n", name, markdown::code_cell(&value)).as_str());
             }
-            _ => (),
+            text.push('\n');
         }
-    }
 
-    for (mname, inames) in mod_instances.iter() {
-        text.push_str(format!("- {}\n", mname).as_str());
-        for iname in inames.iter() {
-            text.push_str(format!("  - {}\n", iname).as_str());
+        let pins = schematic::resolve_connections(design, inst);
+        if pins.is_empty() {
+            continue;
+        }
+
+        let known_ports = schematic::instance_ports(design, &inst.module_name);
+
+        text.push_str("| Port | Direction | Connection |\n");
+        text.push_str("|------|-----------|------------|\n");
+        for pin in pins {
+            let port = known_ports.and_then(|ports| ports.iter().find(|p| p.name == pin.port));
+            let dir_str = match port {
+                Some(port) if port.port_type.interface.is_some() => "interface",
+                _ => match pin.direction {
+                    Some(Direction::Input) => "➔ input",
+                    Some(Direction::Output) => "output ➔",
+                    Some(Direction::Inout) => "inout",
+                    Some(Direction::Ref) => "ref",
+                    _ => "",
+                },
+            };
+
+            let conn_str = match (&pin.kind, &pin.expr) {
+                (None, _) | (Some(_), None) => String::from("⚠ unconnected"),
+                (Some(ConnectionKind::Implicit), Some(expr)) => format!("`{}` (`.{}`)", expr, pin.port),
+                (Some(ConnectionKind::Wildcard), Some(expr)) => format!("`{}` (`.*`)", expr),
+                (Some(_), Some(expr)) => markdown::code_cell(&Some(expr.clone())),
+            };
+
+            // Named connection to a port the child module doesn't have.
+            let conn_str = match (known_ports, port) {
+                (Some(_), None) => format!("{} ⚠ no such port", conn_str),
+                _ => conn_str,
+            };
+
+            text.push_str(format!("| `{}` | {} | {} |\n", pin.port, dir_str, conn_str).as_str());
         }
+        text.push_str("\n\n");
     }
 }
 
-fn print_module_comments(
+/// Parameter overrides of instance, positional ones are mapped
+/// to parameter names of instantiated module if it is known.
+///
+fn resolve_parameters(
+    design: &Design,
+    inst: &Instance
+) -> Vec<(String, Option<String>)>
+{
+    // Local parameters can't be overridden, so they don't take positions.
+    let params: Vec<&str> = design.modules.iter()
+        .find(|m| m.name == inst.module_name)
+        .map(|m| m.parameters.as_slice())
+        .or_else(|| design.interfaces.iter()
+            .find(|i| i.name == inst.module_name)
+            .map(|i| i.parameters.as_slice()))
+        .unwrap_or(&[])
+        .iter()
+        .filter(|p| matches!(p.kind, ParamKind::Parameter | ParamKind::TypeParameter))
+        .map(|p| p.name.as_str())
+        .collect();
+
+    inst.parameters.iter()
+        .enumerate()
+        .map(|(i, param)| {
+            let name = match &param.name {
+                Some(name) => name.clone(),
+                None => params.get(i).map_or(format!("#{}", i + 1), |n| n.to_string()),
+            };
+            (name, param.value.clone())
+        })
+        .collect()
+}
+
+/// Print Mermaid schematic of connections between instances
+/// and write the same schematic to DOT file next to the page.
+///
+fn print_schematic(
     text: &mut String,
-    syntax_tree: &SyntaxTree,
-    _module_node: &RefNode,
-    prev_node: &Option<RefNode>,
+    book: &mut Book,
+    module: &Module,
+    design: &Design,
+    collapse_buses: bool
 )
 {
-    for node in prev_node.clone().into_iter() {
-        match node {
-            RefNode::Comment(x) => {
-                let comment = unwrap_node!(x, Comment).unwrap();
-                //text.push_str(format!("\ncomment:\n {}\n", get_whole_str(syntax_tree, &comment)).as_str());
-                let comment = unwrap_locate!(comment).unwrap();
-                let comment = syntax_tree.get_str(comment).unwrap();
-                text.push_str(format!("\n\n### Description:\n\n").as_str());
-                text.push_str(extract_text_from_comment(comment).as_str());
-            }
-            _ => (),
-        }
+    if module.instances.is_empty() {
+        return;
     }
+
+    let nets = schematic::module_nets(design, module, collapse_buses);
+
+    let mut dot_path = files::book_path(Path::new(&module.location.file));
+    dot_path.set_extension(format!("module.{}.dot", module.name));
+    book.add_file(dot_path.to_str().unwrap(), schematic::to_dot(module, &nets));
+
+    text.push_str("### Schematic: \n\n");
+    text.push_str(&schematic::to_mermaid(module, &nets));
+    text.push_str(format!("\nGraphviz: [{}]({})\n\n\n",
+        dot_path.file_name().unwrap().to_str().unwrap(),
+        dot_path.file_name().unwrap().to_str().unwrap()).as_str());
 }
 
-fn extract_text_from_comment(raw_text: &str) -> String
+/// Print state diagram and transition table of every state machine.
+///
+fn print_state_machines(
+    text: &mut String,
+    module: &Module,
+    design: &Design
+)
 {
-    let re = Regex::new(r"^\s*/(\*)+").unwrap();
-    let text = re.replace_all(raw_text, "");
+    if module.state_machines.is_empty() {
+        return;
+    }
 
-    let re = Regex::new(r"(\*/)+\s*$").unwrap();
-    let text = re.replace_all(&text, "");
+    text.push_str("### State machines: \n\n");
 
-    // Ugly workaround for:
-    // "Note that ^ matches after new lines, even at the end of input"
-    let re = Regex::new(r"(?m)^\s*(\*)+\s*$").unwrap();
-    let text = re.replace_all(&text, "<!--empty_line-->");
+    for machine in &module.state_machines {
+        text.push_str(format!("#### `{}`\n\n", machine.state_var).as_str());
+        text.push_str(format!("Line: {}\n\n", machine.location.line).as_str());
+        if let Some(state_type) = &machine.state_type {
+            text.push_str(format!("Type: `{}`\n\n", markdown::table_cell(state_type)).as_str());
+        }
+        if let Some(next_var) = &machine.next_var {
+            text.push_str(format!("Next state: `{}`\n\n", next_var).as_str());
+        }
+        match (&machine.reset_state, &machine.reset_condition) {
+            (Some(state), Some(cond)) => text.push_str(format!("Reset state: `{}` when `{}`\n\n",
+                state, cond).as_str()),
+            (Some(state), None) => text.push_str(format!("Reset state: `{}`\n\n", state).as_str()),
+            _ => (),
+        }
 
-    let re = Regex::new(r"(?m)^\s*(\*)+").unwrap();
-    let text = re.replace_all(&text, "");
+        let states = fsm::state_names(design, machine);
+        text.push_str(&fsm::to_mermaid(machine, &states));
+        text.push('\n');
+
+        text.push_str("| From | To | Condition |\n");
+        text.push_str("|------|----|-----------|\n");
+        for tr in &machine.transitions {
+            text.push_str(format!("| `{}` | `{}` | {} |\n",
+                tr.from, tr.to, markdown::code_cell(&tr.condition)).as_str());
+        }
+        text.push_str("\n\n");
+    }
+}
 
-    // Fixup for the ugly workaround
-    let re = Regex::new(r"(?m)^<!--empty_line-->$").unwrap();
-    let text = re.replace_all(&text, "");
+fn print_module_comments(
+    text: &mut String,
+    module: &Module
+)
+{
+    if let Some(comment) = &module.comment {
+        text.push_str("\n\n### Description:\n\n");
+        text.push_str(comment.as_str());
+        text.push('\n');
+    }
+}
 
-    text.to_string()
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mdbook::design::parse_str;
+
+    #[test]
+    fn parameter_overrides() {
+        let design = parse_str("
+            module Sub #(parameter W = 8, parameter type T = logic, parameter D = 2) ();
+                localparam L = W * 2;
+            endmodule
+            module Top;
+                Sub #(16, int) u_pos ();
+                Sub #(.D(4), .W()) u_named ();
+                Sub #(1, logic, 3, 4) u_extra ();
+                Ext #(1, 2) u_ext ();
+                Ext #(.P(1)) u_ext_named ();
+                Sub u_default ();
+            endmodule");
+        let params = |index: usize| resolve_parameters(&design, &design.modules[1].instances[index])
+            .into_iter()
+            .map(|(name, value)| format!("{} = {}", name, value.as_deref().unwrap_or("-")))
+            .collect::<Vec<_>>();
+
+        assert_eq!(params(0), ["W = 16", "T = int"]);
+        assert_eq!(params(1), ["D = 4", "W = -"]);
+        // Local parameter takes no position.
+        assert_eq!(params(2), ["W = 1", "T = logic", "D = 3", "#4 = 4"]);
+        assert_eq!(params(3), ["#1 = 1", "#2 = 2"]);
+        assert_eq!(params(4), ["P = 1"]);
+        assert!(params(5).is_empty());
+    }
+}
@@ -3,21 +3,80 @@
 //!
 //!
 
-use sv_parser::{unwrap_node, unwrap_locate, Locate, RefNode, SyntaxTree};
+use sv_parser::{unwrap_node, unwrap_locate, Locate, NodeEvent, RefNode, SyntaxTree};
+use std::collections::HashMap;
+use std::path::Path;
+use regex::Regex;
+
+use crate::mdbook::design::Location;
 
 pub fn get_identifier(node: RefNode) -> Option<Locate> {
     // unwrap_node! can take multiple types
     match unwrap_node!(node, SimpleIdentifier, EscapedIdentifier) {
         Some(RefNode::SimpleIdentifier(x)) => {
-            return Some(x.nodes.0);
+            Some(x.nodes.0)
         }
         Some(RefNode::EscapedIdentifier(x)) => {
-            return Some(x.nodes.0);
+            Some(x.nodes.0)
         }
         _ => None,
     }
 }
 
+/// Parsed SV file, to find lines of tokens in the original text.
+///
+pub struct SourceFile {
+    pub path: String,
+    /// Offsets where lines start, empty if the file can't be read.
+    line_starts: Vec<usize>,
+    len: usize,
+}
+
+impl SourceFile {
+    pub fn new(path: &str) -> Self {
+        let text = std::fs::read_to_string(path).unwrap_or_default();
+        let line_starts = match text.is_empty() {
+            true => Vec::new(),
+            false => std::iter::once(0)
+                .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+                .collect(),
+        };
+
+        SourceFile { path: path.to_string(), line_starts, len: text.len() }
+    }
+
+    /// Line of the token in the original source file.
+    ///
+    /// `Locate::line` counts lines of preprocessed text, which differs
+    /// from the file when it has `include` directives or excluded `ifdef` blocks.
+    /// Falls back to `Locate::line` if the token comes from another file or a macro.
+    ///
+    pub fn line(&self, syntax_tree: &SyntaxTree, locate: &Locate) -> u32 {
+        match syntax_tree.get_origin(locate) {
+            Some((origin_path, offset))
+                if origin_path == Path::new(&self.path) && !self.line_starts.is_empty() && offset <= self.len =>
+                self.line_starts.partition_point(|&start| start <= offset) as u32,
+            _ => locate.line,
+        }
+    }
+
+    /// Location of the token in the original source file.
+    ///
+    pub fn location(&self, syntax_tree: &SyntaxTree, locate: &Locate) -> Location {
+        Location { file: self.path.clone(), line: self.line(syntax_tree, locate) }
+    }
+}
+
+/// Get text of the node without trailing whitespace and comments.
+///
+pub fn get_str_trim(
+    syntax_tree: &SyntaxTree,
+    node: &RefNode
+) -> Option<String>
+{
+    syntax_tree.get_str_trim(vec![node.clone()]).map(|s| s.to_string())
+}
+
 pub fn get_whole_str(
     syntax_tree: &SyntaxTree,
     node: &RefNode
@@ -38,4 +97,270 @@ pub fn get_whole_str(
     }
 
     s
-}
\ No newline at end of file
+}
+
+/// Strip comment markers `/*`, `*/` and leading `*` from comment text.
+///
+pub fn extract_text_from_comment(raw_text: &str) -> String
+{
+    let re = Regex::new(r"^\s*/(\*)+").unwrap();
+    let text = re.replace_all(raw_text, "");
+
+    let re = Regex::new(r"(\*/)+\s*$").unwrap();
+    let text = re.replace_all(&text, "");
+
+    // Ugly workaround for:
+    // "Note that ^ matches after new lines, even at the end of input"
+    let re = Regex::new(r"(?m)^\s*(\*)+\s*$").unwrap();
+    let text = re.replace_all(&text, "<!--empty_line-->");
+
+    let re = Regex::new(r"(?m)^\s*(\*)+").unwrap();
+    let text = re.replace_all(&text, "");
+
+    // Fixup for the ugly workaround, `\s*` above may join several empty lines in one.
+    let re = Regex::new(r"(?m)^(<!--empty_line-->)+$").unwrap();
+    let text = re.replace_all(&text, "");
+
+    text.to_string()
+}
+
+/// Clean up text of `//` or `/* */` comment.
+///
+pub fn clean_comment(raw_text: &str) -> String
+{
+    match raw_text.strip_prefix("//") {
+        Some(text) => text.trim_start_matches(['/', '!', '<']).trim().to_string(),
+        None => extract_text_from_comment(raw_text)
+                    .trim_start_matches(['!', '<'])
+                    .trim().to_string(),
+    }
+}
+
+fn is_scope(node: &RefNode) -> bool {
+    matches!(node,
+        RefNode::ModuleDeclaration(_) |
+        RefNode::InterfaceDeclaration(_) |
+        RefNode::ProgramDeclaration(_) |
+        RefNode::PackageDeclaration(_) |
+        RefNode::ClassDeclaration(_) |
+        RefNode::FunctionDeclaration(_) |
+        RefNode::TaskDeclaration(_))
+}
+
+/// Get all nodes of the node's own scope.
+///
+/// Nodes of nested scopes, like functions, tasks, classes
+/// or nested modules, are skipped.
+///
+pub fn scope_nodes<'a>(node: &RefNode<'a>) -> Vec<RefNode<'a>> {
+    let mut nodes: Vec<RefNode<'a>> = Vec::new();
+    let mut skip_depth: usize = 0;
+    let mut is_root = true;
+
+    for event in node.clone().into_iter().event() {
+        match event {
+            NodeEvent::Enter(x) => {
+                if skip_depth > 0 {
+                    skip_depth += 1;
+                }
+                else if !is_root && is_scope(&x) {
+                    skip_depth = 1;
+                }
+                else {
+                    nodes.push(x);
+                }
+                is_root = false;
+            }
+            NodeEvent::Leave(_) => {
+                skip_depth = skip_depth.saturating_sub(1);
+            }
+        }
+    }
+
+    nodes
+}
+
+/// Get nested scope nodes, like functions, tasks, classes
+/// or nested modules, declared directly in the node's own scope.
+///
+pub fn nested_scopes<'a>(node: &RefNode<'a>) -> Vec<RefNode<'a>> {
+    let mut nodes: Vec<RefNode<'a>> = Vec::new();
+    let mut skip_depth: usize = 0;
+    let mut is_root = true;
+
+    for event in node.clone().into_iter().event() {
+        match event {
+            NodeEvent::Enter(x) => {
+                if skip_depth > 0 {
+                    skip_depth += 1;
+                }
+                else if !is_root && is_scope(&x) {
+                    skip_depth = 1;
+                    nodes.push(x);
+                }
+                is_root = false;
+            }
+            NodeEvent::Leave(_) => {
+                skip_depth = skip_depth.saturating_sub(1);
+            }
+        }
+    }
+
+    nodes
+}
+
+struct CommentInfo {
+    offset: usize,
+    first_line: u32,
+    last_line: u32,
+    text: String,
+}
+
+/// Comments of a syntax subtree.
+///
+/// Used to attach comments to declarations,
+/// either trailing on the same line or preceding on the lines above.
+///
+pub struct Comments {
+    comments: Vec<CommentInfo>,
+    /// Line number and offset of the first code token on the line.
+    code_lines: HashMap<u32, usize>,
+    /// Offset and first character of code tokens, in order.
+    tokens: Vec<(usize, char)>,
+}
+
+impl Comments {
+
+    pub fn new(syntax_tree: &SyntaxTree, node: &RefNode) -> Comments {
+        let mut comments: Vec<CommentInfo> = Vec::new();
+        let mut code_lines: HashMap<u32, usize> = HashMap::new();
+        let mut tokens: Vec<(usize, char)> = Vec::new();
+        let mut whitespace_depth: usize = 0;
+
+        for event in node.clone().into_iter().event() {
+            match event {
+                NodeEvent::Enter(RefNode::WhiteSpace(_)) => whitespace_depth += 1,
+                NodeEvent::Leave(RefNode::WhiteSpace(_)) => whitespace_depth -= 1,
+                NodeEvent::Enter(RefNode::Comment(x)) => {
+                    let locate = &x.nodes.0;
+                    if let Some(text) = syntax_tree.get_str(locate) {
+                        let nr_lines = text.trim_end().matches('\n').count() as u32;
+                        comments.push(CommentInfo {
+                            offset: locate.offset,
+                            first_line: locate.line,
+                            last_line: locate.line + nr_lines,
+                            text: clean_comment(text),
+                        });
+                    }
+                }
+                NodeEvent::Enter(RefNode::Locate(x)) if whitespace_depth == 0 => {
+                    let offset = code_lines.entry(x.line).or_insert(x.offset);
+                    *offset = (*offset).min(x.offset);
+                    if let Some(c) = syntax_tree.get_str(x).and_then(|t| t.chars().next()) {
+                        tokens.push((x.offset, c));
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        Comments { comments, code_lines, tokens }
+    }
+
+    fn is_standalone(&self, comment: &CommentInfo) -> bool {
+        match self.code_lines.get(&comment.first_line) {
+            Some(&offset) => offset > comment.offset,
+            None => true,
+        }
+    }
+
+    /// Comment on the same line after the token.
+    ///
+    /// In `a, b, // comment` the comment is of `b` only: it is not trailing
+    /// when `,` or `;` outside of brackets is followed by more code.
+    ///
+    pub fn trailing(&self, locate: &Locate) -> Option<String> {
+        let comment = self.comments.iter()
+            .find(|c| c.first_line == locate.line && c.offset > locate.offset)?;
+
+        let mut depth: i32 = 0;
+        let mut separated = false;
+        for &(_, c) in self.tokens.iter().filter(|(offset, _)| *offset > locate.offset && *offset < comment.offset) {
+            if separated {
+                return None;
+            }
+            match c {
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                ',' | ';' if depth <= 0 => separated = true,
+                _ => (),
+            }
+        }
+
+        Some(comment.text.clone())
+    }
+
+    /// Block of standalone comments right above the line of the token.
+    ///
+    pub fn preceding(&self, locate: &Locate) -> Option<String> {
+        self.preceding_block(locate).map(|block| block.join(" "))
+    }
+
+    /// Same as `preceding`, but every comment stays on its own line,
+    /// so consecutive `//` lines keep paragraphs and lists of markdown descriptions.
+    ///
+    pub fn preceding_text(&self, locate: &Locate) -> Option<String> {
+        self.preceding_block(locate).map(|block| block.join("\n"))
+    }
+
+    fn preceding_block(&self, locate: &Locate) -> Option<Vec<&str>> {
+        let mut block: Vec<&str> = Vec::new();
+        let mut line = locate.line;
+
+        while let Some(c) = self.comments.iter()
+            .rev()
+            .find(|c| c.last_line + 1 == line && c.offset < locate.offset && self.is_standalone(c))
+        {
+            block.push(&c.text);
+            line = c.first_line;
+        }
+
+        if block.is_empty() {
+            return None;
+        }
+
+        block.reverse();
+        Some(block)
+    }
+
+    /// Trailing comment if there is one, otherwise preceding comment.
+    ///
+    pub fn attached(&self, locate: &Locate) -> Option<String> {
+        self.trailing(locate).or_else(|| self.preceding(locate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sv_parser::parse_sv_str;
+    use std::path::PathBuf;
+
+    #[test]
+    fn preceding_comments() {
+        let source = "// Title.\n//\n// - item\n/* Block */\nclass C; endclass\n";
+        let (syntax_tree, _) = parse_sv_str(source, PathBuf::from("test.sv"),
+            &HashMap::new(), &[] as &[PathBuf], false, false).unwrap();
+        let root = syntax_tree.into_iter().next().unwrap();
+        let class = syntax_tree.into_iter()
+            .find(|node| matches!(node, RefNode::ClassDeclaration(_)))
+            .unwrap();
+        let first = unwrap_locate!(class).unwrap();
+
+        let comments = Comments::new(&syntax_tree, &root);
+
+        assert_eq!(comments.preceding(first).as_deref(), Some("Title.  - item Block"));
+        assert_eq!(comments.preceding_text(first).as_deref(), Some("Title.\n\n- item\nBlock"));
+        assert_eq!(comments.trailing(first), None);
+    }
+}
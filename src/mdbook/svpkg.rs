@@ -1,56 +1,24 @@
-//! Parse SV file and generate info about Verilog package(s).
+//! Generate info about Verilog package(s).
 //!
 //!
 //!
 
-use sv_parser::{parse_sv, unwrap_node, /*unwrap_locate, Locate,*/ RefNode, SyntaxTree};
-//use sv_parser::{PortDirection, NetType, IntegerVectorType};
-use std::collections::HashMap;
-use std::path::{Path, PathBuf};
-//use regex::Regex;
-use crate::mdbook::svpar;
+use crate::mdbook::design::{Design, Package, Scope, Typedef, TypedefKind};
+use crate::mdbook::book::Book;
+use crate::mdbook::markdown;
+use crate::mdbook::xref::Resolver;
 
 pub fn generate_sv_package_info(
-    output_path: &str,
-    file_path: &str
+    book: &mut Book,
+    design: &Design
 ) -> (Vec<String>, Vec<(String, String, String)>)
 {
     let mut text: Vec<String> = Vec::new();
     let mut list: Vec<(String, String, String)> = Vec::new();
 
-    // The list of defined macros
-    let defines = HashMap::new();
-    // The list of include paths
-    let includes: Vec<PathBuf> = Vec::new();
-
-    // Parse
-    let result = parse_sv(file_path, &defines, &includes, false, true);
-
-    if let Ok((syntax_tree, _)) = result {
-        let mut prev_node: Option<RefNode> = None;
-        // &SyntaxTree is iterable
-        for node in &syntax_tree {
-            // The type of each node is RefNode
-            match node {
-                RefNode::PackageDeclaration(x) => {
-                    let id = unwrap_node!(x, PackageIdentifier).unwrap();
-                    let id = svpar::get_identifier(id).unwrap();
-                    let id = syntax_tree.get_str(&id).unwrap();
-                    let item = print_package(&mut text, output_path, file_path, id,
-                        &syntax_tree, &node, &prev_node);
-                    list.push(item)
-                }
-                RefNode::Description(_) => {
-                }
-                RefNode::WhiteSpace(_) => {
-                }
-                RefNode::Locate(_) => {
-                }
-                x => { prev_node = Some(x); () },
-            }
-        }
-    } else {
-        println!("parsing of '{}' failed\n", file_path);
+    for pkg in &design.packages {
+        let item = print_package(&mut text, book, pkg, design);
+        list.push(item)
     }
 
     (text, list)
@@ -58,38 +26,160 @@ pub fn generate_sv_package_info(
 
 fn print_package(
     top_text: &mut Vec<String>,
-    output_path: &str,
-    file_path: &str,
-    pkg_name: &str,
-    _syntax_tree: &SyntaxTree,
-    _pkg_node: &RefNode,
-    _prev_node: &Option<RefNode>
+    book: &mut Book,
+    pkg: &Package,
+    design: &Design
 ) -> (String, String, String)
 {
+    let pkg_name = pkg.name.as_str();
+    let file_path = pkg.location.file.as_str();
+
     let mut text = String::new();
 
     text.push_str(format!("## Package `{}`\n\n", pkg_name).as_str());
     text.push_str(format!("File: `{}`\n\n", file_path).as_str());
 
-    /*text.push_str(format!("### Ports: \n\n").as_str());
-    if is_ansi {
-        print_ansi_ports(&mut text, syntax_tree, module_node);
-    }
+    let page = markdown::page_path(file_path, "pkg", pkg_name);
+
+    let scope = Scope::Package(pkg_name.to_string());
+    let resolver = Resolver::new(design, &scope);
 
-    text.push_str(format!("\n\n### Instantiates modules: \n\n").as_str());
-    print_instantiated_modules(&mut text, syntax_tree, module_node);*/
+    print_imports_exports(&mut text, &resolver, pkg, &page);
 
-    //print_module_comments(&mut text, syntax_tree, module_node, prev_node);
+    markdown::print_parameters(&mut text, &pkg.parameters);
 
-    let mut pkg_path = Path::new(output_path).join("src").join(file_path);
-    pkg_path.set_extension(format!("pkg.{}.md", pkg_name));
-    let mut src_pkg_path = Path::new("src").join(file_path);
-    src_pkg_path.set_extension(format!("pkg.{}.md", pkg_name));
-    let src_pkg_path = src_pkg_path.to_str().unwrap();
+    print_typedefs(&mut text, pkg, &resolver, &page);
 
-    std::fs::write(pkg_path, text).expect("failed to write file");
+    markdown::print_subroutine_list(&mut text, design, &scope, &page);
+
+    if let Some(comment) = &pkg.comment {
+        text.push_str("### Description:\n\n");
+        text.push_str(comment.as_str());
+        text.push('\n');
+    }
+
+    let src_pkg_path = page.as_str();
+
+    book.add_page(src_pkg_path, text);
 
     top_text.push(format!("- [`{}  :{}`]({})\n", pkg_name, file_path, src_pkg_path));
 
     (pkg_name.to_string(), file_path.to_string(), src_pkg_path.to_string())
-}
\ No newline at end of file
+}
+
+fn print_imports_exports(
+    text: &mut String,
+    resolver: &Resolver,
+    pkg: &Package,
+    page: &str
+)
+{
+    for (title, items) in [("Imports", &pkg.imports), ("Exports", &pkg.exports)] {
+        if items.is_empty() {
+            continue;
+        }
+
+        text.push_str(format!("### {}: \n\n", title).as_str());
+
+        for item in items {
+            // `export *::*` refers to all imported packages.
+            let pkg_page = item.split_once("::")
+                .filter(|(name, _)| *name != "*")
+                .and_then(|(name, _)| resolver.package_page(name));
+            match pkg_page {
+                Some(pkg_page) => text.push_str(format!("- [`{}`]({})\n",
+                    item, markdown::relative_link(page, &pkg_page)).as_str()),
+                None => text.push_str(format!("- `{}`\n", item).as_str()),
+            }
+        }
+
+        text.push_str("\n\n");
+    }
+}
+
+fn print_typedefs(
+    text: &mut String,
+    pkg: &Package,
+    resolver: &Resolver,
+    page: &str
+)
+{
+    if pkg.typedefs.is_empty() {
+        return;
+    }
+
+    text.push_str("### Types: \n\n");
+
+    for typedef in &pkg.typedefs {
+        print_typedef(text, typedef, resolver, page);
+    }
+}
+
+fn print_typedef(
+    text: &mut String,
+    typedef: &Typedef,
+    resolver: &Resolver,
+    page: &str
+)
+{
+    text.push_str(format!("#### `{}`\n\n", typedef.name).as_str());
+
+    match (typedef.kind, typedef.width) {
+        (TypedefKind::Forward, _) => {
+            let decl = ["typedef", typedef.data_type.as_str(), typedef.name.as_str()]
+                .iter()
+                .filter(|w| !w.is_empty())
+                .copied()
+                .collect::<Vec<&str>>()
+                .join(" ");
+            text.push_str(format!("Forward declaration: `{}`\n\n", decl).as_str());
+        }
+        (_, Some(width)) => text.push_str(
+            format!("Type: `{}`, width: {} bits\n\n", typedef.data_type, width).as_str()),
+        (_, None) => text.push_str(
+            format!("Type: `{}`\n\n", typedef.data_type).as_str()),
+    }
+
+    if let Some(comment) = &typedef.comment {
+        text.push_str(comment.as_str());
+        text.push_str("\n\n");
+    }
+
+    match typedef.kind {
+        TypedefKind::Enum => {
+            text.push_str("| Name | Value | Encoding | Description |\n");
+            text.push_str("|------|-------|----------|-------------|\n");
+            for member in &typedef.members {
+                text.push_str(format!("| `{}` | {} | {} | {} |\n",
+                    member.name,
+                    markdown::code_cell(&member.value),
+                    markdown::code_cell(&member.encoding),
+                    markdown::table_cell(member.comment.as_deref().unwrap_or(""))).as_str());
+            }
+            text.push_str("\n\n");
+        }
+        TypedefKind::Struct | TypedefKind::Union => {
+            text.push_str("| Field | Type | Width | Bits | Description |\n");
+            text.push_str("|-------|------|-------|------|-------------|\n");
+            for member in &typedef.members {
+                let bits = match (member.offset, member.width) {
+                    (Some(offset), Some(width)) if width > 0 =>
+                        format!("`[{}:{}]`", offset + width - 1, offset),
+                    _ => String::new(),
+                };
+                let member_type = match &member.data_type {
+                    Some(data_type) => markdown::type_link(data_type, resolver.type_str_page(data_type), page),
+                    None => String::new(),
+                };
+                text.push_str(format!("| `{}` | {} | {} | {} | {} |\n",
+                    member.name,
+                    member_type,
+                    member.width.map(|w| w.to_string()).unwrap_or_default(),
+                    bits,
+                    markdown::table_cell(member.comment.as_deref().unwrap_or(""))).as_str());
+            }
+            text.push_str("\n\n");
+        }
+        _ => (),
+    }
+}
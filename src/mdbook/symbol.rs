@@ -0,0 +1,258 @@
+//! Draw block symbol of module as inline SVG.
+//!
+//! Inputs are on the left side, outputs on the right side and inouts
+//! at the bottom. Bus width, or dimensions with parameters, is written
+//! over the port wire, parameters are listed inside the box under the module name.
+//!
+
+use crate::mdbook::consteval::{self, ConstEnv};
+use crate::mdbook::design::{Direction, Module, ParamKind, Port};
+
+const CHAR_WIDTH: usize = 7;
+const ROW_HEIGHT: usize = 20;
+const WIRE_LENGTH: usize = 40;
+const MARGIN: usize = 10;
+
+/// SVG image of module block symbol, without blank lines
+/// so it stays one HTML block in markdown.
+///
+pub fn module_symbol_svg(module: &Module) -> String {
+    let env = parameter_env(module);
+
+    // Interface ports have no direction and are drawn on the left like inputs.
+    let left: Vec<&Port> = module.ports.iter()
+        .filter(|p| matches!(p.direction, Some(Direction::Input) | Some(Direction::Ref) | None))
+        .collect();
+    let right: Vec<&Port> = module.ports.iter()
+        .filter(|p| p.direction == Some(Direction::Output))
+        .collect();
+    let bottom: Vec<&Port> = module.ports.iter()
+        .filter(|p| p.direction == Some(Direction::Inout))
+        .collect();
+
+    // Local parameters can't be overridden, so they are not part of the symbol.
+    let params: Vec<String> = module.parameters.iter()
+        .filter(|p| matches!(p.kind, ParamKind::Parameter | ParamKind::TypeParameter))
+        .map(|p| match &p.default {
+            Some(default) => format!("{} = {}", p.name, default),
+            None => p.name.clone(),
+        })
+        .collect();
+
+    let label_len = |ports: &[&Port]| ports.iter().map(|p| port_label(p).len()).max().unwrap_or(0);
+    let annotation_len = |ports: &[&Port]| ports.iter()
+        .map(|p| width_annotation(p, &env).map_or(0, |w| w.len()))
+        .max()
+        .unwrap_or(0);
+
+    // Wires must be long enough for width annotations.
+    let left_wire = WIRE_LENGTH.max((annotation_len(&left) + 2) * CHAR_WIDTH);
+    let right_wire = WIRE_LENGTH.max((annotation_len(&right) + 2) * CHAR_WIDTH);
+
+    let header_rows = 1 + params.len();
+    let side_rows = left.len().max(right.len());
+    let bottom_slot = (label_len(&bottom) + 2) * CHAR_WIDTH;
+
+    let box_width = [
+        (label_len(&left) + label_len(&right) + 4) * CHAR_WIDTH,
+        (module.name.len() + 4) * CHAR_WIDTH,
+        (params.iter().map(|p| p.len()).max().unwrap_or(0) + 4) * CHAR_WIDTH,
+        bottom.len() * bottom_slot,
+        120,
+    ].into_iter().max().unwrap();
+    let box_height = (header_rows + side_rows + usize::from(!bottom.is_empty())) * ROW_HEIGHT + MARGIN;
+
+    let box_x = MARGIN + left_wire;
+    let box_y = MARGIN;
+    let width = box_x + box_width + right_wire + MARGIN;
+    let bottom_wire = match bottom.is_empty() {
+        true => 0,
+        false => WIRE_LENGTH,
+    };
+    let height = box_y + box_height + bottom_wire + MARGIN;
+
+    let mut svg = String::new();
+
+    svg.push_str(format!("<svg xmlns=\"http://www.w3.org/2000/svg\" class=\"svdoc-symbol\" \
+        width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" \
+        font-family=\"monospace\" font-size=\"12\">\n", width, height, width, height).as_str());
+    svg.push_str(format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" \
+        fill=\"none\" stroke=\"currentColor\" stroke-width=\"2\"/>\n",
+        box_x, box_y, box_width, box_height).as_str());
+
+    let center = box_x + box_width / 2;
+    svg.push_str(format!("<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" font-weight=\"bold\" \
+        fill=\"currentColor\">{}</text>\n",
+        center, box_y + ROW_HEIGHT - 4, xml_escape(&module.name)).as_str());
+    for (i, param) in params.iter().enumerate() {
+        svg.push_str(format!("<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" font-style=\"italic\" \
+            fill=\"currentColor\">{}</text>\n",
+            center, box_y + (i + 2) * ROW_HEIGHT - 4, xml_escape(param)).as_str());
+    }
+
+    let first_row_y = box_y + header_rows * ROW_HEIGHT + ROW_HEIGHT / 2;
+
+    for (i, port) in left.iter().enumerate() {
+        let y = first_row_y + i * ROW_HEIGHT;
+        draw_wire(&mut svg, box_x - left_wire, y, box_x, y, width_annotation(port, &env));
+        svg.push_str(format!("<text x=\"{}\" y=\"{}\" fill=\"currentColor\">{}</text>\n",
+            box_x + CHAR_WIDTH, y + 4, xml_escape(&port_label(port))).as_str());
+    }
+
+    for (i, port) in right.iter().enumerate() {
+        let y = first_row_y + i * ROW_HEIGHT;
+        draw_wire(&mut svg, box_x + box_width, y, box_x + box_width + right_wire, y,
+            width_annotation(port, &env));
+        svg.push_str(format!("<text x=\"{}\" y=\"{}\" text-anchor=\"end\" fill=\"currentColor\">{}</text>\n",
+            box_x + box_width - CHAR_WIDTH, y + 4, xml_escape(&port_label(port))).as_str());
+    }
+
+    let slot = box_width / bottom.len().max(1);
+    for (i, port) in bottom.iter().enumerate() {
+        let x = box_x + slot * i + slot / 2;
+        let y = box_y + box_height;
+        draw_wire(&mut svg, x, y, x, y + bottom_wire, width_annotation(port, &env));
+        svg.push_str(format!("<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" fill=\"currentColor\">{}</text>\n",
+            x, y - 6, xml_escape(&port_label(port))).as_str());
+    }
+
+    svg.push_str("</svg>\n");
+
+    svg
+}
+
+/// Port wire, a bus is marked with a slash and its width.
+///
+fn draw_wire(svg: &mut String, x1: usize, y1: usize, x2: usize, y2: usize, annotation: Option<String>) {
+    svg.push_str(format!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"currentColor\"/>\n",
+        x1, y1, x2, y2).as_str());
+
+    if let Some(annotation) = annotation {
+        let (mx, my) = ((x1 + x2) / 2, (y1 + y2) / 2);
+        svg.push_str(format!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"currentColor\"/>\n",
+            mx - 4, my + 5, mx + 4, my - 5).as_str());
+        match y1 == y2 {
+            true => svg.push_str(format!("<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" \
+                font-size=\"10\" fill=\"currentColor\">{}</text>\n",
+                mx, my - 7, xml_escape(&annotation)).as_str()),
+            false => svg.push_str(format!("<text x=\"{}\" y=\"{}\" \
+                font-size=\"10\" fill=\"currentColor\">{}</text>\n",
+                mx + 7, my + 4, xml_escape(&annotation)).as_str()),
+        }
+    }
+}
+
+/// Port name, interface ports also show interface and modport.
+///
+fn port_label(port: &Port) -> String {
+    let mut label = port.name.clone();
+    label.push_str(&port.unpacked_dims.concat());
+
+    if let Some(iface) = &port.port_type.interface {
+        match &port.port_type.modport {
+            Some(modport) => label = format!("{} ({}.{})", label, iface, modport),
+            None => label = format!("{} ({})", label, iface),
+        }
+    }
+
+    label
+}
+
+/// Width of bus port, `None` for scalar and `[0:0]` ports.
+/// Dimensions with parameters are shown as written, with computed width if possible.
+///
+fn width_annotation(port: &Port, env: &ConstEnv) -> Option<String> {
+    let dims = &port.port_type.packed_dims;
+    if dims.iter().all(|dim| dim.replace(char::is_whitespace, "") == "[0:0]") {
+        return None;
+    }
+
+    match (dims_width(dims, &ConstEnv::new()), dims_width(dims, env)) {
+        (Some(width), _) => Some(width.to_string()),
+        (None, Some(width)) => Some(format!("{} ({})", dims.concat(), width)),
+        (None, None) => Some(dims.concat()),
+    }
+}
+
+/// Number of bits in packed dimensions.
+///
+fn dims_width(dims: &[String], env: &ConstEnv) -> Option<u64> {
+    dims.iter()
+        .map(|dim| {
+            let range = dim.trim().trim_start_matches('[').trim_end_matches(']');
+            match range.split_once(':') {
+                Some((msb, lsb)) => consteval::range_width(msb, lsb, env),
+                None => consteval::eval(range, env).and_then(|n| u64::try_from(n).ok()),
+            }
+        })
+        .try_fold(1u64, |acc, w| acc.checked_mul(w?))
+}
+
+/// Values of parameters with constant defaults.
+///
+fn parameter_env(module: &Module) -> ConstEnv {
+    let mut env = ConstEnv::new();
+
+    for param in &module.parameters {
+        if let Some(value) = param.default.as_deref().and_then(|d| consteval::eval(d, &env)) {
+            env.insert(param.name.clone(), value);
+        }
+    }
+
+    env
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mdbook::design::parse_str;
+
+    #[test]
+    fn width_annotations() {
+        let design = parse_str("
+            module Bus #(W = 8, N = 2, parameter type T = logic) (
+                input  logic              clk,
+                input  logic [0:0]        one,
+                input  logic [1:1]        bit1,
+                input  logic [7:0]        byte_bus,
+                input  logic [3:0][7:0]   packed_bus,
+                input  logic [W-1:0]      param_bus,
+                output logic [N-1:0][W:1] param_packed,
+                output logic [DEPTH-1:0]  unknown_bus,
+                output logic [$clog2(W)-1:0] func_bus,
+                output logic [W-8:0]      param_one,
+                output logic [7:0]        mem [4],
+                output logic [15:0][1152921504606846975:0] huge
+            );
+            endmodule");
+        let module = &design.modules[0];
+        let env = parameter_env(module);
+
+        let annotations = module.ports.iter()
+            .map(|p| (p.name.as_str(), width_annotation(p, &env)))
+            .collect::<Vec<_>>();
+        assert_eq!(annotations, [
+            ("clk", None),
+            ("one", None),
+            ("bit1", Some("1".to_string())),
+            ("byte_bus", Some("8".to_string())),
+            ("packed_bus", Some("32".to_string())),
+            ("param_bus", Some("[W-1:0] (8)".to_string())),
+            ("param_packed", Some("[N-1:0][W:1] (16)".to_string())),
+            ("unknown_bus", Some("[DEPTH-1:0]".to_string())),
+            ("func_bus", Some("[$clog2(W)-1:0] (3)".to_string())),
+            // Width of one bit, but still a bus.
+            ("param_one", Some("[W-8:0] (1)".to_string())),
+            ("mem", Some("8".to_string())),
+            // Overflow of 64 bit width.
+            ("huge", Some("[15:0][1152921504606846975:0]".to_string())),
+        ]);
+    }
+}
@@ -0,0 +1,563 @@
+//! Extract types declared with `typedef` and compute their widths.
+//!
+//! Widths and enum encodings are computed only when all involved
+//! constants and types are known: literals, parameters of the scope
+//! and of imported packages, and previously declared types.
+//!
+
+use std::collections::HashMap;
+use sv_parser::{unwrap_locate, unwrap_node, RefNode, SyntaxTree};
+use sv_parser::{DataType, DataTypeOrVoid, EnumBaseType, PackedDimension, StructUnion};
+use sv_parser::{DataTypeEnum, DataTypeStructUnion, IntegerAtomType, NonIntegerType, TypeDeclaration};
+
+use crate::mdbook::consteval::{self, ConstEnv};
+use crate::mdbook::design::{Location, Package, Parameter, TypeMember, Typedef, TypedefKind};
+use crate::mdbook::svpar;
+
+/// Constants and type widths visible in a scope.
+///
+#[derive(Default)]
+pub struct TypeEnv {
+    pub consts: ConstEnv,
+    pub widths: HashMap<String, u64>,
+}
+
+impl TypeEnv {
+
+    /// Make names declared in the package visible as `Pkg::name`,
+    /// and also as `name` if the package is imported.
+    pub fn add_package(&mut self, pkg: &Package, imported: bool) {
+        let mut consts = ConstEnv::new();
+        for param in &pkg.parameters {
+            if let Some(value) = param.default.as_ref().and_then(|d| consteval::eval(d, &consts)) {
+                consts.insert(param.name.clone(), value);
+            }
+        }
+        for (name, value) in consts {
+            if imported {
+                self.consts.insert(name.clone(), value);
+            }
+            self.consts.insert(format!("{}::{}", pkg.name, name), value);
+        }
+        for typedef in &pkg.typedefs {
+            if let Some(width) = typedef.width {
+                if imported {
+                    self.widths.insert(typedef.name.clone(), width);
+                }
+                self.widths.insert(format!("{}::{}", pkg.name, typedef.name), width);
+            }
+        }
+    }
+
+    /// Add values of parameters, in order of declaration.
+    pub fn add_parameters(&mut self, params: &[Parameter]) {
+        for param in params {
+            if let Some(value) = param.default.as_ref().and_then(|d| consteval::eval(d, &self.consts)) {
+                self.consts.insert(param.name.clone(), value);
+            }
+        }
+    }
+}
+
+/// Extract types declared with `typedef` directly in the scope.
+///
+pub fn extract_typedefs(
+    syntax_tree: &SyntaxTree,
+    source: &svpar::SourceFile,
+    scope_node: &RefNode,
+    comments: &svpar::Comments,
+    env: &mut TypeEnv
+) -> Vec<Typedef>
+{
+    let mut typedefs: Vec<Typedef> = Vec::new();
+
+    for node in svpar::scope_nodes(scope_node) {
+        let x = match node {
+            RefNode::TypeDeclaration(x) => x,
+            _ => continue,
+        };
+
+        let (id, mut typedef) = match x {
+            TypeDeclaration::DataType(t) => {
+                let mut typedef = data_type_typedef(syntax_tree, &t.nodes.1, env);
+                // Unpacked dimensions of the typedef itself, like `typedef byte_t mem_t[16]`.
+                let dims: Vec<&str> = t.nodes.3.iter()
+                    .filter_map(|dim| syntax_tree.get_str_trim(dim))
+                    .collect();
+                if !dims.is_empty() {
+                    typedef.data_type = format!("{} {}", typedef.data_type, dims.concat());
+                    typedef.packed = false;
+                    typedef.width = None;
+                }
+                (RefNode::from(&t.nodes.2), typedef)
+            }
+            TypeDeclaration::Reserved(t) => {
+                let data_type = match &t.nodes.1 {
+                    Some(keyword) => syntax_tree.get_str_trim(keyword).unwrap_or(""),
+                    None => "",
+                };
+                (RefNode::from(&t.nodes.2), new_typedef(TypedefKind::Forward, data_type))
+            }
+            TypeDeclaration::Interface(t) => {
+                let data_type = syntax_tree.get_str_trim(t.as_ref()).unwrap_or("");
+                (RefNode::from(&t.nodes.4), new_typedef(TypedefKind::Alias, data_type))
+            }
+        };
+
+        let id = svpar::get_identifier(id).unwrap();
+        typedef.name = syntax_tree.get_str(&id).unwrap().to_string();
+        typedef.location = source.location(syntax_tree, &id);
+
+        if let Some(first) = unwrap_locate!(node.clone()) {
+            typedef.comment = comments.preceding(first).or_else(|| comments.trailing(&id));
+        }
+
+        attach_member_comments(syntax_tree, &node, comments, &mut typedef.members);
+
+        // Forward declaration does not hide already known width.
+        if typedef.kind != TypedefKind::Forward {
+            match typedef.width {
+                Some(width) => { env.widths.insert(typedef.name.clone(), width); }
+                None => { env.widths.remove(&typedef.name); }
+            }
+        }
+
+        typedefs.push(typedef);
+    }
+
+    typedefs
+}
+
+fn new_typedef(kind: TypedefKind, data_type: &str) -> Typedef {
+    Typedef {
+        name: String::new(),
+        kind,
+        data_type: data_type.to_string(),
+        packed: false,
+        width: None,
+        members: Vec::new(),
+        location: Location { file: String::new(), line: 0 },
+        comment: None,
+    }
+}
+
+fn data_type_typedef(syntax_tree: &SyntaxTree, data_type: &DataType, env: &TypeEnv) -> Typedef {
+    match data_type {
+        DataType::Enum(x) => enum_typedef(syntax_tree, x, env),
+        DataType::StructUnion(x) => struct_union_typedef(syntax_tree, x, env),
+        _ => {
+            let text = syntax_tree.get_str_trim(data_type).unwrap_or("");
+            let mut typedef = new_typedef(TypedefKind::Alias, text);
+            typedef.width = type_width(syntax_tree, data_type, env);
+            typedef.packed = typedef.width.is_some();
+            typedef
+        }
+    }
+}
+
+/// Enum with values of members, computed as previous value plus one
+/// when not given explicitly.
+///
+fn enum_typedef(syntax_tree: &SyntaxTree, x: &DataTypeEnum, env: &TypeEnv) -> Typedef {
+    let base_type = match &x.nodes.1 {
+        Some(base) => syntax_tree.get_str_trim(base).unwrap_or("int").to_string(),
+        None => String::from("int"),
+    };
+    let base_width = match &x.nodes.1 {
+        Some(EnumBaseType::Atom(b)) => atom_width(&b.nodes.0),
+        Some(EnumBaseType::Vector(b)) => match &b.nodes.2 {
+            Some(dim) => packed_dims_width(syntax_tree, std::slice::from_ref(dim), env),
+            None => Some(1),
+        },
+        Some(EnumBaseType::Type(b)) => {
+            let name = syntax_tree.get_str_trim(&b.nodes.0).unwrap_or("");
+            let width = env.widths.get(name).copied();
+            match &b.nodes.1 {
+                Some(dim) => width.and_then(|w| Some(w * packed_dims_width(syntax_tree, std::slice::from_ref(dim), env)?)),
+                None => width,
+            }
+        }
+        None => Some(32),
+    };
+    let width = base_width.and_then(|w| Some(w * packed_dims_width(syntax_tree, &x.nodes.3, env)?));
+
+    let mut typedef = new_typedef(TypedefKind::Enum, &format!("enum {}", base_type));
+    typedef.packed = true;
+    typedef.width = width;
+
+    let mut next_value: Option<i64> = Some(0);
+    let (_, list, _) = &(x.nodes.2).nodes;
+
+    for member in list.contents() {
+        let name = syntax_tree.get_str_trim(&member.nodes.0).unwrap_or("").to_string();
+
+        let (value_text, value) = match &member.nodes.2 {
+            Some((_, expr)) => {
+                let text = syntax_tree.get_str_trim(expr).unwrap_or("").to_string();
+                let value = consteval::eval(&text, &env.consts);
+                (Some(text), value)
+            }
+            None => (next_value.map(|v| v.to_string()), next_value),
+        };
+
+        next_value = value.and_then(|v| v.checked_add(1));
+
+        let encoding = match (value, width) {
+            (Some(value), Some(width)) if width <= 64 => Some(encode(value, width)),
+            _ => None,
+        };
+
+        typedef.members.push(TypeMember {
+            name,
+            data_type: None,
+            value: value_text,
+            encoding,
+            width: None,
+            offset: None,
+            comment: None,
+        });
+    }
+
+    typedef
+}
+
+/// Sized literal of the value, binary for narrow types, hex for wide ones.
+///
+fn encode(value: i64, width: u64) -> String {
+    let mask = if width == 64 { u64::MAX } else { (1u64 << width) - 1 };
+    let value = (value as u64) & mask;
+
+    match width <= 16 {
+        true => format!("{}'b{:0w$b}", width, value, w = width as usize),
+        false => format!("{}'h{:0w$x}", width, value, w = width.div_ceil(4) as usize),
+    }
+}
+
+/// Struct or union with width of every field and,
+/// for packed ones, offset of the field's least significant bit.
+///
+fn struct_union_typedef(syntax_tree: &SyntaxTree, x: &DataTypeStructUnion, env: &TypeEnv) -> Typedef {
+    let kind = match &x.nodes.0 {
+        StructUnion::Struct(_) => TypedefKind::Struct,
+        _ => TypedefKind::Union,
+    };
+    let packed = x.nodes.1.is_some();
+
+    let mut typedef = new_typedef(kind, syntax_tree.get_str_trim(&x.nodes.0).unwrap_or(""));
+    if let Some((packed_keyword, signing)) = &x.nodes.1 {
+        typedef.data_type.push(' ');
+        typedef.data_type.push_str(syntax_tree.get_str_trim(packed_keyword).unwrap_or("packed"));
+        if let Some(signing) = signing {
+            typedef.data_type.push(' ');
+            typedef.data_type.push_str(syntax_tree.get_str_trim(signing).unwrap_or(""));
+        }
+    }
+    typedef.packed = packed;
+
+    let (_, (first, rest), _) = &(x.nodes.2).nodes;
+
+    for member in std::iter::once(first).chain(rest.iter()) {
+        let data_type_str = syntax_tree.get_str_trim(&member.nodes.2).unwrap_or("").to_string();
+        let type_width = match &member.nodes.2 {
+            DataTypeOrVoid::DataType(data_type) => type_width(syntax_tree, data_type, env),
+            DataTypeOrVoid::Void(_) => Some(0),
+        };
+
+        for var in RefNode::from(&member.nodes.3).into_iter() {
+            if let RefNode::VariableDeclAssignmentVariable(v) = var {
+                let name = syntax_tree.get_str_trim(&v.nodes.0).unwrap_or("").to_string();
+                let dims: Vec<&str> = v.nodes.1.iter()
+                    .filter_map(|dim| syntax_tree.get_str_trim(dim))
+                    .collect();
+                let mut width = type_width;
+                for dim in &v.nodes.1 {
+                    width = width.and_then(|w| Some(w * unpacked_dim_size(syntax_tree, &RefNode::from(dim), env)?));
+                }
+                let data_type = match dims.is_empty() {
+                    true => data_type_str.clone(),
+                    false => format!("{} {}", data_type_str, dims.concat()),
+                };
+                typedef.members.push(TypeMember {
+                    name,
+                    data_type: Some(data_type),
+                    value: None,
+                    encoding: None,
+                    width,
+                    offset: None,
+                    comment: None,
+                });
+            }
+        }
+    }
+
+    if packed {
+        let dims_width = packed_dims_width(syntax_tree, &x.nodes.3, env);
+        let widths: Option<Vec<u64>> = typedef.members.iter().map(|m| m.width).collect();
+        if let Some(widths) = widths {
+            let size = match kind {
+                TypedefKind::Struct => widths.iter().sum(),
+                _ => widths.iter().copied().max().unwrap_or(0),
+            };
+            typedef.width = dims_width.map(|dims_width| size * dims_width);
+
+            // The first field of packed struct is the most significant.
+            let mut offset = size;
+            for member in &mut typedef.members {
+                member.offset = match kind {
+                    TypedefKind::Struct => { offset -= member.width.unwrap_or(0); Some(offset) }
+                    _ => Some(0),
+                };
+            }
+        }
+    }
+
+    typedef
+}
+
+/// Comments of enum members and struct fields.
+///
+fn attach_member_comments(
+    syntax_tree: &SyntaxTree,
+    node: &RefNode,
+    comments: &svpar::Comments,
+    members: &mut [TypeMember]
+)
+{
+    let mut ids = Vec::new();
+    for n in node.clone().into_iter() {
+        match n {
+            RefNode::EnumIdentifier(_) => ids.push(n),
+            RefNode::VariableDeclAssignmentVariable(v) => ids.push(RefNode::from(&v.nodes.0)),
+            _ => (),
+        }
+    }
+
+    for (member, id) in members.iter_mut().zip(ids) {
+        if let Some(id) = svpar::get_identifier(id) {
+            if syntax_tree.get_str(&id) == Some(member.name.as_str()) {
+                member.comment = comments.attached(&id);
+            }
+        }
+    }
+}
+
+fn atom_width(atom: &IntegerAtomType) -> Option<u64> {
+    match atom {
+        IntegerAtomType::Byte(_) => Some(8),
+        IntegerAtomType::Shortint(_) => Some(16),
+        IntegerAtomType::Int(_) => Some(32),
+        IntegerAtomType::Longint(_) => Some(64),
+        IntegerAtomType::Integer(_) => Some(32),
+        IntegerAtomType::Time(_) => Some(64),
+    }
+}
+
+/// Width in bits of a data type, `None` if not known or not packed.
+///
+pub fn type_width(syntax_tree: &SyntaxTree, data_type: &DataType, env: &TypeEnv) -> Option<u64> {
+    match data_type {
+        DataType::Vector(x) => packed_dims_width(syntax_tree, &x.nodes.2, env),
+        DataType::Atom(x) => atom_width(&x.nodes.0),
+        DataType::NonIntegerType(x) => match x.as_ref() {
+            NonIntegerType::Shortreal(_) => Some(32),
+            NonIntegerType::Real(_) | NonIntegerType::Realtime(_) => Some(64),
+        },
+        DataType::Enum(x) => enum_typedef(syntax_tree, x, env).width,
+        DataType::StructUnion(x) => struct_union_typedef(syntax_tree, x, env).width,
+        DataType::Type(x) => {
+            let scope = x.nodes.0.as_ref()
+                .and_then(|scope| syntax_tree.get_str_trim(scope))
+                .unwrap_or("");
+            let name = format!("{}{}", scope, syntax_tree.get_str_trim(&x.nodes.1).unwrap_or(""));
+            let width = env.widths.get(&name).copied()?;
+            Some(width * packed_dims_width(syntax_tree, &x.nodes.2, env)?)
+        }
+        // Plain type name may be parsed as class type.
+        DataType::ClassType(x) => {
+            let name = syntax_tree.get_str_trim(x.as_ref())?;
+            env.widths.get(name).copied()
+        }
+        _ => None,
+    }
+}
+
+/// Width in bits of one element of net or variable declared by the node.
+///
+pub fn declaration_width(syntax_tree: &SyntaxTree, node: &RefNode, env: &TypeEnv) -> Option<u64> {
+    match unwrap_node!(node.clone(), DataType, ImplicitDataType)? {
+        RefNode::DataType(x) => type_width(syntax_tree, x, env),
+        RefNode::ImplicitDataType(x) => packed_dims_width(syntax_tree, &x.nodes.1, env),
+        _ => None,
+    }
+}
+
+/// Product of sizes of packed dimensions, 1 if there are none.
+///
+fn packed_dims_width(syntax_tree: &SyntaxTree, dims: &[PackedDimension], env: &TypeEnv) -> Option<u64> {
+    let mut width: u64 = 1;
+
+    for dim in dims {
+        let range = match dim {
+            PackedDimension::Range(r) => &(r.nodes.0).nodes.1,
+            PackedDimension::UnsizedDimension(_) => return None,
+        };
+        let msb = syntax_tree.get_str_trim(&range.nodes.0)?;
+        let lsb = syntax_tree.get_str_trim(&range.nodes.2)?;
+        width *= consteval::range_width(msb, lsb, &env.consts)?;
+    }
+
+    Some(width)
+}
+
+/// Number of elements of unpacked dimension `[N]` or `[msb:lsb]`.
+///
+fn unpacked_dim_size(syntax_tree: &SyntaxTree, dim: &RefNode, env: &TypeEnv) -> Option<u64> {
+    match unwrap_node!(dim.clone(), ConstantRange, ConstantExpression)? {
+        RefNode::ConstantRange(r) => {
+            let msb = syntax_tree.get_str_trim(&r.nodes.0)?;
+            let lsb = syntax_tree.get_str_trim(&r.nodes.2)?;
+            consteval::range_width(msb, lsb, &env.consts)
+        }
+        RefNode::ConstantExpression(e) => {
+            let size = consteval::eval(syntax_tree.get_str_trim(e)?, &env.consts)?;
+            u64::try_from(size).ok()
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sv_parser::parse_sv_str;
+    use std::path::PathBuf;
+
+    /// Typedefs of the first package in `source`.
+    ///
+    fn package_typedefs(source: &str, env: &mut TypeEnv) -> Vec<Typedef> {
+        let (syntax_tree, _) = parse_sv_str(source, PathBuf::from("test.sv"),
+            &HashMap::new(), &[] as &[PathBuf], false, false).unwrap();
+        let pkg = syntax_tree.into_iter()
+            .find(|node| matches!(node, RefNode::PackageDeclaration(_)))
+            .unwrap();
+        let comments = svpar::Comments::new(&syntax_tree, &pkg);
+
+        extract_typedefs(&syntax_tree, &svpar::SourceFile::new("test.sv"), &pkg, &comments, env)
+    }
+
+    fn member_layout(typedef: &Typedef) -> Vec<(&str, Option<u64>, Option<u64>)> {
+        typedef.members.iter().map(|m| (m.name.as_str(), m.width, m.offset)).collect()
+    }
+
+    #[test]
+    fn packed_struct() {
+        let mut env = TypeEnv::default();
+        env.consts.insert(String::from("W"), 8);
+
+        let typedefs = package_typedefs("package P;
+            typedef logic [W-1:0] byte_t;
+            typedef struct packed {
+                logic       valid;
+                byte_t [1:0] data;
+                logic [3:0] id, tag;
+                bit signed [$clog2(W):0] count;
+            } packet_t;
+            endpackage", &mut env);
+
+        assert_eq!(typedefs[0].width, Some(8));
+        assert_eq!(typedefs[1].kind, TypedefKind::Struct);
+        assert_eq!(typedefs[1].width, Some(1 + 16 + 4 + 4 + 4));
+        assert_eq!(member_layout(&typedefs[1]), vec![
+            ("valid", Some(1), Some(28)),
+            ("data", Some(16), Some(12)),
+            ("id", Some(4), Some(8)),
+            ("tag", Some(4), Some(4)),
+            ("count", Some(4), Some(0)),
+        ]);
+        assert_eq!(env.widths.get("packet_t"), Some(&29));
+    }
+
+    #[test]
+    fn packed_union_and_array() {
+        let mut env = TypeEnv::default();
+
+        let typedefs = package_typedefs("package P;
+            typedef union packed {
+                logic [15:0] word;
+                logic [1:0][7:0] bytes;
+            } word_t;
+            typedef struct packed { byte a; shortint b; } [3:0] quad_t;
+            endpackage", &mut env);
+
+        assert_eq!(typedefs[0].width, Some(16));
+        assert_eq!(member_layout(&typedefs[0]), vec![
+            ("word", Some(16), Some(0)),
+            ("bytes", Some(16), Some(0)),
+        ]);
+        assert_eq!(typedefs[1].width, Some(4 * 24));
+        assert_eq!(member_layout(&typedefs[1]), vec![
+            ("a", Some(8), Some(16)),
+            ("b", Some(16), Some(0)),
+        ]);
+    }
+
+    #[test]
+    fn unpacked_struct() {
+        let mut env = TypeEnv::default();
+
+        let typedefs = package_typedefs("package P;
+            typedef struct {
+                int     count;
+                byte    data [4];
+                real    ratio;
+            } stats_t;
+            typedef logic [7:0] mem_t [16];
+            endpackage", &mut env);
+
+        assert!(!typedefs[0].packed);
+        assert_eq!(typedefs[0].width, None);
+        assert_eq!(member_layout(&typedefs[0]), vec![
+            ("count", Some(32), None),
+            ("data", Some(32), None),
+            ("ratio", Some(64), None),
+        ]);
+        assert_eq!(typedefs[1].width, None);
+        assert_eq!(env.widths.get("mem_t"), None);
+    }
+
+    #[test]
+    fn unknown_width() {
+        let mut env = TypeEnv::default();
+
+        let typedefs = package_typedefs("package P;
+            typedef struct packed {
+                logic [N-1:0] data;
+                logic         valid;
+            } item_t;
+            endpackage", &mut env);
+
+        assert_eq!(typedefs[0].width, None);
+        assert_eq!(member_layout(&typedefs[0]), vec![
+            ("data", None, None),
+            ("valid", Some(1), None),
+        ]);
+    }
+
+    #[test]
+    fn enum_encodings() {
+        let mut env = TypeEnv::default();
+
+        let typedefs = package_typedefs("package P;
+            typedef enum logic [1:0] { IDLE, RUN = 2, STOP } state_t;
+            typedef enum { A, B } int_enum_t;
+            endpackage", &mut env);
+
+        let encodings: Vec<Option<&str>> = typedefs[0].members.iter()
+            .map(|m| m.encoding.as_deref())
+            .collect();
+        assert_eq!(typedefs[0].width, Some(2));
+        assert_eq!(encodings, vec![Some("2'b00"), Some("2'b10"), Some("2'b11")]);
+        assert_eq!(typedefs[1].width, Some(32));
+        assert_eq!(typedefs[1].members[1].encoding.as_deref(), Some("32'h00000001"));
+    }
+}
@@ -0,0 +1,766 @@
+//! Render WaveDrom diagrams as inline SVG without JavaScript.
+//!
+//! Supported are timing diagrams (`signal`) with the common wave characters,
+//! `data` labels, `period`, `phase`, groups, spacers, `head`/`foot` text
+//! and `config.hscale`, and register diagrams (`reg`) with `config.bits`,
+//! `config.lanes` and `config.hspace`.
+//! Other diagrams like `assign` are left to WaveDrom in the browser.
+//! Source is WaveJSON, which is JSON5: keys may be unquoted,
+//! strings single quoted and trailing commas are allowed.
+//!
+
+use serde_json::{Map, Number, Value};
+
+const CHAR_WIDTH: f64 = 7.0;
+const MARGIN: f64 = 10.0;
+
+/// Width of one wave period with `hscale` 1.
+const PERIOD_WIDTH: f64 = 40.0;
+/// Height of wave and distance between lanes.
+const WAVE_HEIGHT: f64 = 20.0;
+const LANE_HEIGHT: f64 = 30.0;
+/// Width of slope of a value change.
+const SLOPE: f64 = 3.0;
+const GROUP_WIDTH: f64 = 16.0;
+
+/// Fill colors of data waves `=`, `2`..`9`, the same as WaveDrom default skin.
+const DATA_COLORS: [&str; 8] = [
+    "#ffffff", "#ffffb4", "#ffe0b9", "#b9e0ff", "#ccfdfe", "#cdfdc5", "#f0c1fb", "#f5c2c0",
+];
+
+/// Register field fill colors by field `type`.
+const TYPE_COLORS: [&str; 8] = [
+    "#ffffff", "#ffd0d0", "#ffe0b0", "#ffffa0", "#c0ffc0", "#c0e0ff", "#e0c0ff", "#e0e0e0",
+];
+
+/// SVG image of WaveDrom diagram, `None` if the kind of diagram is not supported.
+///
+pub fn to_svg(source: &str) -> Result<Option<String>,String> {
+    let json = parse_json5(source)?;
+
+    if let Some(signal) = json.get("signal") {
+        return Ok(Some(signal_svg(&json, signal)?));
+    }
+    if let Some(reg) = json.get("reg") {
+        return Ok(Some(reg_svg(&json, reg)?));
+    }
+
+    Ok(None)
+}
+
+/// Parse JSON5 as used by WaveDrom into JSON value.
+///
+pub fn parse_json5(source: &str) -> Result<Value,String> {
+    let mut parser = Json5Parser { chars: source.chars().collect(), pos: 0 };
+
+    let value = parser.value()?;
+    parser.skip_space();
+    match parser.peek() {
+        None => Ok(value),
+        Some(c) => Err(format!("unexpected '{}' after the end of WaveJSON", c)),
+    }
+}
+
+struct Json5Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Json5Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    /// Skip white space and comments.
+    fn skip_space(&mut self) {
+        loop {
+            match (self.peek(), self.peek_at(1)) {
+                (Some(c), _) if c.is_whitespace() => self.pos += 1,
+                (Some('/'), Some('/')) => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.pos += 1;
+                    }
+                }
+                (Some('/'), Some('*')) => {
+                    self.pos += 2;
+                    while !matches!((self.peek(), self.peek_at(1)), (None, _) | (Some('*'), Some('/'))) {
+                        self.pos += 1;
+                    }
+                    self.pos = (self.pos + 2).min(self.chars.len());
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(),String> {
+        self.skip_space();
+        match self.peek() {
+            Some(p) if p == c => { self.pos += 1; Ok(()) },
+            Some(p) => Err(format!("expected '{}' but found '{}' in WaveJSON", c, p)),
+            None => Err(format!("expected '{}' but WaveJSON ended", c)),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value,String> {
+        self.skip_space();
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some(q) if q == '"' || q == '\'' => Ok(Value::String(self.string()?)),
+            Some(c) if c == '-' || c == '+' || c == '.' || c.is_ascii_digit() => self.number(),
+            Some(c) if c.is_alphabetic() || c == '_' || c == '$' => match self.identifier().as_str() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                "null" => Ok(Value::Null),
+                "Infinity" | "NaN" => Ok(Value::Null),
+                other => Err(format!("unexpected '{}' in WaveJSON", other)),
+            },
+            Some(c) => Err(format!("unexpected '{}' in WaveJSON", c)),
+            None => Err(String::from("WaveJSON ended unexpectedly")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Value,String> {
+        self.expect('{')?;
+        let mut map = Map::new();
+        loop {
+            self.skip_space();
+            if self.peek() == Some('}') {
+                self.pos += 1;
+                return Ok(Value::Object(map));
+            }
+            let key = match self.peek() {
+                Some(q) if q == '"' || q == '\'' => self.string()?,
+                _ => self.identifier(),
+            };
+            if key.is_empty() {
+                return Err(String::from("expected key in WaveJSON object"));
+            }
+            self.expect(':')?;
+            let value = self.value()?;
+            map.insert(key, value);
+            self.skip_space();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => (),
+                _ => return Err(String::from("expected ',' or '}' in WaveJSON object")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value,String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_space();
+            if self.peek() == Some(']') {
+                self.pos += 1;
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_space();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => (),
+                _ => return Err(String::from("expected ',' or ']' in WaveJSON array")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String,String> {
+        let quote = self.peek().unwrap();
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(String::from("unterminated string in WaveJSON")),
+                Some(c) if c == quote => { self.pos += 1; return Ok(s); },
+                Some('\\') => {
+                    self.pos += 1;
+                    let c = self.peek().ok_or("unterminated string in WaveJSON")?;
+                    self.pos += 1;
+                    match c {
+                        'n' => s.push('\n'),
+                        't' => s.push('\t'),
+                        'r' => s.push('\r'),
+                        'u' => {
+                            let hex: String = self.chars[self.pos..].iter().take(4).collect();
+                            self.pos += hex.len();
+                            let code = u32::from_str_radix(&hex, 16).map_err(|e| e.to_string())?;
+                            s.push(char::from_u32(code).unwrap_or('?'));
+                        }
+                        '\n' => (),
+                        c => s.push(c),
+                    }
+                }
+                Some(c) => { s.push(c); self.pos += 1; },
+            }
+        }
+    }
+
+    fn identifier(&mut self) -> String {
+        let mut s = String::new();
+        while let Some(c) = self.peek() {
+            if !(c.is_alphanumeric() || c == '_' || c == '$') {
+                break;
+            }
+            s.push(c);
+            self.pos += 1;
+        }
+        s
+    }
+
+    fn number(&mut self) -> Result<Value,String> {
+        let mut s = String::new();
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '+') {
+                break;
+            }
+            s.push(c);
+            self.pos += 1;
+        }
+
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s.strip_prefix('+').unwrap_or(&s)),
+        };
+        let number = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+            Some(hex) => i64::from_str_radix(hex, 16).ok().map(|v| Number::from(if negative { -v } else { v })),
+            None => match s.parse::<i64>() {
+                Ok(v) => Some(Number::from(v)),
+                Err(_) => digits.parse::<f64>().ok()
+                    .and_then(|v| Number::from_f64(if negative { -v } else { v })),
+            },
+        };
+
+        number.map(Value::Number).ok_or(format!("bad number '{}' in WaveJSON", s))
+    }
+}
+
+/// Value of wave during one period.
+#[derive(Clone)]
+enum WaveState {
+    Low,
+    High,
+    /// Clock pulse every period, `true` for positive `p`.
+    Clock(bool),
+    Z,
+    X,
+    /// Data with color index and label.
+    Data(usize, String),
+}
+
+/// One period of wave.
+struct Period {
+    state: WaveState,
+    /// `.` or `|` continues the previous state.
+    continued: bool,
+    /// `|` draws gap mark.
+    gap: bool,
+}
+
+struct Row<'a> {
+    lane: Option<&'a Map<String, Value>>,
+    depth: usize,
+}
+
+struct Group {
+    label: String,
+    depth: usize,
+    first: usize,
+    last: usize,
+}
+
+/// Flatten lanes and groups into rows, groups are arrays starting with label.
+///
+fn flatten_lanes<'a>(
+    lanes: &'a [Value],
+    depth: usize,
+    rows: &mut Vec<Row<'a>>,
+    groups: &mut Vec<Group>
+)
+{
+    for lane in lanes {
+        match lane {
+            Value::Array(items) => {
+                let (label, items) = match items.first() {
+                    Some(Value::String(label)) => (label.clone(), &items[1..]),
+                    _ => (String::new(), &items[..]),
+                };
+                let first = rows.len();
+                flatten_lanes(items, depth + 1, rows, groups);
+                if rows.len() > first {
+                    groups.push(Group { label, depth, first, last: rows.len() - 1 });
+                }
+            }
+            Value::Object(map) => rows.push(Row { lane: Some(map), depth }),
+            _ => rows.push(Row { lane: None, depth }),
+        }
+    }
+}
+
+fn get_f64(map: Option<&Value>, key: &str) -> Option<f64> {
+    map.and_then(|m| m.get(key)).and_then(|v| match v {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    })
+}
+
+fn get_str(map: Option<&Value>, key: &str) -> Option<String> {
+    map.and_then(|m| m.get(key)).and_then(|v| match v {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Array(items) => Some(items.iter()
+            .filter_map(|i| match i {
+                Value::String(s) => Some(s.clone()),
+                Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ")),
+        _ => None,
+    })
+}
+
+/// States of wave by period, `.` extends the previous state and `|` is a gap.
+///
+fn wave_states(wave: &str, data: &[String]) -> Vec<Period> {
+    let mut states: Vec<Period> = Vec::new();
+    let mut labels = data.iter();
+
+    for c in wave.chars() {
+        let prev = states.last().map(|p| p.state.clone());
+        let state = match c {
+            '0' | 'l' | 'L' | 'd' => WaveState::Low,
+            '1' | 'h' | 'H' | 'u' => WaveState::High,
+            'p' | 'P' => WaveState::Clock(true),
+            'n' | 'N' => WaveState::Clock(false),
+            'z' => WaveState::Z,
+            '=' | '2'..='9' => {
+                let color = match c {
+                    '=' => 0,
+                    c => c.to_digit(10).unwrap() as usize - 2,
+                };
+                WaveState::Data(color, labels.next().cloned().unwrap_or_default())
+            }
+            '.' | '|' => match prev {
+                Some(WaveState::Data(color, _)) => WaveState::Data(color, String::new()),
+                Some(state) => state,
+                None => WaveState::X,
+            },
+            _ => WaveState::X,
+        };
+        states.push(Period { state, continued: c == '.' || c == '|', gap: c == '|' });
+    }
+
+    states
+}
+
+/// Draw one lane of waves starting at `x0`, `y` is the top of the wave.
+///
+fn draw_wave(svg: &mut String, states: &[Period], x0: f64, y: f64, width: f64) {
+    let top = y;
+    let bottom = y + WAVE_HEIGHT;
+    let mid = y + WAVE_HEIGHT / 2.0;
+
+    // Previous level to connect the next segment, `None` after data or undefined value.
+    let mut prev_y: Option<f64> = None;
+
+    let mut i = 0;
+    while i < states.len() {
+        let state = &states[i].state;
+
+        // Clocks repeat every period, other states merge with following `.`.
+        let mut j = i + 1;
+        if !matches!(state, WaveState::Clock(_)) {
+            while j < states.len() && states[j].continued {
+                j += 1;
+            }
+        }
+        let x = x0 + i as f64 * width;
+        let end = x0 + j as f64 * width;
+
+        match state {
+            WaveState::Low | WaveState::High => {
+                let level = match state { WaveState::High => top, _ => bottom };
+                let start = prev_y.unwrap_or(mid);
+                svg.push_str(format!("<path d=\"M{},{} L{},{} L{},{}\" fill=\"none\" stroke=\"currentColor\"/>\n",
+                    x, start, x + SLOPE.min(end - x), level, end, level).as_str());
+                prev_y = Some(level);
+            }
+            WaveState::Clock(positive) => {
+                let (first, second) = match positive { true => (top, bottom), false => (bottom, top) };
+                let start = prev_y.unwrap_or(second);
+                svg.push_str(format!("<path d=\"M{},{} L{},{} L{},{} L{},{} L{},{}\" fill=\"none\" stroke=\"currentColor\"/>\n",
+                    x, start, x, first, x + width / 2.0, first, x + width / 2.0, second, end, second).as_str());
+                prev_y = Some(second);
+            }
+            WaveState::Z => {
+                let start = prev_y.unwrap_or(mid);
+                svg.push_str(format!("<path d=\"M{},{} L{},{} L{},{}\" fill=\"none\" stroke=\"#0041c4\"/>\n",
+                    x, start, x + SLOPE, mid, end, mid).as_str());
+                prev_y = None;
+            }
+            WaveState::X | WaveState::Data(_, _) => {
+                let fill = match state {
+                    WaveState::Data(color, _) => DATA_COLORS[*color % DATA_COLORS.len()].to_string(),
+                    _ => String::from("url(#wavedrom-hatch)"),
+                };
+                svg.push_str(format!("<path d=\"M{},{} L{},{} L{},{} L{},{} L{},{} L{},{} Z\" fill=\"{}\" stroke=\"currentColor\"/>\n",
+                    x, mid, x + SLOPE, top, end - SLOPE, top, end, mid, end - SLOPE, bottom, x + SLOPE, bottom, fill).as_str());
+                if let WaveState::Data(_, label) = state {
+                    svg.push_str(format!("<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" fill=\"#000\">{}</text>\n",
+                        (x + end) / 2.0, mid + 4.0, xml_escape(label)).as_str());
+                }
+                prev_y = None;
+            }
+        }
+
+        i = j;
+    }
+
+    for (i, period) in states.iter().enumerate() {
+        if period.gap {
+            let x = x0 + (i as f64 + 0.5) * width;
+            svg.push_str(format!("<path d=\"M{},{} L{},{}\" stroke=\"#fff\" stroke-width=\"4\"/>\n",
+                x - 2.0, bottom + 3.0, x + 2.0, top - 3.0).as_str());
+            svg.push_str(format!("<path d=\"M{},{} L{},{} M{},{} L{},{}\" stroke=\"currentColor\"/>\n",
+                x - 4.0, bottom + 3.0, x, top - 3.0, x, bottom + 3.0, x + 4.0, top - 3.0).as_str());
+        }
+    }
+}
+
+/// SVG of timing diagram.
+///
+fn signal_svg(json: &Value, signal: &Value) -> Result<String,String> {
+    let lanes = match signal {
+        Value::Array(lanes) => lanes,
+        _ => return Err(String::from("'signal' must be an array")),
+    };
+
+    let hscale = get_f64(json.get("config"), "hscale").unwrap_or(1.0).max(0.1);
+    let period_width = PERIOD_WIDTH * hscale;
+
+    let mut rows: Vec<Row> = Vec::new();
+    let mut groups: Vec<Group> = Vec::new();
+    flatten_lanes(lanes, 0, &mut rows, &mut groups);
+
+    let max_depth = rows.iter().map(|r| r.depth).max().unwrap_or(0);
+    let groups_width = max_depth as f64 * GROUP_WIDTH;
+    let name_width = rows.iter()
+        .filter_map(|r| r.lane.and_then(|l| l.get("name")).and_then(|n| n.as_str()))
+        .map(|n| n.chars().count() as f64 * CHAR_WIDTH)
+        .fold(0.0, f64::max);
+
+    let waves_x = MARGIN + groups_width + name_width + MARGIN;
+
+    // Lane waves in periods, `period` stretches and `phase` shifts the lane.
+    let mut lane_waves: Vec<Option<(Vec<Period>, f64, f64)>> = Vec::new();
+    let mut periods: f64 = 0.0;
+    for row in &rows {
+        let lane = row.lane.map(|l| Value::Object(l.clone()));
+        let wave = get_str(lane.as_ref(), "wave");
+        match wave {
+            Some(wave) => {
+                let data: Vec<String> = match lane.as_ref().and_then(|l| l.get("data")) {
+                    Some(Value::Array(items)) => items.iter()
+                        .map(|i| match i {
+                            Value::String(s) => s.clone(),
+                            other => other.to_string(),
+                        })
+                        .collect(),
+                    Some(Value::String(s)) => s.split_whitespace().map(|s| s.to_string()).collect(),
+                    _ => Vec::new(),
+                };
+                let period = get_f64(lane.as_ref(), "period").unwrap_or(1.0).max(0.1);
+                let phase = get_f64(lane.as_ref(), "phase").unwrap_or(0.0);
+                let states = wave_states(&wave, &data);
+                periods = periods.max(states.len() as f64 * period - phase);
+                lane_waves.push(Some((states, period, phase)));
+            }
+            None => lane_waves.push(None),
+        }
+    }
+
+    let head = get_str(json.get("head"), "text");
+    let foot = get_str(json.get("foot"), "text");
+    let tick = get_f64(json.get("head"), "tick");
+
+    let head_height = match (&head, tick) {
+        (None, None) => 0.0,
+        (Some(_), Some(_)) => 40.0,
+        _ => 20.0,
+    };
+    let foot_height = match foot { Some(_) => 20.0, None => 0.0 };
+
+    let waves_width = (periods.ceil() * period_width).max(period_width);
+    let width = waves_x + waves_width + MARGIN;
+    let lanes_y = MARGIN + head_height;
+    let height = lanes_y + rows.len() as f64 * LANE_HEIGHT + foot_height + MARGIN;
+
+    let mut svg = String::new();
+
+    svg.push_str(format!("<svg xmlns=\"http://www.w3.org/2000/svg\" class=\"wavedrom\" \
+        width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" \
+        font-family=\"Helvetica, Arial, sans-serif\" font-size=\"11\">\n", width, height, width, height).as_str());
+    svg.push_str("<defs><pattern id=\"wavedrom-hatch\" width=\"4\" height=\"4\" patternUnits=\"userSpaceOnUse\">\
+        <rect width=\"4\" height=\"4\" fill=\"#fff\"/><path d=\"M0,4 L4,0\" stroke=\"#888\" stroke-width=\"0.7\"/>\
+        </pattern></defs>\n");
+
+    // Grid line every period.
+    let mut x = waves_x;
+    while x <= waves_x + waves_width + 0.5 {
+        svg.push_str(format!("<path d=\"M{},{} L{},{}\" stroke=\"#ccc\" stroke-dasharray=\"1,3\"/>\n",
+            x, lanes_y, x, lanes_y + rows.len() as f64 * LANE_HEIGHT).as_str());
+        x += period_width;
+    }
+
+    if let Some(head) = &head {
+        svg.push_str(format!("<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" font-weight=\"bold\" font-size=\"14\">{}</text>\n",
+            waves_x + waves_width / 2.0, MARGIN + 12.0, xml_escape(head)).as_str());
+    }
+    if let Some(tick) = tick {
+        let y = lanes_y - 4.0;
+        for i in 0..=(periods.ceil() as usize) {
+            svg.push_str(format!("<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" fill=\"#888\">{}</text>\n",
+                waves_x + i as f64 * period_width, y, tick as i64 + i as i64).as_str());
+        }
+    }
+
+    for (n, (row, wave)) in rows.iter().zip(&lane_waves).enumerate() {
+        let y = lanes_y + n as f64 * LANE_HEIGHT + (LANE_HEIGHT - WAVE_HEIGHT) / 2.0;
+        if let Some(name) = row.lane.and_then(|l| l.get("name")).and_then(|n| n.as_str()) {
+            svg.push_str(format!("<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>\n",
+                waves_x - MARGIN, y + WAVE_HEIGHT / 2.0 + 4.0, xml_escape(name)).as_str());
+        }
+        if let Some((states, period, phase)) = wave {
+            draw_wave(&mut svg, states, waves_x - phase * period_width, y, period * period_width);
+        }
+    }
+
+    for group in &groups {
+        let x = MARGIN + group.depth as f64 * GROUP_WIDTH + GROUP_WIDTH / 2.0;
+        let y1 = lanes_y + group.first as f64 * LANE_HEIGHT + 4.0;
+        let y2 = lanes_y + (group.last + 1) as f64 * LANE_HEIGHT - 4.0;
+        svg.push_str(format!("<path d=\"M{},{} L{},{} L{},{} L{},{}\" fill=\"none\" stroke=\"#0041c4\"/>\n",
+            x + 4.0, y1, x, y1, x, y2, x + 4.0, y2).as_str());
+        if !group.label.is_empty() {
+            let y = (y1 + y2) / 2.0;
+            svg.push_str(format!("<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" transform=\"rotate(-90 {} {})\" fill=\"#0041c4\">{}</text>\n",
+                x - 3.0, y, x - 3.0, y, xml_escape(&group.label)).as_str());
+        }
+    }
+
+    if let Some(foot) = &foot {
+        svg.push_str(format!("<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+            waves_x + waves_width / 2.0, height - MARGIN - 4.0, xml_escape(foot)).as_str());
+    }
+
+    svg.push_str("</svg>\n");
+
+    Ok(svg)
+}
+
+/// Register field placed at bit `lsb`.
+struct RegField {
+    name: Option<String>,
+    attr: Option<String>,
+    field_type: Option<usize>,
+    lsb: usize,
+    bits: usize,
+}
+
+/// SVG of register diagram, bit 0 is on the right of the first lane.
+///
+fn reg_svg(json: &Value, reg: &Value) -> Result<String,String> {
+    let fields = match reg {
+        Value::Array(fields) => fields,
+        _ => return Err(String::from("'reg' must be an array")),
+    };
+
+    let config = json.get("config");
+
+    let mut layout: Vec<RegField> = Vec::new();
+    let mut total = 0;
+    for field in fields {
+        let bits = get_f64(Some(field), "bits").unwrap_or(1.0).max(1.0) as usize;
+        layout.push(RegField {
+            name: get_str(Some(field), "name"),
+            attr: get_str(Some(field), "attr"),
+            field_type: get_f64(Some(field), "type").map(|t| t as usize),
+            lsb: total,
+            bits,
+        });
+        total += bits;
+    }
+
+    let total = get_f64(config, "bits").map(|b| b as usize).unwrap_or(total).max(total).max(1);
+    let lanes = (get_f64(config, "lanes").unwrap_or(1.0).max(1.0) as usize).min(total);
+    let lane_bits = total.div_ceil(lanes);
+    let hspace = get_f64(config, "hspace").unwrap_or(640.0).max(100.0);
+    // Whole pixels per bit keep coordinates short.
+    let bit_width = (hspace / lane_bits as f64).floor().max(1.0);
+    let hspace = bit_width * lane_bits as f64;
+
+    let box_height = 30.0;
+    let lane_height = 14.0 + box_height + 18.0;
+
+    let width = hspace + 2.0 * MARGIN;
+    let height = lanes as f64 * lane_height + 2.0 * MARGIN;
+
+    let mut svg = String::new();
+
+    svg.push_str(format!("<svg xmlns=\"http://www.w3.org/2000/svg\" class=\"wavedrom\" \
+        width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" \
+        font-family=\"Helvetica, Arial, sans-serif\" font-size=\"12\">\n", width, height, width, height).as_str());
+
+    for lane in 0..lanes {
+        let lane_lsb = lane * lane_bits;
+        let lane_msb = (lane_lsb + lane_bits).min(total) - 1;
+        let box_y = MARGIN + lane as f64 * lane_height + 14.0;
+        // Position of left edge of bit.
+        let bit_x = |bit: usize| MARGIN + (lane_msb - bit) as f64 * bit_width;
+
+        svg.push_str(format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#eee\" stroke=\"currentColor\"/>\n",
+            bit_x(lane_msb), box_y, (lane_msb - lane_lsb + 1) as f64 * bit_width, box_height).as_str());
+
+        for RegField { name, attr, field_type, lsb, bits } in &layout {
+            let msb = lsb + bits - 1;
+            if msb < lane_lsb || *lsb > lane_msb {
+                continue;
+            }
+            let lsb = (*lsb).max(lane_lsb);
+            let msb = msb.min(lane_msb);
+            let x = bit_x(msb);
+            let w = (msb - lsb + 1) as f64 * bit_width;
+            let fill = match (name, field_type) {
+                (_, Some(t)) => TYPE_COLORS[*t % TYPE_COLORS.len()],
+                (Some(_), None) => "#fff",
+                (None, None) => "#eee",
+            };
+            svg.push_str(format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"currentColor\"/>\n",
+                x, box_y, w, box_height, fill).as_str());
+
+            // Bit ticks inside the field.
+            for bit in lsb..msb {
+                let tx = bit_x(bit);
+                svg.push_str(format!("<path d=\"M{},{} L{},{} M{},{} L{},{}\" stroke=\"currentColor\"/>\n",
+                    tx, box_y, tx, box_y + 4.0, tx, box_y + box_height - 4.0, tx, box_y + box_height).as_str());
+            }
+
+            svg.push_str(format!("<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" font-size=\"10\">{}</text>\n",
+                x + w - bit_width / 2.0, box_y - 3.0, lsb).as_str());
+            if msb != lsb {
+                svg.push_str(format!("<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" font-size=\"10\">{}</text>\n",
+                    x + bit_width / 2.0, box_y - 3.0, msb).as_str());
+            }
+            if let Some(name) = name {
+                svg.push_str(format!("<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+                    x + w / 2.0, box_y + box_height / 2.0 + 4.0, xml_escape(name)).as_str());
+            }
+            if let Some(attr) = attr.as_ref().filter(|a| !a.is_empty()) {
+                svg.push_str(format!("<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" font-size=\"11\">{}</text>\n",
+                    x + w / 2.0, box_y + box_height + 13.0, xml_escape(attr)).as_str());
+            }
+        }
+    }
+
+    svg.push_str("</svg>\n");
+
+    Ok(svg)
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn json5() {
+        let source = "{
+            // line comment
+            signal: [ /* block
+                comment */
+                { name: 'clk', wave: \"p...\", },
+            ],
+            'head': {text: 'it\\'s', tick: -1.5e1,},
+        }";
+
+        assert_eq!(parse_json5(source), Ok(json!({
+            "signal": [{ "name": "clk", "wave": "p..." }],
+            "head": { "text": "it's", "tick": -15.0 },
+        })));
+        assert_eq!(parse_json5("[0x1F, .5, +2]"), Ok(json!([31, 0.5, 2])));
+        assert!(parse_json5("{ a: 1 } }").is_err());
+        assert!(parse_json5("{ a: 'open }").is_err());
+        assert!(parse_json5("{ a: 1 /* open").is_err());
+    }
+
+    #[test]
+    fn wave_periods() {
+        let data = [String::from("A"), String::from("B")];
+        let states = wave_states(".x=.|2", &data);
+
+        assert_eq!(states.len(), 6);
+        assert!(matches!(states[0].state, WaveState::X));
+        assert!(matches!(states[1].state, WaveState::X));
+        assert!(matches!(&states[2].state, WaveState::Data(0, label) if label == "A"));
+        assert!(matches!(&states[3].state, WaveState::Data(0, label) if label.is_empty()));
+        assert!(states[3].continued && !states[3].gap);
+        assert!(matches!(&states[4].state, WaveState::Data(0, label) if label.is_empty()));
+        assert!(states[4].continued && states[4].gap);
+        assert!(matches!(&states[5].state, WaveState::Data(0, label) if label == "B"));
+        assert!(!states[5].continued);
+    }
+
+    #[test]
+    fn signal_groups() {
+        let source = "{ signal: [
+            { name: 'clk', wave: 'p...' },
+            ['Bus',
+                { name: 'addr', wave: 'x=.x', data: ['A0'] },
+                ['Data', { name: 'data', wave: 'x=x=', data: 'D0 D1' }],
+            ],
+            {},
+        ]}";
+        let svg = to_svg(source).unwrap().unwrap();
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        for text in [">clk</text>", ">addr</text>", ">data</text>", ">Bus</text>", ">Data</text>",
+            ">A0</text>", ">D0</text>", ">D1</text>"] {
+            assert!(svg.contains(text), "{} missing", text);
+        }
+        assert!(svg.contains("url(#wavedrom-hatch)"));
+
+        let json = parse_json5(source).unwrap();
+        let mut rows = Vec::new();
+        let mut groups = Vec::new();
+        flatten_lanes(json["signal"].as_array().unwrap(), 0, &mut rows, &mut groups);
+
+        assert_eq!(rows.iter().map(|r| r.depth).collect::<Vec<_>>(), [0, 1, 2, 0]);
+        assert_eq!(groups.iter().map(|g| (g.label.as_str(), g.depth, g.first, g.last)).collect::<Vec<_>>(),
+            [("Data", 1, 2, 2), ("Bus", 0, 1, 2)]);
+    }
+
+    #[test]
+    fn other_diagrams() {
+        assert!(to_svg("{ reg: [{ bits: 8, name: 'ctrl' }, { bits: 8 }] }").unwrap().unwrap().contains(">ctrl</text>"));
+        assert_eq!(to_svg("{ assign: [['out', ['|', 'a', 'b']]] }"), Ok(None));
+        assert!(to_svg("{ signal: 1 }").is_err());
+        assert!(to_svg("not json").is_err());
+    }
+}
@@ -0,0 +1,326 @@
+//! Resolve references to SV entities into pages where they are documented.
+//!
+//! Unscoped type names are looked up like SV does: in the own package,
+//! then in explicitly imported items and then in wildcard imported packages.
+//! Types which are not visible there are left unlinked, even if some package
+//! defines them. Names which can't be resolved are reported as warnings once per scope.
+//!
+
+use std::cell::RefCell;
+use crate::mdbook::design::{Design, Package, Scope};
+use crate::mdbook::markdown;
+
+/// Resolver of references made from one scope.
+///
+pub struct Resolver<'a> {
+    pub design: &'a Design,
+    /// Name of the scope for warnings, like `module Top`.
+    context: String,
+    /// Package whose declarations are visible unscoped.
+    package: Option<&'a str>,
+    /// Import items, like `P::*` or `P::name`.
+    imports: Vec<String>,
+    /// Parameters of the scope, type parameters shadow user types.
+    locals: Vec<String>,
+    /// Already reported names.
+    unresolved: RefCell<Vec<String>>,
+}
+
+impl<'a> Resolver<'a> {
+
+    pub fn new(design: &'a Design, scope: &'a Scope) -> Self {
+        let mut resolver = Resolver {
+            design,
+            context: String::new(),
+            package: None,
+            imports: Vec::new(),
+            locals: Vec::new(),
+            unresolved: RefCell::new(Vec::new()),
+        };
+
+        match scope {
+            Scope::CompilationUnit => {
+                resolver.context = String::from("compilation unit");
+            }
+            Scope::Package(name) => {
+                resolver.context = format!("package {}", name);
+                resolver.package = Some(name);
+                if let Some(pkg) = design.packages.iter().find(|p| &p.name == name) {
+                    resolver.imports = pkg.imports.clone();
+                    resolver.locals = pkg.parameters.iter().map(|p| p.name.clone()).collect();
+                }
+            }
+            Scope::Module(name) => {
+                resolver.context = format!("module {}", name);
+                if let Some(module) = design.modules.iter().find(|m| &m.name == name) {
+                    resolver.imports = module.imports.clone();
+                    resolver.locals = module.parameters.iter().map(|p| p.name.clone()).collect();
+                }
+            }
+            Scope::Interface(name) => {
+                resolver.context = format!("interface {}", name);
+                if let Some(iface) = design.interfaces.iter().find(|i| &i.name == name) {
+                    resolver.imports = iface.imports.clone();
+                    resolver.locals = iface.parameters.iter().map(|p| p.name.clone()).collect();
+                }
+            }
+            Scope::Class(name) => {
+                resolver.context = format!("class {}", name);
+                if let Some(class) = design.classes.iter().find(|c| &c.name == name) {
+                    resolver.imports = class.imports.clone();
+                    resolver.locals = class.parameters.iter().map(|p| p.name.clone()).collect();
+                }
+            }
+        }
+
+        resolver
+    }
+
+    /// Page of user type, `MyPkg::state_t` or unscoped `state_t`,
+    /// defined by `typedef` in a package or as a class.
+    ///
+    pub fn type_page(&self, type_name: &str) -> Option<String> {
+        if self.locals.iter().any(|l| l == type_name) {
+            return None;
+        }
+
+        let page = match type_name.rsplit_once("::") {
+            Some((scope, name)) => self.find_package(scope)
+                .filter(|pkg| pkg.typedefs.iter().any(|t| t.name == name))
+                .map(|pkg| markdown::page_path(&pkg.location.file, "pkg", &pkg.name))
+                .or_else(|| markdown::class_page(self.design, name)),
+            None => self.visible_typedef_package(type_name)
+                .map(|pkg| markdown::page_path(&pkg.location.file, "pkg", &pkg.name))
+                .or_else(|| markdown::class_page(self.design, type_name)),
+        };
+
+        if page.is_none() {
+            self.warn("type", type_name);
+        }
+
+        page
+    }
+
+    /// Page of type which starts the type text, like `state_t` in `state_t [3:0]`.
+    ///
+    pub fn type_str_page(&self, type_str: &str) -> Option<String> {
+        let type_name = type_str
+            .split(|c: char| c.is_whitespace() || c == '[' || c == '#')
+            .next()
+            .unwrap_or("");
+
+        let is_user_type = type_name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && !is_builtin_type(type_name);
+
+        match is_user_type {
+            true => self.type_page(type_name),
+            false => None,
+        }
+    }
+
+    /// Page of module or interface, like in instantiation.
+    ///
+    pub fn module_page(&self, name: &str) -> Option<String> {
+        let page = self.design.modules.iter()
+            .find(|m| m.name == name)
+            .map(|m| markdown::page_path(&m.location.file, "module", &m.name))
+            .or_else(|| markdown::iface_page(self.design, name));
+
+        if page.is_none() {
+            self.warn("module", name);
+        }
+
+        page
+    }
+
+    /// Page of interface, like in interface port.
+    ///
+    pub fn iface_page(&self, name: &str) -> Option<String> {
+        let page = markdown::iface_page(self.design, name);
+
+        if page.is_none() {
+            self.warn("interface", name);
+        }
+
+        page
+    }
+
+    /// Page of package, like in `import P::*`.
+    ///
+    pub fn package_page(&self, name: &str) -> Option<String> {
+        let page = self.find_package(name)
+            .map(|pkg| markdown::page_path(&pkg.location.file, "pkg", &pkg.name));
+
+        if page.is_none() {
+            self.warn("package", name);
+        }
+
+        page
+    }
+
+    /// Page of class, like in `extends`.
+    ///
+    pub fn class_page(&self, name: &str) -> Option<String> {
+        let name = name.rsplit_once("::").map_or(name, |(_, n)| n);
+        let page = markdown::class_page(self.design, name);
+
+        if page.is_none() {
+            self.warn("class", name);
+        }
+
+        page
+    }
+
+    fn find_package(&self, name: &str) -> Option<&'a Package> {
+        self.design.packages.iter().find(|pkg| pkg.name == name)
+    }
+
+    fn visible_typedef_package(&self, type_name: &str) -> Option<&'a Package> {
+        let defines = |pkg: &&Package| pkg.typedefs.iter().any(|t| t.name == type_name);
+
+        // Own package.
+        if let Some(pkg) = self.package.and_then(|name| self.find_package(name)).filter(defines) {
+            return Some(pkg);
+        }
+
+        // Explicit import `P::name`.
+        for item in &self.imports {
+            if let Some((pkg_name, name)) = item.split_once("::") {
+                if name == type_name {
+                    return self.find_package(pkg_name).filter(defines);
+                }
+            }
+        }
+
+        // Wildcard import `P::*`, the name must be defined in one of them.
+        let wildcard: Vec<&Package> = self.imports.iter()
+            .filter_map(|item| item.strip_suffix("::*"))
+            .filter_map(|pkg_name| self.find_package(pkg_name))
+            .filter(defines)
+            .collect();
+        match wildcard.as_slice() {
+            [pkg] => Some(pkg),
+            [] => None,
+            _ => {
+                println!("Warning: type '{}' in {} is ambiguous, it is imported from several packages",
+                    type_name, self.context);
+                None
+            }
+        }
+    }
+
+    fn warn(&self, kind: &str, name: &str) {
+        let mut unresolved = self.unresolved.borrow_mut();
+        if !unresolved.iter().any(|n| n == name) {
+            println!("Warning: unresolved reference to {} '{}' in {}", kind, name, self.context);
+            unresolved.push(name.to_string());
+        }
+    }
+}
+
+/// Built-in type keywords, which may start type text.
+///
+fn is_builtin_type(name: &str) -> bool {
+    matches!(name,
+        "bit" | "logic" | "reg" | "byte" | "shortint" | "int" | "longint" | "integer" | "time" |
+        "real" | "shortreal" | "realtime" | "string" | "chandle" | "event" | "void" |
+        "signed" | "unsigned" | "wire" | "tri" | "wand" | "wor" | "tri0" | "tri1" | "triand" |
+        "trior" | "trireg" | "supply0" | "supply1" | "uwire" | "var" | "const" | "static" |
+        "automatic" | "enum" | "struct" | "union" | "packed" | "virtual" | "interface" | "type")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mdbook::design::parse_str;
+
+    const SOURCE: &str = "
+        package A;
+            typedef logic [3:0] nibble_t;
+            typedef logic [7:0] byte_t;
+        endpackage
+        package B;
+            typedef logic [7:0] byte_t;
+            typedef logic [15:0] word_t;
+            class Packet; endclass
+        endpackage
+        package C;
+            import A::nibble_t;
+            typedef nibble_t [1:0] pair_t;
+        endpackage
+        module ScopedUser; endmodule
+        module ExplicitUser; import A::byte_t; endmodule
+        module WildcardUser; import B::*; endmodule
+        module AmbiguousUser; import A::*; import B::*; endmodule
+        module ShadowedUser #(parameter type word_t = int); import B::*; endmodule";
+
+    fn resolve(design: &Design, module: &str, type_name: &str) -> Option<String> {
+        Resolver::new(design, &Scope::Module(module.to_string())).type_page(type_name)
+    }
+
+    const PAGE_A: Option<&str> = Some("src/test.pkg.A.md");
+    const PAGE_B: Option<&str> = Some("src/test.pkg.B.md");
+
+    #[test]
+    fn scoped_names() {
+        let design = parse_str(SOURCE);
+
+        assert_eq!(resolve(&design, "ScopedUser", "A::byte_t").as_deref(), PAGE_A);
+        assert_eq!(resolve(&design, "ScopedUser", "B::byte_t").as_deref(), PAGE_B);
+        assert_eq!(resolve(&design, "ScopedUser", "A::word_t"), None);
+        assert_eq!(resolve(&design, "ScopedUser", "B::Packet").as_deref(),
+            Some("src/test.class.Packet.md"));
+
+        // Not imported, so not visible unscoped.
+        assert_eq!(resolve(&design, "ScopedUser", "byte_t"), None);
+    }
+
+    #[test]
+    fn explicit_import() {
+        let design = parse_str(SOURCE);
+
+        assert_eq!(resolve(&design, "ExplicitUser", "byte_t").as_deref(), PAGE_A);
+        assert_eq!(resolve(&design, "ExplicitUser", "nibble_t"), None);
+        assert_eq!(resolve(&design, "ExplicitUser", "word_t"), None);
+
+        // Own package and explicit import of package scope.
+        let scope = Scope::Package("C".to_string());
+        let resolver = Resolver::new(&design, &scope);
+        assert_eq!(resolver.type_page("pair_t").as_deref(), Some("src/test.pkg.C.md"));
+        assert_eq!(resolver.type_page("nibble_t").as_deref(), PAGE_A);
+    }
+
+    #[test]
+    fn wildcard_import() {
+        let design = parse_str(SOURCE);
+
+        assert_eq!(resolve(&design, "WildcardUser", "byte_t").as_deref(), PAGE_B);
+        assert_eq!(resolve(&design, "WildcardUser", "word_t").as_deref(), PAGE_B);
+        assert_eq!(resolve(&design, "WildcardUser", "nibble_t"), None);
+        let scope = Scope::Module("WildcardUser".to_string());
+        assert_eq!(Resolver::new(&design, &scope).type_str_page("word_t [3:0]").as_deref(), PAGE_B);
+    }
+
+    #[test]
+    fn ambiguous_wildcard_imports() {
+        let design = parse_str(SOURCE);
+
+        // Both packages export `byte_t`, the other names are unique.
+        assert_eq!(resolve(&design, "AmbiguousUser", "byte_t"), None);
+        assert_eq!(resolve(&design, "AmbiguousUser", "nibble_t").as_deref(), PAGE_A);
+        assert_eq!(resolve(&design, "AmbiguousUser", "word_t").as_deref(), PAGE_B);
+        assert_eq!(resolve(&design, "AmbiguousUser", "B::byte_t").as_deref(), PAGE_B);
+    }
+
+    #[test]
+    fn type_parameters_and_builtins() {
+        let design = parse_str(SOURCE);
+        let scope = Scope::Module("ShadowedUser".to_string());
+        let resolver = Resolver::new(&design, &scope);
+
+        assert_eq!(resolver.type_page("word_t"), None);
+        assert_eq!(resolver.type_page("byte_t").as_deref(), PAGE_B);
+        assert_eq!(resolver.type_str_page("logic [7:0]"), None);
+        assert_eq!(resolver.type_str_page("int unsigned"), None);
+    }
+}
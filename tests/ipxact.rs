@@ -0,0 +1,314 @@
+//! Round-trip of sample designs through IP-XACT components:
+//! ports and parameters read back from generated XML
+//! must be the same as in the design model,
+//! and elements must be in the order of the 2014 schema.
+//!
+
+use svdocgen::args::ParsedOptions;
+use svdocgen::mdbook::design::{Design, Direction, ParamKind};
+use svdocgen::mdbook::ipxact::{self, Vlnv};
+use svdocgen::mdbook::render::Context;
+
+/// Child elements of `ipxact:component` in the order of the 2014 schema.
+///
+const COMPONENT_SEQUENCE: &[&str] = &[
+    "vendor", "library", "name", "version",
+    "busInterfaces", "indirectInterfaces", "channels", "remapStates",
+    "addressSpaces", "memoryMaps", "model", "componentGenerators", "choices",
+    "fileSets", "whiteboxElements", "cpus", "otherClockDrivers", "resetTypes",
+    "description", "parameters", "assertions", "vendorExtensions",
+];
+
+/// Child elements of `ipxact:port` in the order of the 2014 schema.
+///
+const PORT_SEQUENCE: &[&str] = &[
+    "name", "displayName", "description", "wire", "transactional", "arrays", "access", "vendorExtensions",
+];
+
+/// Vectors and arrays of sample ports with dimensions as (left, right) bounds,
+/// other ports have none.
+///
+type PortBounds = (&'static str, &'static str, &'static [(&'static str, &'static str)], &'static [(&'static str, &'static str)]);
+
+const SAMPLE1_BOUNDS: &[PortBounds] = &[
+    ("Dff", "in", &[("WIDTH-1", "0")], &[]),
+    ("Dff", "out", &[("WIDTH-1", "0")], &[]),
+    ("Mux2", "in1", &[("WIDTH-1", "0")], &[]),
+    ("Mux2", "in2", &[("WIDTH-1", "0")], &[]),
+    ("Mux2", "out", &[("WIDTH-1", "0")], &[]),
+    ("And3", "in1", &[("WIDTH-1", "0")], &[]),
+    ("And3", "in2", &[("WIDTH-1", "0")], &[]),
+    ("And3", "in3", &[("WIDTH-1", "0")], &[]),
+    ("And3", "out", &[("WIDTH-1", "0")], &[]),
+    ("RippleCarryAdder", "in1", &[("WIDTH-1", "0")], &[]),
+    ("RippleCarryAdder", "in2", &[("WIDTH-1", "0")], &[]),
+    ("RippleCarryAdder", "sum", &[("WIDTH-1", "0")], &[]),
+];
+
+const JTAG_CORE_DBG_BOUNDS: &[PortBounds] = &[
+    ("CoreDbgApb", "addr", &[("APB_ADDR_WIDTH-1", "0")], &[]),
+    ("CoreDbgApb", "wdata", &[("APB_WDATA_WIDTH-1", "0")], &[]),
+    ("CoreDbgApb", "wstrobe", &[("3", "0")], &[]),
+    ("CoreDbgApb", "rdata", &[("APB_RDATA_WIDTH-1", "0")], &[]),
+    ("CoreDbgApb", "core_dbg_addr", &[("APB_ADDR_WIDTH-1", "0")], &[]),
+    ("CoreDbgApb", "core_dbg_wdata", &[("APB_WDATA_WIDTH-1", "0")], &[]),
+    ("CoreDbgApb", "core_dbg_rdata", &[("APB_RDATA_WIDTH-1", "0")], &[]),
+    ("DbgAccPort", "memi_addr", &[("MEMI_ADDR_WIDTH-1", "0")], &[]),
+    ("DbgAccPort", "memi_sel", &[("MEMI_NR_SLAVES-1", "0")], &[]),
+    ("DbgAccPort", "memi_wdata", &[("MEMI_WDATA_WIDTH-1", "0")], &[]),
+    ("DbgAccPort", "memi_rdata", &[("MEMI_RDATA_WIDTH-1", "0")], &[]),
+    ("JtagTapFsm", "state", &[("WIDTH-1", "0")], &[]),
+    ("DbgApbBus", "addr", &[("ADDR_WIDTH-1", "0")], &[]),
+    ("DbgApbBus", "sel", &[("NR_SLAVES-1", "0")], &[]),
+    ("DbgApbBus", "wdata", &[("WDATA_WIDTH-1", "0")], &[]),
+    ("DbgApbBus", "wstrobe", &[("3", "0")], &[]),
+    ("DbgApbBus", "rdata", &[("RDATA_WIDTH-1", "0")], &[]),
+    ("DbgApbBus", "s2m_ready", &[("NR_SLAVES-1", "0")], &[]),
+    ("DbgApbBus", "s2m_data", &[("RDATA_WIDTH-1", "0")], &[("0", "NR_SLAVES-1")]),
+    ("JtagTapDRegs", "latched_jtag_ir", &[("INSN_WIDTH-1", "0")], &[]),
+    ("JtagTapInsnReg", "latched_jtag_ir", &[("WIDTH-1", "0")], &[]),
+    ("CoreDbgPort", "memi_addr", &[("MEMI_ADDR_WIDTH-1", "0")], &[]),
+    ("CoreDbgPort", "memi_sel", &[("MEMI_NR_SLAVES-1", "0")], &[]),
+    ("CoreDbgPort", "memi_wdata", &[("MEMI_WDATA_WIDTH-1", "0")], &[]),
+    ("CoreDbgPort", "memi_rdata", &[("MEMI_RDATA_WIDTH-1", "0")], &[]),
+];
+
+fn test_vlnv() -> Vlnv {
+    Vlnv {
+        vendor: String::from("test"),
+        library: String::from("rtl"),
+        version: String::from("1.0"),
+    }
+}
+
+fn parse_sample(dir: &str) -> Design {
+    parse_input(&format!("samples/{}", dir))
+}
+
+fn parse_input(input: &str) -> Design {
+    let options = ParsedOptions {
+        output_dir: String::new(),
+        inputs: vec![input.to_string()],
+        filelists: Vec::new(),
+        includes: Vec::new(),
+        incdirs: Vec::new(),
+        defines: Vec::new(),
+        tops: Vec::new(),
+        collapse_buses: false,
+        format: String::from("ipxact"),
+        js_libs: String::from("cdn"),
+        project_name: String::new(),
+    };
+
+    Context::new(&options).unwrap().design
+}
+
+/// Check that tags are balanced and return text of all `<ipxact:NAME>` elements
+/// found directly inside elements `<ipxact:PARENT>`, one list per parent.
+///
+fn read_elements(xml: &str, parent: &str) -> Vec<Vec<(String, String)>> {
+    let mut stack: Vec<String> = Vec::new();
+    let mut items: Vec<Vec<(String, String)>> = Vec::new();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find('<') {
+        let end = rest[start..].find('>').unwrap() + start;
+        let tag = &rest[start + 1..end];
+        let text = &rest[end + 1..];
+        rest = text;
+
+        if tag.starts_with('?') || tag.ends_with('/') {
+            continue;
+        }
+        if let Some(name) = tag.strip_prefix('/') {
+            assert_eq!(stack.pop().as_deref(), Some(name), "unbalanced tag");
+            continue;
+        }
+
+        let name = tag.split_whitespace().next().unwrap().to_string();
+        if stack.last().map(|p| p.as_str()) == Some(format!("ipxact:{}", parent).as_str()) {
+            let value = &text[..text.find('<').unwrap()];
+            if !value.trim().is_empty() {
+                items.last_mut().unwrap().push((name.clone(), unescape(value)));
+            }
+        }
+        if name == format!("ipxact:{}", parent) {
+            items.push(Vec::new());
+        }
+        stack.push(name);
+    }
+
+    assert!(stack.is_empty(), "unclosed tags {:?}", stack);
+
+    items
+}
+
+/// Names of child elements of every `<ipxact:PARENT>` element, without namespace.
+///
+fn child_names(xml: &str, parent: &str) -> Vec<Vec<String>> {
+    let mut stack: Vec<String> = Vec::new();
+    let mut children: Vec<Vec<String>> = Vec::new();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find('<') {
+        let end = rest[start..].find('>').unwrap() + start;
+        let tag = &rest[start + 1..end];
+        rest = &rest[end + 1..];
+
+        if tag.starts_with('?') {
+            continue;
+        }
+        if tag.starts_with('/') {
+            stack.pop();
+            continue;
+        }
+
+        let name = tag.trim_end_matches('/').split_whitespace().next().unwrap().to_string();
+        if stack.last().map(|p| p.as_str()) == Some(format!("ipxact:{}", parent).as_str()) {
+            children.last_mut().unwrap().push(name.trim_start_matches("ipxact:").to_string());
+        }
+        if name == format!("ipxact:{}", parent) {
+            children.push(Vec::new());
+        }
+        if !tag.ends_with('/') {
+            stack.push(name);
+        }
+    }
+
+    children
+}
+
+/// Check that children of every `PARENT` element follow the schema sequence.
+///
+fn check_order(xml: &str, parent: &str, sequence: &[&str], context: &str) {
+    for children in child_names(xml, parent) {
+        let positions: Vec<usize> = children.iter()
+            .map(|child| sequence.iter().position(|s| s == child)
+                .unwrap_or_else(|| panic!("unexpected {} in {} of {}", child, parent, context)))
+            .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]),
+            "order of {} children of {}: {:?}", parent, context, children);
+    }
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+fn get<'a>(item: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    item.iter()
+        .find(|(n, _)| n == &format!("ipxact:{}", name))
+        .map(|(_, v)| v.as_str())
+}
+
+fn check_round_trip(design: &Design, bounds: &[PortBounds]) {
+    let vlnv = test_vlnv();
+
+    assert!(!design.modules.is_empty());
+
+    for module in &design.modules {
+        let xml = ipxact::component_xml(module, &vlnv);
+
+        check_order(&xml, "component", COMPONENT_SEQUENCE, &module.name);
+        check_order(&xml, "port", PORT_SEQUENCE, &module.name);
+
+        let ports: Vec<_> = module.ports.iter().filter(|p| p.port_type.interface.is_none()).collect();
+        let port_items = read_elements(&xml, "port");
+        let wire_items = read_elements(&xml, "wire");
+        let vector_items = read_elements(&xml, "vector");
+        let array_items = read_elements(&xml, "array");
+        assert_eq!(ports.len(), port_items.len(), "ports of {}", module.name);
+
+        let mut vectors = vector_items.iter();
+        let mut arrays = array_items.iter();
+        for ((port, item), wire) in ports.iter().zip(&port_items).zip(&wire_items) {
+            assert_eq!(get(item, "name"), Some(port.name.as_str()));
+            let direction = match port.direction {
+                Some(Direction::Output) => "out",
+                Some(Direction::Inout) | Some(Direction::Ref) => "inout",
+                _ => "in",
+            };
+            assert_eq!(get(wire, "direction"), Some(direction), "port {}.{}", module.name, port.name);
+
+            let (expected_vectors, expected_arrays) = bounds.iter()
+                .find(|b| b.0 == module.name && b.1 == port.name)
+                .map_or((&[][..], &[][..]), |b| (b.2, b.3));
+            for (left, right) in expected_vectors {
+                let vector = vectors.next().unwrap_or_else(|| panic!("vector of {}.{}", module.name, port.name));
+                assert_eq!((get(vector, "left"), get(vector, "right")), (Some(*left), Some(*right)),
+                    "vector of {}.{}", module.name, port.name);
+            }
+            for (left, right) in expected_arrays {
+                let array = arrays.next().unwrap_or_else(|| panic!("array of {}.{}", module.name, port.name));
+                assert_eq!((get(array, "left"), get(array, "right")), (Some(*left), Some(*right)),
+                    "array of {}.{}", module.name, port.name);
+            }
+        }
+        assert!(vectors.next().is_none(), "unexpected vectors in {}", module.name);
+        assert!(arrays.next().is_none(), "unexpected arrays in {}", module.name);
+
+        let params: Vec<_> = module.parameters.iter()
+            .filter(|p| matches!(p.kind, ParamKind::Parameter | ParamKind::TypeParameter))
+            .collect();
+        let param_items = read_elements(&xml, "parameter");
+        assert_eq!(params.len(), param_items.len(), "parameters of {}", module.name);
+        for (param, item) in params.iter().zip(&param_items) {
+            assert_eq!(get(item, "name"), Some(param.name.as_str()));
+            assert_eq!(get(item, "value").unwrap_or(""), param.default.as_deref().unwrap_or(""));
+        }
+
+        let bus_items = read_elements(&xml, "busInterface");
+        let bus_ports: Vec<_> = module.ports.iter().filter(|p| p.port_type.interface.is_some()).collect();
+        assert_eq!(bus_ports.len(), bus_items.len(), "bus interfaces of {}", module.name);
+    }
+}
+
+#[test]
+fn round_trip_sample1() {
+    check_round_trip(&parse_sample("sample1"), SAMPLE1_BOUNDS);
+}
+
+#[test]
+fn round_trip_jtag_core_dbg() {
+    check_round_trip(&parse_sample("JtagCoreDbg"), JTAG_CORE_DBG_BOUNDS);
+}
+
+#[test]
+fn bus_interface_modes() {
+    let dir = std::env::temp_dir().join(format!("svdocgen-ipxact-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("Bus.sv"), "
+        interface Bus;
+            logic req;
+            modport master(output req);
+            modport target(input req);
+            modport mp(input req);
+        endinterface
+        /* Bridge. */
+        module Bridge(Bus.master m, Bus.target s, Bus.mp watch, Bus plain, input logic q [0:3]);
+        endmodule
+        ").unwrap();
+
+    let design = parse_input(dir.to_str().unwrap());
+    let module = design.modules.iter().find(|m| m.name == "Bridge").unwrap();
+    let xml = ipxact::component_xml(module, &test_vlnv());
+
+    check_order(&xml, "component", COMPONENT_SEQUENCE, &module.name);
+    check_order(&xml, "port", PORT_SEQUENCE, &module.name);
+
+    let modes: Vec<Vec<String>> = child_names(&xml, "busInterface").into_iter()
+        .map(|children| children.into_iter()
+            .filter(|c| ["master", "slave", "monitor"].contains(&c.as_str()))
+            .collect())
+        .collect();
+    assert_eq!(modes, vec![vec!["master"], vec!["slave"], vec!["monitor"], vec!["monitor"]]);
+
+    let arrays = read_elements(&xml, "array");
+    assert_eq!(arrays.len(), 1);
+    assert_eq!((get(&arrays[0], "left"), get(&arrays[0], "right")), (Some("0"), Some("3")));
+
+    assert_eq!(child_names(&xml, "component")[0].iter().filter(|c| *c == "description").count(), 1);
+}